@@ -0,0 +1,298 @@
+//! Applying an edited task file.
+//!
+//! This module works out what `tasg apply` should do with a JSON file of tasks, typically the
+//! output of `tasg list --json` after editing it. Objects in the file are matched to existing
+//! tasks by ID and only the fields present in an object are changed, so a file exported with
+//! `--fields` applies cleanly. Planning is pure; the binary previews the plan with the diff engine
+//! and writes it in a single save.
+
+use serde_json::{Map, Value};
+
+use crate::error::TaskError;
+use crate::ids::next_id;
+use crate::task::Task;
+
+/// An edited task, as read from the file.
+pub type Edit = Map<String, Value>;
+
+/// What to do with tasks that only appear on one side.
+///
+/// # Fields
+///
+/// - `allow_new` - Whether to add tasks in the file that do not match an existing task.
+/// - `prune_missing` - Whether to move existing tasks that are missing from the file to the trash.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ApplyOptions {
+    /// Whether to add tasks in the file that do not match an existing task.
+    pub allow_new: bool,
+
+    /// Whether to move existing tasks that are missing from the file to the trash.
+    pub prune_missing: bool,
+}
+
+/// The result of planning an apply.
+///
+/// # Fields
+///
+/// - `tasks` - The tasks after applying the file; pruned tasks are included with `deleted_at` set.
+/// - `skipped_new` - The number of tasks in the file that were not added because `allow_new` was not set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    /// The tasks after applying the file; pruned tasks are included with `deleted_at` set.
+    pub tasks: Vec<Task>,
+
+    /// The number of tasks in the file that were not added because `allow_new` was not set.
+    pub skipped_new: usize,
+}
+
+impl Plan {
+    /// The tasks after applying the file, without the pruned ones.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Task>` - The tasks that are not in the trash, for comparing against the current tasks.
+    pub fn remaining(&self) -> Vec<Task> {
+        self.tasks.iter().filter(|t| !t.is_deleted()).cloned().collect()
+    }
+}
+
+/// Parses the contents of a file to apply.
+///
+/// # Arguments
+///
+/// * `data` - The contents of the file: a JSON array of task objects.
+///
+/// # Returns
+///
+/// * `Result<Vec<Edit>, TaskError>` - The objects, or `TaskError::InvalidInput` if the contents are not a JSON array of objects.
+pub fn parse(data: &str) -> Result<Vec<Edit>, TaskError> {
+    serde_json::from_str(data)
+        .map_err(|e| TaskError::InvalidInput(format!("Expected a JSON array of tasks: {}", e)))
+}
+
+/// Plans applying edited tasks to the current tasks.
+///
+/// Each object with the `id` of a current task overwrites that task's fields with the ones it
+/// contains; tasks that end up unchanged are left as they were, and changed tasks have their
+/// `updated_at` set to `now`, `description_updated_at` set if their description changed, and
+/// `completed_at` set or cleared if their status changed. Objects without
+/// a matching `id` are new tasks, which are given the next free ID unless theirs is free. Current
+/// tasks without a matching object are kept, or moved to the trash with `prune_missing`.
+///
+/// # Arguments
+///
+/// * `current` - The current tasks, excluding those in the trash.
+/// * `edits` - The objects read from the file.
+/// * `used_ids` - Every ID in use, including by tasks in the trash.
+/// * `options` - What to do with tasks that only appear on one side.
+/// * `now` - The current time.
+///
+/// # Returns
+///
+/// * `Result<Plan, TaskError>` - The plan, or `TaskError::InvalidInput` if an object has invalid fields, a new task has no description, or an ID appears twice.
+pub fn plan(
+    current: &[Task],
+    edits: &[Edit],
+    used_ids: &[u32],
+    options: ApplyOptions,
+    now: chrono::DateTime<chrono::Local>,
+) -> Result<Plan, TaskError> {
+    let mut tasks = current.to_vec();
+    let mut seen = Vec::new();
+    let mut new = Vec::new();
+
+    for edit in edits {
+        let id = match edit.get("id") {
+            None => None,
+            Some(id) => Some(
+                id.as_u64()
+                    .and_then(|id| u32::try_from(id).ok())
+                    .ok_or_else(|| TaskError::InvalidInput(format!("Invalid task ID {}", id)))?,
+            ),
+        };
+        match id.and_then(|id| tasks.iter_mut().find(|t| t.id == id)) {
+            Some(task) => {
+                if seen.contains(&task.id) {
+                    return Err(TaskError::InvalidInput(format!(
+                        "Task {} appears more than once",
+                        task.id
+                    )));
+                }
+                seen.push(task.id);
+                let mut updated = overlay(task, edit)?;
+                if updated.status != task.status && !edit.contains_key("completed_at") {
+                    let status = updated.status;
+                    updated.status = task.status;
+                    updated.set_status(status, now);
+                }
+                if updated.description != task.description
+                    && !edit.contains_key("description_updated_at")
+                {
+                    updated.description_updated_at = Some(now);
+                }
+                if updated != *task {
+                    *task = Task { updated_at: now, ..updated };
+                }
+            }
+            None => new.push((id, edit)),
+        }
+    }
+
+    if options.prune_missing {
+        for task in tasks.iter_mut().filter(|t| !seen.contains(&t.id)) {
+            task.deleted_at = Some(now);
+            task.updated_at = now;
+        }
+    }
+
+    let skipped_new = if options.allow_new { 0 } else { new.len() };
+    if options.allow_new {
+        let mut used: Vec<u32> = used_ids.to_vec();
+        for (id, edit) in new {
+            let id = match id {
+                Some(id) if id > 0 && !used.contains(&id) => id,
+                _ => next_id(used.iter().copied())?,
+            };
+            used.push(id);
+            let task = overlay(&Task::new(id, String::new()), edit)?;
+            if task.description.trim().is_empty() {
+                return Err(TaskError::InvalidInput("New tasks must have a description".into()));
+            }
+            tasks.push(Task { id, created_at: now, updated_at: now, deleted_at: None, ..task });
+        }
+    }
+
+    Ok(Plan { tasks, skipped_new })
+}
+
+/// Overwrites a task's fields with the ones in an edited object; the ID is kept.
+fn overlay(task: &Task, edit: &Edit) -> Result<Task, TaskError> {
+    let Value::Object(mut fields) = serde_json::to_value(task)? else {
+        unreachable!("tasks serialize to objects");
+    };
+    if edit.contains_key("status") && !edit.contains_key("completed") {
+        // The current `completed` flag would otherwise override a new status; see `TaskRecord`.
+        fields.remove("completed");
+    }
+    fields.extend(edit.iter().filter(|(k, _)| *k != "id").map(|(k, v)| (k.clone(), v.clone())));
+    serde_json::from_value(Value::Object(fields))
+        .map_err(|e| TaskError::InvalidInput(format!("Invalid fields for task {}: {}", task.id, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Status;
+
+    /// Parses edited objects from JSON text.
+    fn edits(data: &str) -> Vec<Edit> {
+        parse(data).unwrap()
+    }
+
+    /// Builds the current tasks used by the tests.
+    fn current() -> Vec<Task> {
+        vec![Task::new(1, String::from("Task 1")), Task::new(2, String::from("Task 2"))]
+    }
+
+    /// Tests that only the fields present in an object are changed.
+    #[test]
+    fn test_plan_changes_present_fields() {
+        let now = chrono::Local::now();
+        let tasks = current();
+        let file = edits(r#"[{"id": 1, "completed": true}, {"id": 2, "description": "Task 2"}]"#);
+        let plan = plan(&tasks, &file, &[1, 2], ApplyOptions::default(), now).unwrap();
+
+        assert!(plan.tasks[0].is_completed());
+        assert_eq!(plan.tasks[0].completed_at, Some(now));
+        assert_eq!(plan.tasks[0].description, "Task 1");
+        assert_eq!(plan.tasks[0].updated_at, now);
+        assert_eq!(plan.tasks[1], tasks[1]);
+        assert_eq!(plan.skipped_new, 0);
+    }
+
+    /// Tests that a new status replaces the stored `completed` flag, whichever is edited.
+    #[test]
+    fn test_plan_status() {
+        let now = chrono::Local::now();
+        let mut tasks = current();
+        tasks[1].set_status(Status::Done, now);
+        let file = edits(r#"[{"id": 1, "status": "done"}, {"id": 2, "status": "blocked"}]"#);
+        let plan = plan(&tasks, &file, &[1, 2], ApplyOptions::default(), now).unwrap();
+
+        assert_eq!((plan.tasks[0].status, plan.tasks[0].completed_at), (Status::Done, Some(now)));
+        assert_eq!((plan.tasks[1].status, plan.tasks[1].completed_at), (Status::Blocked, None));
+    }
+
+    /// Tests that a changed description records when it changed.
+    #[test]
+    fn test_plan_description_updated_at() {
+        let now = chrono::Local::now();
+        let tasks = current();
+        let file = edits(r#"[{"id": 1, "description": "Task one"}, {"id": 2, "notes": "Soon"}]"#);
+        let plan = plan(&tasks, &file, &[1, 2], ApplyOptions::default(), now).unwrap();
+
+        assert_eq!(plan.tasks[0].description_updated_at, Some(now));
+        assert_eq!(plan.tasks[1].description_updated_at, None);
+    }
+
+    /// Tests that a file without changes leaves every task as it was.
+    #[test]
+    fn test_plan_no_changes() {
+        let tasks = current();
+        let file = edits(&serde_json::to_string(&tasks).unwrap());
+        let plan = plan(&tasks, &file, &[1, 2], ApplyOptions::default(), chrono::Local::now());
+        assert_eq!(plan.unwrap().tasks, tasks);
+    }
+
+    /// Tests that new tasks are only added with `allow_new`, avoiding used IDs.
+    #[test]
+    fn test_plan_allow_new() {
+        let now = chrono::Local::now();
+        let tasks = current();
+        let file =
+            edits(r#"[{"id": 3, "description": "Reuses a trashed ID"}, {"description": "No ID"}]"#);
+
+        let skipped = plan(&tasks, &file, &[1, 2, 3], ApplyOptions::default(), now).unwrap();
+        assert_eq!(skipped.tasks, tasks);
+        assert_eq!(skipped.skipped_new, 2);
+
+        let options = ApplyOptions { allow_new: true, ..ApplyOptions::default() };
+        let added = plan(&tasks, &file, &[1, 2, 3], options, now).unwrap();
+        let ids: Vec<(u32, &str)> =
+            added.tasks.iter().map(|t| (t.id, t.description.as_str())).collect();
+        assert_eq!(
+            ids,
+            vec![(1, "Task 1"), (2, "Task 2"), (4, "Reuses a trashed ID"), (5, "No ID")]
+        );
+
+        let missing = edits(r#"[{"completed": true}]"#);
+        assert!(plan(&current(), &missing, &[1, 2], options, now).is_err());
+    }
+
+    /// Tests that missing tasks are only moved to the trash with `prune_missing`.
+    #[test]
+    fn test_plan_prune_missing() {
+        let now = chrono::Local::now();
+        let tasks = current();
+        let file = edits(r#"[{"id": 2}]"#);
+
+        let kept = plan(&tasks, &file, &[1, 2], ApplyOptions::default(), now).unwrap();
+        assert_eq!(kept.remaining().len(), 2);
+
+        let options = ApplyOptions { prune_missing: true, ..ApplyOptions::default() };
+        let pruned = plan(&tasks, &file, &[1, 2], options, now).unwrap();
+        assert_eq!(pruned.tasks[0].deleted_at, Some(now));
+        assert_eq!(pruned.remaining(), vec![tasks[1].clone()]);
+    }
+
+    /// Tests that invalid files are rejected.
+    #[test]
+    fn test_plan_invalid() {
+        let now = chrono::Local::now();
+        assert!(parse(r#"{"id": 1}"#).is_err());
+        let wrong_type = edits(r#"[{"id": 1, "completed": "maybe"}]"#);
+        assert!(plan(&current(), &wrong_type, &[1, 2], ApplyOptions::default(), now).is_err());
+        let twice = edits(r#"[{"id": 1}, {"id": 1}]"#);
+        assert!(plan(&current(), &twice, &[1, 2], ApplyOptions::default(), now).is_err());
+    }
+}
@@ -1,5 +1,13 @@
 //! Command-line interface (CLI)
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
+use clap_complete::Shell;
+
+use crate::duration::parse_duration;
+use crate::ids::parse_id;
+use crate::render::Output;
+use crate::task::{ChangedField, Status};
 
 /// Command-line interface for the Tasg application.
 ///
@@ -9,8 +17,31 @@ use clap::{Parser, Subcommand};
 /// The CLI supports various operations such as adding tasks, listing tasks, completing tasks,
 /// deleting tasks, and editing tasks.
 #[derive(Parser, Debug)]
-#[command(name = "tasg", about, version, author)]
+#[command(name = "tasg", about, version, author, disable_help_subcommand = true)]
 pub struct Cli {
+    /// Refuse to modify the tasks.
+    ///
+    /// Every command that would write to the tasks fails instead, while read commands work
+    /// normally. Can also be enabled by setting the `TASG_READONLY` environment variable to `1`.
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Show what a command would do without saving any changes.
+    ///
+    /// The command runs as usual and prints its usual output, but the tasks file, the undo
+    /// history, and the record of when tasks were last listed are left untouched. Backups are
+    /// refused, and `completions install` only prints where it would write.
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// How `list`, `trash`, `show`, and `count` print their results: `standard` or `plain`.
+    ///
+    /// `plain` prints short sentences such as `Task 12. Buy milk. Created 3 days ago.` without
+    /// padding, symbols, or colors, which reads better with a screen reader. Defaults to the
+    /// `output` key of the config file, or `standard`.
+    #[arg(long, global = true)]
+    pub output: Option<Output>,
+
     /// The command to execute, specified as a subcommand.
     ///
     /// This field holds the parsed subcommand, which can be one of the variants in the `Commands` enum.
@@ -28,25 +59,69 @@ pub struct Cli {
 ///
 /// - `Add` - Adds a new task with the specified description.
 /// - `List` - Lists tasks, with an option to show all tasks, including completed ones.
-/// - `Complete` - Marks a task as complete by its ID.
-/// - `Delete` - Deletes a task by its ID.
+/// - `Show` - Shows every field of a single task by its ID.
+/// - `Count` - Counts the tasks, completed tasks, and pending tasks.
+/// - `Complete` - Marks tasks as complete by their IDs, or all pending tasks at once.
+/// - `Status` - Sets a task's status: to do, in progress, done, or blocked.
+/// - `Delete` - Moves tasks to the trash by their IDs, or deletes them permanently.
+/// - `Trash` - Lists the tasks in the trash.
+/// - `Restore` - Restores a task from the trash by its ID.
+/// - `EmptyTrash` - Permanently deletes the tasks in the trash.
 /// - `Edit` - Edits the description of an existing task by its ID.
+/// - `Get` - Prints a single named field of a task.
+/// - `Set` - Sets a named field of a task.
+/// - `Touch` - Bumps a task's update time without changing anything else.
+/// - `Diff` - Compares the tasks against another task file.
+/// - `Apply` - Applies the changes in an edited task file.
+/// - `Doctor` - Checks the tasks for problems, optionally fixing them.
+/// - `Tutorial` - Walks through the basic commands using a throwaway task list.
+/// - `Wait` - Blocks until a task is completed or deleted.
+/// - `Backup` - Copies the tasks to a timestamped backup file, keeping only the most recent backups.
+/// - `Carryover` - Copies open tasks from the previous period's task file into the current one.
+/// - `Do` - Carries out a whole instruction given as a single string, e.g. `"complete 12"`.
+/// - `Completions` - Prints or installs shell completion scripts.
+/// - `Help` - Prints the help for a command, or explains a help topic such as durations.
+/// - `Undo` - Reverts the last changes made to the tasks.
+/// - `Redo` - Re-applies the last changes reverted by `Undo`.
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Add a new task to the task list.
     ///
-    /// This subcommand adds a new task with the provided description.
+    /// This subcommand adds a new task with the provided description. Without one, the
+    /// description is prompted for when standard input is a terminal.
     ///
     /// # Arguments
     ///
     /// - `description` - A string representing the description of the new task.
+    /// - `stdin_description` - A flag to read the description and notes from standard input instead.
+    /// - `idempotency_key` - A key identifying this addition, so that re-running it does not add a duplicate.
+    /// - `depends_on_last` - A flag to make the new task depend on the most recently created task.
     ///
     Add {
         /// The description of the task to add.
         ///
-        /// This argument specifies the text description for the new task.
+        /// This argument specifies the text description for the new task. If it is left out and
+        /// standard input is a terminal, it is prompted for.
         #[arg()]
-        description: String,
+        description: Option<String>,
+
+        /// Read the description from standard input, e.g. a here-document.
+        ///
+        /// The first line is the description and any further lines are the task's notes. Quotes
+        /// and other shell metacharacters need no escaping.
+        #[arg(long, conflicts_with = "description")]
+        stdin_description: bool,
+
+        /// A key identifying this addition, unique within the task list.
+        ///
+        /// If a task with the same key and description already exists, nothing is added and the
+        /// existing task's ID is printed. Reusing a key with a different description is an error.
+        #[arg(long, value_name = "KEY")]
+        idempotency_key: Option<String>,
+
+        /// Make the new task depend on the most recently created task.
+        #[arg(long)]
+        depends_on_last: bool,
     },
 
     /// List tasks from the task list.
@@ -56,43 +131,300 @@ pub enum Commands {
     /// # Arguments
     ///
     /// - `all` - A flag indicating whether to show all tasks. If set, completed tasks will also be listed.
+    /// - `width` - The total width of the output; longer descriptions are truncated to fit.
+    /// - `fields` - The fields to show, in order.
+    /// - `json` - A flag to print the tasks as JSON instead of a table.
+    /// - `hide_completed_column` - A flag to leave out the `Completed` column, even with `--all`.
+    /// - `highlight_changed` - Highlight tasks updated within this long, e.g. `1d`.
+    /// - `completed_between` - Only show tasks completed between two dates, inclusive.
+    /// - `since_last_run` - Only show tasks added or updated since `list` last ran.
+    /// - `changed_since` - Only show tasks changed within this long, e.g. `7d`.
+    /// - `field` - The change `changed_since` looks at: any change, the description, or completion.
+    /// - `compact` - Print one short `#<id> [x] description` line per task instead of a table.
+    /// - `borders` - A flag to draw borders around the columns and rows of the table.
+    /// - `truncate` - Cut descriptions longer than this many characters, ending them with an ellipsis.
     ///
     List {
         /// Show all tasks, including completed ones.
         #[arg(short, long)]
         all: bool,
+
+        /// Fix the total width of the output.
+        ///
+        /// The description column is sized so that each line is at most this many characters,
+        /// and longer descriptions are truncated with an ellipsis.
+        #[arg(long)]
+        width: Option<usize>,
+
+        /// The fields to show, in order, separated by commas.
+        ///
+        /// Valid fields are `id`, `description`, `created_at`, `updated_at`, `completed`, and
+        /// `status`.
+        /// Defaults to `id,description,created_at`, plus `completed` with `--all`.
+        #[arg(long, value_delimiter = ',', num_args = 1..)]
+        fields: Vec<String>,
+
+        /// Print the tasks as a JSON array instead of a table.
+        #[arg(long, conflicts_with = "width")]
+        json: bool,
+
+        /// Leave out the `Completed` column, even with `--all`.
+        #[arg(long, conflicts_with = "fields")]
+        hide_completed_column: bool,
+
+        /// Highlight tasks updated within this long, e.g. `30m`, `12h`, `1d`, or `2w`.
+        ///
+        /// Highlighted tasks are shown in yellow, or marked with a `*` when colors are off.
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration, conflicts_with = "json")]
+        highlight_changed: Option<chrono::Duration>,
+
+        /// Only show tasks completed between two dates, inclusive, e.g. `2024-07-01 2024-07-31`.
+        ///
+        /// Tasks completed before `tasg` recorded completion times are never shown.
+        #[arg(long, num_args = 2, value_names = ["START", "END"])]
+        completed_between: Vec<chrono::NaiveDate>,
+
+        /// Only show tasks added or updated since `list` last ran for this tasks file.
+        ///
+        /// Every task is shown the first time `list` runs.
+        #[arg(long)]
+        since_last_run: bool,
+
+        /// Only show tasks changed within this long, e.g. `12h`, `7d`, or `2w`.
+        ///
+        /// `--field` chooses which change counts; by default any change does.
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        changed_since: Option<chrono::Duration>,
+
+        /// The change `--changed-since` looks at: `any`, `description`, or `completed`.
+        ///
+        /// Descriptions last changed before `tasg` recorded when they change count as changed
+        /// when the task was created.
+        #[arg(long, requires = "changed_since")]
+        field: Option<ChangedField>,
+
+        /// Print one short line per task, e.g. `#3 [x] Buy milk`, instead of a table.
+        #[arg(
+            long,
+            conflicts_with_all = ["json", "width", "fields", "hide_completed_column", "highlight_changed"]
+        )]
+        compact: bool,
+
+        /// Draw borders around the columns and rows of the table, e.g. for reports.
+        #[arg(long, conflicts_with_all = ["json", "compact"])]
+        borders: bool,
+
+        /// Cut descriptions longer than this many characters, ending them with an ellipsis.
+        ///
+        /// Unlike `--width`, this fixes the description column rather than the whole line, and
+        /// also applies to `--compact`.
+        #[arg(
+            long,
+            value_name = "N",
+            value_parser = clap::value_parser!(u32).range(1..),
+            conflicts_with_all = ["json", "width"]
+        )]
+        truncate: Option<u32>,
+    },
+
+    /// Show every field of a single task.
+    ///
+    /// This subcommand also shows tasks that are completed or in the trash.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - The ID of the task to show.
+    /// - `width` - The total width of the output; longer values are wrapped.
+    /// - `json` - A flag to print the task as a JSON object instead.
+    ///
+    Show {
+        /// The ID of the task to show.
+        #[arg(value_parser = parse_id)]
+        id: u32,
+
+        /// Wrap values so that each line is at most this many characters.
+        ///
+        /// Defaults to the `COLUMNS` environment variable when printing to a terminal.
+        #[arg(long)]
+        width: Option<usize>,
+
+        /// Print the task as a JSON object, with every field that is set.
+        #[arg(long, conflicts_with = "width")]
+        json: bool,
     },
 
-    /// Mark a task as complete.
+    /// Count the tasks.
     ///
-    /// This subcommand updates the status of the specified task to complete based on its ID.
+    /// This subcommand prints the number of tasks, completed tasks, and pending tasks. Tasks in
+    /// the trash are not counted.
     ///
     /// # Arguments
     ///
-    /// - `id` - The ID of the task to mark as complete. Must be a positive integer.
+    /// - `json` - A flag to print the counts as a JSON object instead, e.g. for a status bar.
+    Count {
+        /// Print the counts as a JSON object, e.g. `{"total":10,"completed":4,"pending":6}`.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Mark tasks as complete.
+    ///
+    /// This subcommand updates the status of the specified tasks to complete based on their IDs.
+    /// When several IDs are given, the tasks that exist are completed and the IDs that were not
+    /// found are reported. With `--all`, every pending task is completed, except those listed in
+    /// `--except`.
+    ///
+    /// # Arguments
+    ///
+    /// - `ids` - The IDs of the tasks to mark as complete, e.g. `3 5-7` or `"#3, #5"`.
+    /// - `all` - A flag to complete all pending tasks instead of the given tasks.
+    /// - `except` - The IDs of pending tasks to leave incomplete when using `--all`.
     Complete {
-        /// The ID of the task to complete.
+        /// The IDs of the tasks to complete.
+        ///
+        /// IDs may be separated by spaces or commas, written as `#12`, or given as ranges such
+        /// as `5-7`.
+        #[arg(required_unless_present = "all")]
+        ids: Vec<String>,
+
+        /// Complete all pending tasks.
+        #[arg(long, conflicts_with = "ids")]
+        all: bool,
+
+        /// Leave these tasks incomplete when completing all tasks.
         ///
-        /// This argument specifies the ID of the task that should be marked as completed.
-        #[arg(value_parser = clap::value_parser!(u32).range(1..))]
+        /// This argument accepts one or more IDs or ranges, separated by spaces or commas.
+        #[arg(long, num_args = 1..)]
+        except: Vec<String>,
+    },
+
+    /// Set the status of a task.
+    ///
+    /// This subcommand moves a task between `todo`, `in_progress`, `blocked`, and `done`.
+    /// Setting it to `done` is the same as completing it, and `list` hides done tasks unless
+    /// `--all` is given.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - The ID of the task to change. Must be a positive integer.
+    /// - `status` - The new status, e.g. `in_progress` or `in-progress`.
+    Status {
+        /// The ID of the task to change.
+        #[arg(value_parser = parse_id)]
         id: u32,
+
+        /// The new status: `todo`, `in_progress`, `blocked`, or `done`.
+        #[arg()]
+        status: Status,
     },
 
-    /// Delete a task from the task list.
+    /// Delete tasks from the task list.
     ///
-    /// This subcommand removes the task with the specified ID from the task list.
+    /// This subcommand moves the tasks with the specified IDs to the trash, from where they can
+    /// be restored. Use `--hard` to delete them permanently instead.
     ///
     /// # Arguments
     ///
-    /// - `id` - The ID of the task to delete. Must be a positive integer.
+    /// - `ids` - The IDs of the tasks to delete, e.g. `3 5-7` or `"#3, #5"`.
+    /// - `hard` - A flag to delete the tasks permanently, bypassing the trash.
     Delete {
-        /// The ID of the task to delete.
+        /// The IDs of the tasks to delete.
         ///
-        /// This argument specifies the ID of the task that should be removed from the list.
-        #[arg(value_parser = clap::value_parser!(u32).range(1..))]
+        /// IDs may be separated by spaces or commas, written as `#12`, or given as ranges such
+        /// as `5-7`.
+        #[arg(required = true)]
+        ids: Vec<String>,
+
+        /// Delete the tasks permanently instead of moving them to the trash.
+        #[arg(long)]
+        hard: bool,
+    },
+
+    /// List the tasks in the trash.
+    ///
+    /// This subcommand lists deleted tasks that can still be restored.
+    Trash,
+
+    /// Restore a task from the trash.
+    ///
+    /// This subcommand moves the task with the specified ID out of the trash and back into the
+    /// task list.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - The ID of the task to restore. Must be a positive integer.
+    Restore {
+        /// The ID of the task to restore.
+        #[arg(value_parser = parse_id)]
         id: u32,
     },
 
+    /// Permanently delete the tasks in the trash.
+    ///
+    /// This subcommand asks for confirmation before deleting, unless `--yes` is given.
+    ///
+    /// # Arguments
+    ///
+    /// - `yes` - A flag to skip the confirmation prompt.
+    /// - `older_than` - Only delete tasks that have been in the trash for more than this many days.
+    EmptyTrash {
+        /// Do not ask for confirmation.
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Only delete tasks that have been in the trash for more than this many days.
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u32>,
+    },
+
+    /// Print a single named field of a task.
+    ///
+    /// This subcommand is meant for scripts: the raw value is printed with no decoration.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - The ID of the task. Must be a positive integer.
+    /// - `field` - The name of the field, e.g. `description` or `created_at`.
+    Get {
+        /// The ID of the task.
+        #[arg(value_parser = parse_id)]
+        id: u32,
+
+        /// The name of the field, e.g. `description` or `created_at`.
+        #[arg()]
+        field: String,
+    },
+
+    /// Set a named field of a task.
+    ///
+    /// This subcommand is meant for scripts: every settable field is changed the same way.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - The ID of the task to change. Must be a positive integer.
+    /// - `field` - The name of the field: `description`, `notes`, `status`, `completed`, or `depends_on`.
+    /// - `value` - The new value of the field.
+    /// - `completed_ok` - A flag to change the description or notes even if the task is completed.
+    Set {
+        /// The ID of the task to change.
+        #[arg(value_parser = parse_id)]
+        id: u32,
+
+        /// The name of the field: `description`, `notes`, `status`, `completed`, or `depends_on`.
+        #[arg()]
+        field: String,
+
+        /// The new value of the field, e.g. `true` for `completed` or `none` for `depends_on`.
+        #[arg()]
+        value: String,
+
+        /// Change the description or notes even if the task is completed.
+        ///
+        /// Setting `edit_completed = true` in the config file has the same effect as this flag.
+        #[arg(long)]
+        completed_ok: bool,
+    },
+
     /// Edit an existing task's description.
     ///
     /// This subcommand allows you to modify the description or mark the task as completed.
@@ -101,11 +433,13 @@ pub enum Commands {
     ///
     /// - `id` - The ID of the task to edit. Must be a positive integer.
     /// - `description` - The new description of the task.
+    /// - `completed_ok` - A flag to edit the task even if it is completed.
+    /// - `interactive` - A flag to prompt for each field instead, showing its current value.
     Edit {
         /// The ID of the task to edit.
         ///
         /// This argument specifies the ID of the task that should be edited.
-        #[arg(value_parser = clap::value_parser!(u32).range(1..))]
+        #[arg(value_parser = parse_id)]
         id: u32,
 
         /// The new description of the task.
@@ -114,6 +448,242 @@ pub enum Commands {
         /// If not provided, the description will remain unchanged.
         #[arg(short, long)]
         description: Option<String>,
+
+        /// Edit the task even if it is completed.
+        ///
+        /// Completed tasks are kept as history, so editing one is refused by default. Setting
+        /// `edit_completed = true` in the config file has the same effect as this flag.
+        #[arg(long)]
+        completed_ok: bool,
+
+        /// Prompt for each field in turn, showing its current value.
+        ///
+        /// Pressing Enter keeps a field's value. Nothing is saved until every field has been
+        /// answered, and Ctrl-D aborts the edit.
+        #[arg(short, long, conflicts_with = "description")]
+        interactive: bool,
+    },
+
+    /// Mark a task as recently updated.
+    ///
+    /// This subcommand sets the task's update time to now without changing anything else.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - The ID of the task to touch. Must be a positive integer.
+    Touch {
+        /// The ID of the task to touch.
+        #[arg(value_parser = parse_id)]
+        id: u32,
+    },
+
+    /// Compare the tasks against another task file.
+    ///
+    /// This subcommand reports tasks that were added, removed, or changed in the given file
+    /// relative to the current tasks.
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The path to the task file to compare against.
+    /// - `stat` - A flag to print only the number of added, removed, and changed tasks.
+    /// - `json` - A flag to print the differences as JSON.
+    Diff {
+        /// The path to the task file to compare against.
+        ///
+        /// This argument specifies the JSON task file, e.g. a copy synced from another machine.
+        #[arg()]
+        path: String,
+
+        /// Print only the number of added, removed, and changed tasks.
+        #[arg(long)]
+        stat: bool,
+
+        /// Print the differences as JSON.
+        #[arg(long, conflicts_with = "stat")]
+        json: bool,
+    },
+
+    /// Apply the changes in an edited task file.
+    ///
+    /// This subcommand reads a JSON array of tasks, e.g. the output of `tasg list --json` after
+    /// editing it, previews the differences, and saves them in a single write. Tasks are matched
+    /// by ID, and only the fields present in the file are changed. Use the global `--dry-run` to
+    /// preview the differences without saving them.
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The path to the edited task file.
+    /// - `allow_new` - A flag to add tasks in the file that do not match an existing task.
+    /// - `prune_missing` - A flag to move tasks that are missing from the file to the trash.
+    /// - `completed_ok` - A flag to change the descriptions or notes of completed tasks.
+    Apply {
+        /// The path to the edited task file.
+        #[arg()]
+        path: String,
+
+        /// Add tasks in the file that do not match an existing task.
+        #[arg(long)]
+        allow_new: bool,
+
+        /// Move tasks that are missing from the file to the trash.
+        #[arg(long)]
+        prune_missing: bool,
+
+        /// Change the descriptions or notes of completed tasks.
+        ///
+        /// Setting `edit_completed = true` in the config file has the same effect as this flag.
+        #[arg(long)]
+        completed_ok: bool,
+    },
+
+    /// Check the tasks for problems.
+    ///
+    /// This subcommand reports tasks with invalid data, such as timestamps in the future, an
+    /// update time before the creation time, or a dependency on a missing task. Without a
+    /// `--check-*` flag, every check runs; with one or more, only those checks run.
+    ///
+    /// # Arguments
+    ///
+    /// - `check_timestamps` - A flag to run the timestamp check.
+    /// - `check_dependencies` - A flag to run the dependency check.
+    /// - `fix` - A flag indicating whether to fix the problems found.
+    Doctor {
+        /// Check for timestamps in the future or updates before creation.
+        #[arg(long)]
+        check_timestamps: bool,
+
+        /// Check for dependencies on missing or trashed tasks, and dependency cycles.
+        #[arg(long)]
+        check_dependencies: bool,
+
+        /// Fix the problems found.
+        ///
+        /// Future timestamps are clamped to the current time, update times before the creation
+        /// time are raised to the creation time, and invalid dependencies are removed.
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Learn the basics of tasg in a guided walkthrough.
+    ///
+    /// This subcommand prompts you to run each basic command against a throwaway task list,
+    /// checking each step before moving on. Your real tasks are never touched.
+    ///
+    /// # Arguments
+    ///
+    /// - `non_interactive` - A flag to print the whole lesson instead of prompting.
+    Tutorial {
+        /// Print the whole lesson without prompting.
+        #[arg(long)]
+        non_interactive: bool,
+    },
+
+    /// Wait until a task is completed.
+    ///
+    /// This subcommand polls the task list until the specified task is completed or deleted, then
+    /// exits successfully. If a timeout is given and reached first, it exits with code 124.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - The ID of the task to wait for. Must be a positive integer.
+    /// - `interval` - The number of seconds between checks.
+    /// - `timeout` - The maximum number of seconds to wait.
+    Wait {
+        /// The ID of the task to wait for.
+        #[arg(value_parser = parse_id)]
+        id: u32,
+
+        /// The number of seconds between checks.
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+
+        /// The maximum number of seconds to wait.
+        ///
+        /// If not provided, waits indefinitely.
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+
+    /// Copy the tasks to a timestamped backup file.
+    ///
+    /// Only the most recent backups are kept: 5 by default, or the number in the
+    /// `TASG_BACKUP_KEEP` environment variable. Backups are written alongside the tasks file, or
+    /// to the directory in the `TASG_BACKUP_DIR` environment variable.
+    Backup,
+
+    /// Copy open tasks from the previous period's task file into the current one.
+    ///
+    /// This subcommand requires a tasks file path containing date tokens, e.g.
+    /// `TASG_FILE=~/tasks/%Y-%W.json`. Carried tasks get new IDs; tasks that were already
+    /// carried over are skipped, so running it twice is harmless.
+    Carryover,
+
+    /// Carry out an instruction given as a single string, e.g. `tasg do "complete 12"`.
+    ///
+    /// This subcommand is meant for launchers: it prints a single line describing the result,
+    /// such as `added 3`, and never asks for confirmation. The supported verbs are `add`,
+    /// `complete`, `delete`, `restore`, `edit`, `touch`, and `get`.
+    ///
+    /// # Arguments
+    ///
+    /// - `instruction` - The verb and its arguments, e.g. `add buy milk`.
+    Do {
+        /// The verb and its arguments, e.g. `add buy milk`.
+        #[arg()]
+        instruction: String,
+    },
+
+    /// Print or install shell completion scripts.
+    ///
+    /// # Arguments
+    ///
+    /// - `command` - Whether to print the script or install it.
+    Completions {
+        /// Whether to print the script or install it.
+        #[command(subcommand)]
+        command: CompletionsCommand,
+    },
+
+    /// Print the help for a command, or explain a help topic.
+    ///
+    /// Topics cover the values accepted by several options, e.g. `tasg help durations`. Without
+    /// a name, the general help is printed along with the list of topics.
+    ///
+    /// # Arguments
+    ///
+    /// - `name` - The topic or command to explain.
+    Help {
+        /// The topic or command to explain, e.g. `durations` or `list`.
+        #[arg()]
+        name: Option<String>,
+    },
+
+    /// Revert the last changes made to the tasks.
+    ///
+    /// Every command that changes the tasks can be undone, up to the last 10 by default, or
+    /// `TASG_UNDO_LIMIT`. The history is kept next to the tasks file, e.g. `tasks.json.undo`.
+    /// The tasks that were put back are listed afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// - `steps` - The number of changes to revert.
+    Undo {
+        /// The number of changes to revert.
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        steps: u32,
+    },
+
+    /// Re-apply the last changes reverted by `undo`.
+    ///
+    /// Changes can be redone until the tasks are changed by any other command.
+    ///
+    /// # Arguments
+    ///
+    /// - `steps` - The number of changes to re-apply.
+    Redo {
+        /// The number of changes to re-apply.
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        steps: u32,
     },
 
     /// Nuke all of the tasks.
@@ -121,3 +691,41 @@ pub enum Commands {
     /// This subcommand will delete all your tasks - use with caution!
     Nuke,
 }
+
+/// The subcommands of `tasg completions`.
+///
+/// # Variants
+///
+/// - `Generate` - Prints the completion script for a shell.
+/// - `Install` - Writes the completion script to where the shell looks for it.
+#[derive(Subcommand, Debug)]
+pub enum CompletionsCommand {
+    /// Print the completion script for a shell.
+    ///
+    /// # Arguments
+    ///
+    /// - `shell` - The shell to generate the script for.
+    Generate {
+        /// The shell to generate the script for.
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
+    /// Write the completion script to where the shell looks for it, and explain how to load it.
+    ///
+    /// Bash, zsh, and fish have a conventional per-user location; other shells need `--path`.
+    ///
+    /// # Arguments
+    ///
+    /// - `shell` - The shell to install the script for.
+    /// - `path` - Where to write the script instead of the conventional location.
+    Install {
+        /// The shell to install the script for.
+        #[arg(value_enum)]
+        shell: Shell,
+
+        /// Where to write the script instead of the conventional location.
+        #[arg(long)]
+        path: Option<PathBuf>,
+    },
+}
@@ -0,0 +1,165 @@
+//! Shell completion scripts.
+//!
+//! `tasg completions generate <shell>` prints a completion script generated from the `Cli`
+//! definition, and `tasg completions install <shell>` writes it to where the shell looks for
+//! completions, so it stays in sync with the installed version of `tasg`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+use crate::cli::Cli;
+use crate::error::TaskError;
+
+/// The name completions are generated for.
+const BIN_NAME: &str = "tasg";
+
+/// Generates the completion script for a shell.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to generate the script for.
+///
+/// # Returns
+///
+/// * `Vec<u8>` - The script.
+pub fn script(shell: Shell) -> Vec<u8> {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Cli::command(), BIN_NAME, &mut script);
+    script
+}
+
+/// The conventional per-user location of the completion script for a shell.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to install completions for.
+///
+/// # Returns
+///
+/// * `Result<PathBuf, TaskError>` - The path, or `TaskError::InvalidInput` if the shell has no conventional location, e.g. PowerShell, or the home directory cannot be found.
+pub fn default_path(shell: Shell) -> Result<PathBuf, TaskError> {
+    let home = || {
+        dirs::home_dir()
+            .ok_or_else(|| TaskError::InvalidInput("Cannot determine the home directory".into()))
+    };
+    match shell {
+        Shell::Bash => {
+            let data = std::env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .map_or_else(|| home().map(|h| h.join(".local/share")), Ok)?;
+            Ok(data.join("bash-completion/completions").join(BIN_NAME))
+        }
+        Shell::Zsh => Ok(home()?.join(".zfunc").join(format!("_{}", BIN_NAME))),
+        Shell::Fish => {
+            Ok(home()?.join(".config/fish/completions").join(format!("{}.fish", BIN_NAME)))
+        }
+        _ => Err(TaskError::InvalidInput(format!(
+            "There is no conventional completion path for {}; pass --path to choose one",
+            shell
+        ))),
+    }
+}
+
+/// Writes the completion script for a shell, creating any missing directories.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to install completions for.
+/// * `path` - Where to write the script.
+///
+/// # Returns
+///
+/// * `Result<(), TaskError>` - `Ok(())` if the script was written, or `TaskError::IoError` naming the path if it cannot be written.
+pub fn install(shell: Shell, path: &Path) -> Result<(), TaskError> {
+    let write = || {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, script(shell))
+    };
+    write().map_err(|e| {
+        TaskError::IoError(io::Error::new(
+            e.kind(),
+            format!("cannot write completions to {}: {}", path.display(), e),
+        ))
+    })
+}
+
+/// How to make a shell load an installed completion script.
+///
+/// # Arguments
+///
+/// * `shell` - The shell the script was installed for.
+/// * `path` - Where the script was written.
+///
+/// # Returns
+///
+/// * `String` - Instructions for the user, with a trailing newline.
+pub fn instructions(shell: Shell, path: &Path) -> String {
+    let dir = path.parent().unwrap_or(Path::new(".")).display();
+    let next = match shell {
+        Shell::Bash => String::from("Start a new shell to use them (requires bash-completion)."),
+        Shell::Zsh => format!(
+            "Add these lines to ~/.zshrc before any call to compinit, then start a new shell:\n  fpath+=({})\n  autoload -Uz compinit && compinit",
+            dir
+        ),
+        Shell::Fish => String::from("Start a new shell to use them."),
+        Shell::PowerShell => format!("Add this line to your $PROFILE:\n  . {}", path.display()),
+        _ => format!("Source {} from your shell's startup file.", path.display()),
+    };
+    format!("Installed {} completions to {}\n{}\n", shell, path.display(), next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that scripts complete the subcommands.
+    #[test]
+    fn test_script() {
+        let script = String::from_utf8(script(Shell::Bash)).unwrap();
+        assert!(script.contains("tasg"));
+        assert!(script.contains("carryover"));
+    }
+
+    /// Tests the conventional paths, and that shells without one are rejected.
+    #[test]
+    fn test_default_path() {
+        let fish = default_path(Shell::Fish).unwrap();
+        assert!(fish.ends_with(".config/fish/completions/tasg.fish"));
+        assert!(default_path(Shell::Zsh).unwrap().ends_with(".zfunc/_tasg"));
+        assert!(default_path(Shell::Bash).unwrap().ends_with("bash-completion/completions/tasg"));
+
+        let err = default_path(Shell::PowerShell).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid input - There is no conventional completion path for powershell; pass --path to choose one"
+        );
+    }
+
+    /// Tests installing into a directory that does not exist yet.
+    #[test]
+    fn test_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("completions").join("_tasg");
+        install(Shell::Zsh, &path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), script(Shell::Zsh));
+        assert!(instructions(Shell::Zsh, &path).contains("fpath+="));
+    }
+
+    /// Tests that an unwritable path is reported with the path.
+    #[test]
+    fn test_install_unwritable() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let path = file.join("tasg.fish");
+        let err = install(Shell::Fish, &path).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&format!("cannot write completions to {}", path.display())));
+    }
+}
@@ -0,0 +1,177 @@
+//! Reading the config file.
+//!
+//! Defaults that would otherwise need a flag on every command can be set in
+//! `~/.config/tasg/config.toml`, or the file named by the `TASG_CONFIG` environment variable.
+//! Command-line flags and environment variables take precedence over the file. Unknown keys are
+//! ignored with a warning, so a config file written for a newer version of `tasg` still works,
+//! while a misspelled key does not go unnoticed.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::TaskError;
+use crate::render::Output;
+
+/// Defaults read from the config file.
+///
+/// # Fields
+///
+/// - `date_format` - The `strftime`-style format used to display timestamps, if set.
+/// - `color` - Whether to use colors, if set; by default they are used when printing to a terminal. `NO_COLOR` always turns them off.
+/// - `list_all` - Whether `list` shows completed tasks without `--all`. Also accepted as `default_list_all`.
+/// - `tasks_file` - The path template of the tasks file, if set; `TASG_FILE` takes precedence.
+/// - `edit_completed` - Whether `edit`, `set`, and `apply` change completed tasks without `--completed-ok`.
+/// - `output` - How `list`, `trash`, `show`, and `count` print their results, if set; `--output` takes precedence.
+/// - `preserve_symlinks` - Whether saving through a symlinked tasks file keeps the link, if set; `TASG_PRESERVE_SYMLINKS` takes precedence.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The `strftime`-style format used to display timestamps, if set.
+    pub date_format: Option<String>,
+
+    /// Whether to use colors, if set; by default they are used when printing to a terminal.
+    pub color: Option<bool>,
+
+    /// Whether `list` shows completed tasks without `--all`.
+    #[serde(alias = "default_list_all")]
+    pub list_all: bool,
+
+    /// The path template of the tasks file, if set; `TASG_FILE` takes precedence.
+    pub tasks_file: Option<String>,
+
+    /// Whether `edit`, `set`, and `apply` change completed tasks without `--completed-ok`.
+    pub edit_completed: bool,
+
+    /// How `list`, `trash`, `show`, and `count` print their results, if set.
+    pub output: Option<Output>,
+
+    /// Whether saving through a symlinked tasks file keeps the link, if set; it does by default.
+    pub preserve_symlinks: Option<bool>,
+}
+
+impl Config {
+    /// The keys a config file may contain.
+    pub const KEYS: &'static [&'static str] = &[
+        "date_format",
+        "color",
+        "list_all",
+        "default_list_all",
+        "tasks_file",
+        "edit_completed",
+        "output",
+        "preserve_symlinks",
+    ];
+
+    /// Finds the keys of a config file that are not in `KEYS`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The TOML contents of the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - The unknown keys, sorted by name, or nothing if the contents are not valid TOML.
+    pub fn unknown_keys(text: &str) -> Vec<String> {
+        let Ok(table) = toml::from_str::<toml::Table>(text) else {
+            return Vec::new();
+        };
+        table.keys().filter(|key| !Config::KEYS.contains(&key.as_str())).cloned().collect()
+    }
+
+    /// Parses the contents of a config file.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The TOML contents of the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, TaskError>` - The config, or `TaskError::InvalidInput` if the contents are not valid TOML, a known key has the wrong type, or `date_format` is not a valid format.
+    pub fn parse(text: &str) -> Result<Self, TaskError> {
+        let config: Config = toml::from_str(text)
+            .map_err(|e| TaskError::InvalidInput(format!("Invalid config file: {}", e)))?;
+        if let Some(format) = &config.date_format {
+            let mut sample = String::new();
+            write!(sample, "{}", chrono::Local::now().format(format)).map_err(|_| {
+                TaskError::InvalidInput(format!("Invalid date_format '{}' in config file", format))
+            })?;
+        }
+        Ok(config)
+    }
+
+    /// Loads the config file at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the config file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, TaskError>` - The config, the default config if the file does not exist, or a `TaskError` if it cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, TaskError> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let text = std::fs::read_to_string(path)?;
+        let config = Config::parse(&text)?;
+        for key in Config::unknown_keys(&text) {
+            eprintln!("Warning: unknown key '{}' in {} is ignored", key, path.display());
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests parsing every key, ignoring unknown ones.
+    #[test]
+    fn test_parse() {
+        let text = "date_format = \"%d/%m/%Y\"\ncolor = false\nlist_all = true\ntasks_file = \"~/tasks/%Y.json\"\nedit_completed = true\noutput = \"plain\"\npreserve_symlinks = false\nbackend = \"sqlite\"\n";
+        assert_eq!(
+            Config::parse(text).unwrap(),
+            Config {
+                date_format: Some(String::from("%d/%m/%Y")),
+                color: Some(false),
+                list_all: true,
+                tasks_file: Some(String::from("~/tasks/%Y.json")),
+                edit_completed: true,
+                output: Some(Output::Plain),
+                preserve_symlinks: Some(false)
+            }
+        );
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert!(Config::parse("default_list_all = true").unwrap().list_all);
+    }
+
+    /// Tests that unknown keys are found, and that every known key is accepted.
+    #[test]
+    fn test_unknown_keys() {
+        let text = "list_all = true\nbackend = \"sqlite\"\n[colors]\ndone = \"green\"\n";
+        assert_eq!(Config::unknown_keys(text), vec!["backend", "colors"]);
+        assert!(Config::unknown_keys("list_all = ").is_empty());
+
+        let known: Vec<String> = Config::KEYS.iter().map(|key| format!("{} = 1", key)).collect();
+        assert!(Config::unknown_keys(&known.join("\n")).is_empty());
+    }
+
+    /// Tests that invalid files are rejected.
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Config::parse("list_all = ").is_err());
+        assert!(Config::parse("list_all = \"yes\"").is_err());
+        assert!(Config::parse("output = \"fancy\"").is_err());
+        let err = Config::parse("date_format = \"%Q\"").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid input - Invalid date_format '%Q' in config file");
+    }
+
+    /// Tests that a missing file gives the default config.
+    #[test]
+    fn test_load_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Config::load(&dir.path().join("config.toml")).unwrap(), Config::default());
+    }
+}
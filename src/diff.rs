@@ -0,0 +1,424 @@
+//! Comparing two sets of tasks.
+//!
+//! This module computes the differences between two task lists, typically the current store and
+//! another task file (for example a copy synced from a different machine or a backup). Tasks are
+//! matched by ID and creation time first, so that a different task which reuses an ID is not
+//! mistaken for an edit; any tasks left unmatched are then paired up by description so that a
+//! task which was renumbered on one side is reported as changed rather than as a removal plus an
+//! addition.
+
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::task::{Status, Task};
+
+/// A single difference between two task lists.
+///
+/// # Variants
+///
+/// - `Added` - The task only exists in the right-hand (other) list.
+/// - `Removed` - The task only exists in the left-hand (current) list.
+/// - `Changed` - The task exists in both lists, but some of its fields differ.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskDiff {
+    /// A task that only exists in the other list.
+    Added(Task),
+
+    /// A task that only exists in the current list.
+    Removed(Task),
+
+    /// A task that exists in both lists with differing fields.
+    Changed {
+        /// The task as it appears in the current list.
+        before: Task,
+
+        /// The task as it appears in the other list.
+        after: Task,
+    },
+}
+
+/// A change to a single field of a task.
+///
+/// # Fields
+///
+/// - `field` - The name of the changed field, as in the task file.
+/// - `before` - The value in the current list.
+/// - `after` - The value in the other list.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// The name of the changed field, as in the task file.
+    pub field: &'static str,
+
+    /// The value in the current list.
+    pub before: Value,
+
+    /// The value in the other list.
+    pub after: Value,
+}
+
+/// The number of differences of each kind.
+///
+/// # Fields
+///
+/// - `added` - The number of tasks that only exist in the other list.
+/// - `removed` - The number of tasks that only exist in the current list.
+/// - `changed` - The number of tasks that exist in both lists with differing fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DiffStat {
+    /// The number of tasks that only exist in the other list.
+    pub added: usize,
+
+    /// The number of tasks that only exist in the current list.
+    pub removed: usize,
+
+    /// The number of tasks that exist in both lists with differing fields.
+    pub changed: usize,
+}
+
+/// Computes the differences between two task lists.
+///
+/// Tasks are matched by ID and creation time. Tasks that have no such counterpart are then
+/// matched by identical description. Matched tasks with any differing field (see
+/// `TaskDiff::changes`) are reported as `Changed`; the rest are reported as `Removed` (left only)
+/// or `Added` (right only).
+///
+/// # Arguments
+///
+/// * `left` - The current tasks, e.g. the contents of the store.
+/// * `right` - The tasks to compare against, e.g. the contents of another file.
+///
+/// # Returns
+///
+/// * `Vec<TaskDiff>` - The differences, ordered by ID.
+pub fn diff(left: &[Task], right: &[Task]) -> Vec<TaskDiff> {
+    let mut diffs = Vec::new();
+    let mut unmatched_left = Vec::new();
+    let mut unmatched_right: Vec<&Task> =
+        right.iter().filter(|r| !left.iter().any(|l| same_task(l, r))).collect();
+
+    for before in left {
+        match right.iter().find(|r| same_task(before, r)) {
+            Some(after) => push_if_changed(&mut diffs, before, after),
+            None => unmatched_left.push(before),
+        }
+    }
+
+    for before in unmatched_left {
+        match unmatched_right.iter().position(|r| r.description == before.description) {
+            Some(index) => {
+                let after = unmatched_right.remove(index);
+                push_if_changed(&mut diffs, before, after);
+            }
+            None => diffs.push(TaskDiff::Removed(before.clone())),
+        }
+    }
+
+    diffs.extend(unmatched_right.into_iter().cloned().map(TaskDiff::Added));
+    diffs.sort_by_key(|d| match d {
+        TaskDiff::Added(task) | TaskDiff::Removed(task) => task.id,
+        TaskDiff::Changed { before, .. } => before.id,
+    });
+    diffs
+}
+
+/// Counts the differences of each kind.
+///
+/// # Arguments
+///
+/// * `diffs` - The differences, as returned by `diff`.
+///
+/// # Returns
+///
+/// * `DiffStat` - The number of added, removed, and changed tasks.
+pub fn stat(diffs: &[TaskDiff]) -> DiffStat {
+    let mut stat = DiffStat::default();
+    for diff in diffs {
+        match diff {
+            TaskDiff::Added(_) => stat.added += 1,
+            TaskDiff::Removed(_) => stat.removed += 1,
+            TaskDiff::Changed { .. } => stat.changed += 1,
+        }
+    }
+    stat
+}
+
+/// Converts differences to a structured JSON value.
+///
+/// The value is an object with `added` and `removed` arrays of tasks, and a `changed` array of
+/// objects with the task's `id` in the current list and its field-level `changes`.
+///
+/// # Arguments
+///
+/// * `diffs` - The differences, as returned by `diff`.
+///
+/// # Returns
+///
+/// * `Value` - The differences as JSON.
+pub fn to_json(diffs: &[TaskDiff]) -> Value {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for diff in diffs {
+        match diff {
+            TaskDiff::Added(task) => added.push(json!(task)),
+            TaskDiff::Removed(task) => removed.push(json!(task)),
+            TaskDiff::Changed { before, .. } => {
+                changed.push(json!({ "id": before.id, "changes": diff.changes() }))
+            }
+        }
+    }
+    json!({ "added": added, "removed": removed, "changed": changed })
+}
+
+/// A field name and a function reading that field of a task as JSON.
+type FieldGetter = (&'static str, fn(&Task) -> Value);
+
+/// Whether two tasks are the same task: the same ID and creation time.
+fn same_task(left: &Task, right: &Task) -> bool {
+    left.same_id(right) && left.created_at == right.created_at
+}
+
+/// Records a `Changed` entry if the matched tasks differ in any compared field.
+fn push_if_changed(diffs: &mut Vec<TaskDiff>, before: &Task, after: &Task) {
+    let diff = TaskDiff::Changed { before: before.clone(), after: after.clone() };
+    if !diff.changes().is_empty() {
+        diffs.push(diff);
+    }
+}
+
+impl TaskDiff {
+    /// The field-level changes of a `Changed` difference.
+    ///
+    /// The ID, description, notes, status, completion time, idempotency key, and dependency are
+    /// compared. The creation time is only used to match tasks, and the update time changes whenever
+    /// a task is saved.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<FieldChange>` - The changed fields in a fixed order, or an empty vector for `Added` and `Removed`.
+    pub fn changes(&self) -> Vec<FieldChange> {
+        let TaskDiff::Changed { before, after } = self else {
+            return Vec::new();
+        };
+        let fields: [FieldGetter; 7] = [
+            ("id", |t| json!(t.id)),
+            ("description", |t| json!(t.description)),
+            ("notes", |t| json!(t.notes)),
+            ("status", |t| json!(t.status)),
+            ("completed_at", |t| json!(t.completed_at)),
+            ("idempotency_key", |t| json!(t.idempotency_key)),
+            ("depends_on", |t| json!(t.depends_on)),
+        ];
+        fields
+            .into_iter()
+            .filter_map(|(field, value)| {
+                let (before, after) = (value(before), value(after));
+                (before != after).then_some(FieldChange { field, before, after })
+            })
+            .collect()
+    }
+}
+
+/// Formats a field value for display: statuses the same way the `list` command does,
+/// descriptions and notes quoted, and missing values as `none`.
+fn display_value(field: &str, value: &Value) -> String {
+    match (field, value) {
+        ("status", value) => match serde_json::from_value::<Status>(value.clone()) {
+            Ok(status) => status.to_string(),
+            Err(_) => value.to_string(),
+        },
+        ("description" | "notes", Value::String(text)) => Value::from(text.as_str()).to_string(),
+        (_, Value::Null) => String::from("none"),
+        (_, Value::String(text)) => text.clone(),
+        (_, value) => value.to_string(),
+    }
+}
+
+impl fmt::Display for FieldChange {
+    /// Formats the change as `field: before -> after`.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter used to write the formatted string.
+    ///
+    /// # Returns
+    ///
+    /// * `fmt::Result` - The result of the formatting operation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.field,
+            display_value(self.field, &self.before),
+            display_value(self.field, &self.after)
+        )
+    }
+}
+
+impl fmt::Display for DiffStat {
+    /// Formats the counts as `N added, N removed, N changed`.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter used to write the formatted string.
+    ///
+    /// # Returns
+    ///
+    /// * `fmt::Result` - The result of the formatting operation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} added, {} removed, {} changed", self.added, self.removed, self.changed)
+    }
+}
+
+impl fmt::Display for TaskDiff {
+    /// Formats the difference as a single line with a `+`, `-`, or `~` marker.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter used to write the formatted string.
+    ///
+    /// # Returns
+    ///
+    /// * `fmt::Result` - The result of the formatting operation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskDiff::Added(task) => write!(f, "+ [{}] {}", task.id, task.description),
+            TaskDiff::Removed(task) => write!(f, "- [{}] {}", task.id, task.description),
+            TaskDiff::Changed { before, .. } => {
+                let changes: Vec<String> = self.changes().iter().map(|c| c.to_string()).collect();
+                write!(f, "~ [{}] {} ({})", before.id, before.description, changes.join(", "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that identical lists produce no differences.
+    #[test]
+    fn test_diff_identical() {
+        let tasks =
+            vec![Task::new(1, String::from("Task 1")), Task::new(2, String::from("Task 2"))];
+        assert!(diff(&tasks, &tasks).is_empty());
+    }
+
+    /// Tests that an added, a removed, and a modified task are all reported.
+    #[test]
+    fn test_diff_added_removed_changed() {
+        let task1 = Task::new(1, String::from("Task 1"));
+        let task2 = Task::new(2, String::from("Task 2"));
+        let task3 = Task::new(3, String::from("Task 3"));
+        let mut task1_done = task1.clone();
+        task1_done.status = Status::Done;
+
+        let left = vec![task1.clone(), task2.clone()];
+        let right = vec![task1_done.clone(), task3.clone()];
+
+        let diffs = diff(&left, &right);
+        assert_eq!(
+            diffs,
+            vec![
+                TaskDiff::Changed { before: task1, after: task1_done },
+                TaskDiff::Removed(task2),
+                TaskDiff::Added(task3),
+            ]
+        );
+    }
+
+    /// Tests that unmatched IDs fall back to matching by description.
+    #[test]
+    fn test_diff_matches_by_description() {
+        let left = vec![Task::new(1, String::from("Renumbered"))];
+        let right = vec![Task::new(4, String::from("Renumbered"))];
+
+        let diffs = diff(&left, &right);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].to_string(), "~ [1] Renumbered (id: 1 -> 4)");
+    }
+
+    /// Tests the `+`/`-` markers of the display format.
+    #[test]
+    fn test_diff_display_markers() {
+        let task = Task::new(7, String::from("Task 7"));
+        assert_eq!(TaskDiff::Added(task.clone()).to_string(), "+ [7] Task 7");
+        assert_eq!(TaskDiff::Removed(task).to_string(), "- [7] Task 7");
+    }
+
+    /// Tests that a different task reusing an ID is not reported as an edit.
+    #[test]
+    fn test_diff_matches_by_id_and_created_at() {
+        let before = Task::new(1, String::from("Original"));
+        let mut reused = Task::new(1, String::from("Replacement"));
+        reused.created_at = before.created_at + chrono::Duration::days(1);
+
+        let diffs = diff(std::slice::from_ref(&before), std::slice::from_ref(&reused));
+        assert_eq!(diffs, vec![TaskDiff::Removed(before), TaskDiff::Added(reused)]);
+    }
+
+    /// Tests the field-level changes of a changed task.
+    #[test]
+    fn test_diff_field_changes() {
+        let before = Task::new(1, String::from("Task 1"));
+        let mut after = before.clone();
+        after.description = String::from("Task one");
+        after.status = Status::Done;
+        after.updated_at = before.updated_at + chrono::Duration::hours(1);
+
+        let changes = TaskDiff::Changed { before, after }.changes();
+        let fields: Vec<&str> = changes.iter().map(|c| c.field).collect();
+        assert_eq!(fields, vec!["description", "status"]);
+        assert_eq!(changes[0].to_string(), r#"description: "Task 1" -> "Task one""#);
+        assert_eq!(changes[1].to_string(), "status: Todo -> Done");
+    }
+
+    /// Tests that notes and the completion time are compared, with notes quoted on one line.
+    #[test]
+    fn test_diff_notes_and_completed_at() {
+        let before = Task::new(1, String::from("Task 1"));
+        let mut after = before.clone();
+        after.notes = Some(String::from("First line\nSecond line"));
+        after.completed_at = Some(before.created_at);
+
+        let changes = TaskDiff::Changed { before: before.clone(), after: after.clone() }.changes();
+        let fields: Vec<&str> = changes.iter().map(|c| c.field).collect();
+        assert_eq!(fields, vec!["notes", "completed_at"]);
+        assert_eq!(changes[0].to_string(), r#"notes: none -> "First line\nSecond line""#);
+        assert_eq!(diff(&[before], &[after]).len(), 1);
+    }
+
+    /// Tests that a task whose only change is its update time is not reported.
+    #[test]
+    fn test_diff_ignores_updated_at() {
+        let before = Task::new(1, String::from("Task 1"));
+        let mut after = before.clone();
+        after.updated_at = before.updated_at + chrono::Duration::hours(1);
+        assert!(diff(&[before], &[after]).is_empty());
+    }
+
+    /// Tests the counts and JSON form of a set of differences.
+    #[test]
+    fn test_diff_stat_and_json() {
+        let task1 = Task::new(1, String::from("Task 1"));
+        let mut task1_done = task1.clone();
+        task1_done.status = Status::Done;
+        let task2 = Task::new(2, String::from("Task 2"));
+        let task3 = Task::new(3, String::from("Task 3"));
+
+        let diffs = diff(&[task1, task2], &[task1_done, task3]);
+        let counts = stat(&diffs);
+        assert_eq!(counts, DiffStat { added: 1, removed: 1, changed: 1 });
+        assert_eq!(counts.to_string(), "1 added, 1 removed, 1 changed");
+
+        let value = to_json(&diffs);
+        assert_eq!(value["added"][0]["id"], 3);
+        assert_eq!(value["removed"][0]["id"], 2);
+        assert_eq!(
+            value["changed"][0],
+            json!({ "id": 1, "changes": [{ "field": "status", "before": "todo", "after": "done" }] })
+        );
+    }
+}
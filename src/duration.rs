@@ -0,0 +1,87 @@
+//! Parsing durations.
+//!
+//! Options such as `list --highlight-changed` take a length of time written as a number and a
+//! unit, e.g. `30m`, `12h`, `1d`, or `2w`. Several parts may be combined, e.g. `1d12h`.
+
+use chrono::Duration;
+
+use crate::error::TaskError;
+
+/// The units a duration may be written in: the letter, its name, and its length in seconds.
+pub const UNITS: &[(char, &str, i64)] = &[
+    ('s', "seconds", 1),
+    ('m', "minutes", 60),
+    ('h', "hours", 60 * 60),
+    ('d', "days", 24 * 60 * 60),
+    ('w', "weeks", 7 * 24 * 60 * 60),
+];
+
+/// Parses a duration such as `12h` or `1d12h`.
+///
+/// The units are listed in `UNITS`: `s` (seconds), `m` (minutes), `h` (hours), `d` (days), and
+/// `w` (weeks).
+///
+/// # Arguments
+///
+/// * `input` - The duration as typed.
+///
+/// # Returns
+///
+/// * `Result<Duration, TaskError>` - The duration, or `TaskError::InvalidInput` naming the input if it is empty, has a part without a number or unit, or is too long.
+pub fn parse_duration(input: &str) -> Result<Duration, TaskError> {
+    let invalid = || {
+        TaskError::InvalidInput(format!(
+            "Invalid duration '{}'; expected e.g. 30m, 12h, 1d, or 2w",
+            input.trim()
+        ))
+    };
+    let mut rest = input.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut total = Duration::zero();
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let amount: i64 = rest[..digits].parse().map_err(|_| invalid())?;
+        let mut chars = rest[digits..].chars();
+        let unit = chars.next().and_then(|c| UNITS.iter().find(|(letter, ..)| *letter == c));
+        let part = unit
+            .and_then(|(_, _, seconds)| amount.checked_mul(*seconds))
+            .and_then(Duration::try_seconds);
+        total = part.and_then(|part| total.checked_add(&part)).ok_or_else(invalid)?;
+        rest = chars.as_str();
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests each unit and combinations of them.
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("45s").unwrap(), Duration::seconds(45));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("12h").unwrap(), Duration::hours(12));
+        assert_eq!(parse_duration(" 1d ").unwrap(), Duration::days(1));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+        assert_eq!(parse_duration("1d12h").unwrap(), Duration::hours(36));
+        assert_eq!(parse_duration("0m").unwrap(), Duration::zero());
+    }
+
+    /// Tests that invalid durations are rejected with the input in the message.
+    #[test]
+    fn test_parse_duration_invalid() {
+        for input in ["", "1", "d", "1x", "1.5h", "-1d", "1d 2h", "99999999999999999999w"] {
+            let err = parse_duration(input).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "Invalid input - Invalid duration '{}'; expected e.g. 30m, 12h, 1d, or 2w",
+                    input.trim()
+                )
+            );
+        }
+    }
+}
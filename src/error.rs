@@ -3,19 +3,23 @@
 //! This module defines the custom error type `TaskError` used for handling errors
 //! in task-related operations within the task management CLI application.
 
-use std::fmt;
-
 /// Custom error type for task-related operations.
 ///
 /// The `TaskError` enum encapsulates various errors that can occur while managing tasks,
 /// including task not found errors, I/O errors, serialization/deserialization errors, and invalid input.
-#[derive(Debug)]
+///
+/// The enum is `#[non_exhaustive]`, so new variants are not a breaking change; match with a
+/// wildcard arm, or use helpers such as `is_not_found`. Wrapped errors are exposed through
+/// `std::error::Error::source`.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum TaskError {
     /// Error indicating that a task with the specified ID was not found.
     ///
     /// # Fields
     ///
     /// * `u32` - The ID of the task that was not found.
+    #[error("Task with ID {0} not found")]
     NotFound(u32),
 
     /// Error representing an I/O operation failure.
@@ -25,7 +29,8 @@ pub enum TaskError {
     /// # Fields
     ///
     /// * `std::io::Error` - The I/O error that occurred.
-    IoError(std::io::Error),
+    #[error("I/O error - {0}")]
+    IoError(#[from] std::io::Error),
 
     /// Error representing a serialization/deserialization failure.
     ///
@@ -34,73 +39,149 @@ pub enum TaskError {
     /// # Fields
     ///
     /// * `serde_json::Error` - The serialization or deserialization error that occurred.
-    SerdeError(serde_json::Error),
+    #[error("Serialization error -  {0}")]
+    SerdeError(#[from] serde_json::Error),
 
     /// Error representing invalid input.
     ///
     /// # Fields
     ///
     /// * `String` - Message stating why input is invalid.
+    #[error("Invalid input - {0}")]
     InvalidInput(String),
-}
 
-impl fmt::Display for TaskError {
-    /// Formats the `TaskError` for display purposes.
+    /// Error indicating that a mutating operation was attempted in read-only mode.
+    #[error("Read-only mode - the tasks cannot be modified")]
+    ReadOnlyMode,
+
+    /// Error indicating that the tasks file is neither UTF-8 nor UTF-16 encoded.
     ///
-    /// This implementation provides a user-friendly description of the error, which is useful
-    /// for displaying error messages to the end-user.
+    /// # Fields
     ///
-    /// # Arguments
+    /// * `String` - The path to the tasks file.
+    #[error("Encoding error - {0} is not valid UTF-8 or UTF-16")]
+    Encoding(String),
+
+    /// Error indicating that waiting for a task to be completed timed out.
     ///
-    /// * `f` - The formatter used to write the formatted string.
+    /// # Fields
+    ///
+    /// * `u32` - The ID of the task that was waited on.
+    #[error("Timed out waiting for task with ID {0}")]
+    TimedOut(u32),
+
+    /// Error indicating that a new task cannot be given an ID because the largest ID,
+    /// `u32::MAX`, is already in use.
+    #[error("ID space exhausted - task {} already exists, so no new ID is available", u32::MAX)]
+    IdSpaceExhausted,
+}
+
+impl TaskError {
+    /// The process exit code to use when a command fails with this error.
+    ///
+    /// Timeouts exit with `124`, following the convention of the `timeout` utility, so scripts can
+    /// tell them apart from other failures. Every other error exits with `1`.
     ///
     /// # Returns
     ///
-    /// * `fmt::Result` - The result of the formatting operation.
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    /// * `i32` - The exit code.
+    pub fn exit_code(&self) -> i32 {
         match self {
-            TaskError::NotFound(id) => write!(f, "Task with ID {} not found", id),
-            TaskError::IoError(e) => write!(f, "I/O error - {}", e),
-            TaskError::SerdeError(e) => write!(f, "Serialization error -  {}", e),
-            TaskError::InvalidInput(msg) => write!(f, "Invalid input - {}", msg),
+            TaskError::TimedOut(_) => 124,
+            _ => 1,
         }
     }
-}
 
-impl std::error::Error for TaskError {}
-
-impl From<std::io::Error> for TaskError {
-    /// Converts a `std::io::Error` into a `TaskError`.
-    ///
-    /// This implementation allows I/O errors to be seamlessly converted into `TaskError::IoError` variants,
-    /// enabling better error handling in task-related operations.
-    ///
-    /// # Arguments
-    ///
-    /// * `error` - The `std::io::Error` to convert.
+    /// Whether the error is a task that was not found.
     ///
     /// # Returns
     ///
-    /// * `TaskError` - The corresponding `TaskError` variant.
-    fn from(error: std::io::Error) -> Self {
-        TaskError::IoError(error)
+    /// * `bool` - `true` for `TaskError::NotFound`, `false` otherwise.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, TaskError::NotFound(_))
     }
-}
 
-impl From<serde_json::Error> for TaskError {
-    /// Converts a `serde_json::Error` into a `TaskError`.
-    ///
-    /// This implementation allows serialization and deserialization errors to be seamlessly converted
-    /// into `TaskError::SerdeError` variants, enabling better error handling in task-related operations.
+    /// Whether the error is an I/O failure.
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `error` - The `serde_json::Error` to convert.
+    /// * `bool` - `true` for `TaskError::IoError`, `false` otherwise.
+    pub fn is_io(&self) -> bool {
+        matches!(self, TaskError::IoError(_))
+    }
+
+    /// Formats the error followed by each of its causes that it does not already mention,
+    /// separated by `: `.
     ///
     /// # Returns
     ///
-    /// * `TaskError` - The corresponding `TaskError` variant.
-    fn from(error: serde_json::Error) -> Self {
-        TaskError::SerdeError(error)
+    /// * `String` - The message, e.g. for printing to the user.
+    pub fn chain(&self) -> String {
+        let mut message = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(cause) = source {
+            let cause_message = cause.to_string();
+            if !message.contains(&cause_message) {
+                message.push_str(": ");
+                message.push_str(&cause_message);
+            }
+            source = cause.source();
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    /// Tests the messages of errors without a source.
+    #[test]
+    fn test_display() {
+        assert_eq!(TaskError::NotFound(3).to_string(), "Task with ID 3 not found");
+        assert_eq!(TaskError::InvalidInput("bad".into()).to_string(), "Invalid input - bad");
+        assert_eq!(
+            TaskError::ReadOnlyMode.to_string(),
+            "Read-only mode - the tasks cannot be modified"
+        );
+        assert_eq!(
+            TaskError::Encoding("tasks.json".into()).to_string(),
+            "Encoding error - tasks.json is not valid UTF-8 or UTF-16"
+        );
+        assert_eq!(
+            TaskError::IdSpaceExhausted.to_string(),
+            "ID space exhausted - task 4294967295 already exists, so no new ID is available"
+        );
+        assert!(TaskError::source(&TaskError::NotFound(3)).is_none());
+    }
+
+    /// Tests that wrapped errors are exposed as the source, and mentioned only once in the chain.
+    #[test]
+    fn test_source_chain() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        let error = TaskError::from(io);
+        assert!(error.is_io());
+        assert!(!error.is_not_found());
+        assert_eq!(error.to_string(), "I/O error - no such file");
+        assert_eq!(error.source().unwrap().to_string(), "no such file");
+        assert_eq!(error.chain(), "I/O error - no such file");
+
+        let error = TaskError::from(serde_json::from_str::<u32>("x").unwrap_err());
+        assert!(error.source().is_some());
+        assert!(error.to_string().starts_with("Serialization error -  expected value"));
+    }
+
+    /// Tests that causes not mentioned in the message are appended to the chain.
+    #[test]
+    fn test_chain_appends_causes() {
+        #[derive(Debug, thiserror::Error)]
+        #[error("outer")]
+        struct Outer(#[source] TaskError);
+
+        let inner = std::io::Error::other(Outer(TaskError::NotFound(2)));
+        let error = TaskError::from(inner);
+        assert_eq!(error.chain(), "I/O error - outer: Task with ID 2 not found");
+        assert!(TaskError::NotFound(2).is_not_found());
     }
 }
@@ -0,0 +1,147 @@
+//! Parsing task IDs.
+//!
+//! IDs are often pasted from `list` output or chat, so the parsers here are forgiving: a leading
+//! `#` and trailing punctuation are ignored, e.g. `#12` or `12.`. Commands that accept several
+//! tasks also take ranges and comma or space separated mixtures, e.g. `#3, 5-7 9`.
+
+use std::collections::HashSet;
+
+use crate::error::TaskError;
+
+/// The largest number of IDs a single range may cover, to catch typos such as `1-1000000`.
+const MAX_RANGE_LEN: u32 = 10_000;
+
+/// Parses a single task ID, ignoring a leading `#` and trailing punctuation.
+///
+/// # Arguments
+///
+/// * `token` - The ID as typed, e.g. `#12`.
+///
+/// # Returns
+///
+/// * `Result<u32, TaskError>` - The ID, or `TaskError::InvalidInput` naming the token if it is not a positive integer.
+pub fn parse_id(token: &str) -> Result<u32, TaskError> {
+    let id = token.trim();
+    let id = id.strip_prefix('#').unwrap_or(id).trim_end_matches(['.', ',', ';', ':', ')']);
+    match id.parse::<u32>() {
+        Ok(id) if id > 0 => Ok(id),
+        _ => Err(TaskError::InvalidInput(format!("Invalid task ID '{}'", token.trim()))),
+    }
+}
+
+/// The ID to give a new task: one more than the largest ID in use, or 1 if there is none.
+///
+/// # Arguments
+///
+/// * `ids` - The IDs in use, including those of tasks in the trash.
+///
+/// # Returns
+///
+/// * `Result<u32, TaskError>` - The ID, or `TaskError::IdSpaceExhausted` if `u32::MAX` is in use.
+pub fn next_id(ids: impl IntoIterator<Item = u32>) -> Result<u32, TaskError> {
+    ids.into_iter().max().unwrap_or(0).checked_add(1).ok_or(TaskError::IdSpaceExhausted)
+}
+
+/// Parses a list of task IDs and ranges, separated by commas and/or whitespace.
+///
+/// Each token is an ID, as accepted by `parse_id`, or an inclusive range of two IDs separated by
+/// `-`, e.g. `5-7` or `#5-#7`, covering at most `MAX_RANGE_LEN` IDs. Duplicates are removed,
+/// keeping the first occurrence.
+///
+/// # Arguments
+///
+/// * `input` - The IDs as typed, e.g. `#3, 5-7 9`.
+///
+/// # Returns
+///
+/// * `Result<Vec<u32>, TaskError>` - The IDs in the order given, or `TaskError::InvalidInput` naming the first token that is not a valid ID or range.
+pub fn parse_ids(input: &str) -> Result<Vec<u32>, TaskError> {
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    for token in input.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+        let range = match token.split_once('-') {
+            Some((start, end)) => {
+                let invalid =
+                    || TaskError::InvalidInput(format!("Invalid task ID range '{}'", token));
+                let (start, end) = (
+                    parse_id(start).map_err(|_| invalid())?,
+                    parse_id(end).map_err(|_| invalid())?,
+                );
+                if start > end {
+                    return Err(invalid());
+                }
+                if end - start >= MAX_RANGE_LEN {
+                    return Err(TaskError::InvalidInput(format!(
+                        "Task ID range '{}' covers more than {} tasks",
+                        token, MAX_RANGE_LEN
+                    )));
+                }
+                start..=end
+            }
+            None => {
+                let id = parse_id(token)?;
+                id..=id
+            }
+        };
+        ids.extend(range.filter(|id| seen.insert(*id)));
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the forms of a single ID that are accepted.
+    #[test]
+    fn test_parse_id() {
+        for token in ["12", "#12", "12.", "#12,", " 12 ", "12:", "12)", "#12;"] {
+            assert_eq!(parse_id(token).unwrap(), 12, "{:?}", token);
+        }
+    }
+
+    /// Tests that invalid single IDs are rejected with the token in the message.
+    #[test]
+    fn test_parse_id_invalid() {
+        for token in ["", "#", "0", "-1", "twelve", "1.5", "##12", "99999999999"] {
+            let err = parse_id(token).unwrap_err();
+            assert_eq!(err.to_string(), format!("Invalid input - Invalid task ID '{}'", token));
+        }
+    }
+
+    /// Tests the next ID, and that it does not wrap around after `u32::MAX`.
+    #[test]
+    fn test_next_id() {
+        assert_eq!(next_id([]).unwrap(), 1);
+        assert_eq!(next_id([3, 999_999, 7]).unwrap(), 1_000_000);
+        assert_eq!(next_id([u32::MAX - 1]).unwrap(), u32::MAX);
+        assert!(matches!(next_id([1, u32::MAX]), Err(TaskError::IdSpaceExhausted)));
+    }
+
+    /// Tests lists mixing separators, ranges, and pasted forms.
+    #[test]
+    fn test_parse_ids() {
+        assert_eq!(parse_ids("#3, 5-7 9").unwrap(), vec![3, 5, 6, 7, 9]);
+        assert_eq!(parse_ids("1,2,,3").unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse_ids("#5-#6 4.").unwrap(), vec![5, 6, 4]);
+        assert_eq!(parse_ids("2 1-3 2").unwrap(), vec![2, 1, 3]);
+        assert_eq!(parse_ids("7-7").unwrap(), vec![7]);
+        assert_eq!(parse_ids("").unwrap(), Vec::<u32>::new());
+    }
+
+    /// Tests that the first invalid token is identified.
+    #[test]
+    fn test_parse_ids_invalid() {
+        let message = |input: &str| parse_ids(input).unwrap_err().to_string();
+        assert_eq!(message("3, x, 5"), "Invalid input - Invalid task ID 'x'");
+        assert_eq!(message("3 7-5"), "Invalid input - Invalid task ID range '7-5'");
+        assert_eq!(message("3-"), "Invalid input - Invalid task ID range '3-'");
+        assert_eq!(message("1-2-3"), "Invalid input - Invalid task ID range '1-2-3'");
+        assert_eq!(message("0-2"), "Invalid input - Invalid task ID range '0-2'");
+        assert_eq!(
+            message("1-10001"),
+            "Invalid input - Task ID range '1-10001' covers more than 10000 tasks"
+        );
+        assert_eq!(parse_ids("1-10000").unwrap().len(), 10_000);
+    }
+}
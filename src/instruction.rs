@@ -0,0 +1,227 @@
+//! Parsing single-string instructions.
+//!
+//! `tasg do` takes a whole instruction as one argument, e.g. `tasg do "complete 12"`, so that
+//! launchers such as Alfred, Raycast, or rofi can drive `tasg` through a single entry point. An
+//! instruction is a verb followed by its arguments; free text such as a description does not need
+//! quoting. Only verbs that never ask for confirmation are supported.
+
+use crate::error::TaskError;
+use crate::ids::{parse_id, parse_ids};
+
+/// The verbs `tasg do` understands, in the order they are listed in errors.
+pub const VERBS: &[&str] = &["add", "complete", "delete", "restore", "edit", "touch", "get"];
+
+/// Verbs of `tasg` that ask for confirmation, which `tasg do` always refuses.
+const CONFIRMED_VERBS: &[&str] = &["nuke", "empty-trash"];
+
+/// A parsed instruction.
+///
+/// # Variants
+///
+/// - `Add` - Adds a task with the given description.
+/// - `Complete` - Completes the given tasks.
+/// - `Delete` - Moves the given tasks to the trash.
+/// - `Restore` - Restores a task from the trash.
+/// - `Edit` - Replaces a task's description.
+/// - `Touch` - Bumps a task's update time.
+/// - `Get` - Prints a single field of a task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    /// Adds a task with the given description.
+    Add { description: String },
+
+    /// Completes the given tasks.
+    Complete { ids: Vec<u32> },
+
+    /// Moves the given tasks to the trash.
+    Delete { ids: Vec<u32> },
+
+    /// Restores a task from the trash.
+    Restore { id: u32 },
+
+    /// Replaces a task's description.
+    Edit { id: u32, description: String },
+
+    /// Bumps a task's update time.
+    Touch { id: u32 },
+
+    /// Prints a single field of a task.
+    Get { id: u32, field: String },
+}
+
+/// Parses an instruction such as `complete 12` or `add buy milk`.
+///
+/// The verb is matched case-insensitively. Descriptions are the remaining words joined by single
+/// spaces, and quotes are honoured as in a shell.
+///
+/// # Arguments
+///
+/// * `text` - The instruction.
+///
+/// # Returns
+///
+/// * `Result<Instruction, TaskError>` - The instruction, or `TaskError::InvalidInput` if the verb is unknown or asks for confirmation, or its arguments are missing or invalid.
+pub fn parse(text: &str) -> Result<Instruction, TaskError> {
+    let args = split_args(text)?;
+    let Some((verb, rest)) = args.split_first() else {
+        return Err(TaskError::InvalidInput(format!(
+            "Empty instruction; expected one of: {}",
+            VERBS.join(", ")
+        )));
+    };
+    let verb = verb.to_lowercase();
+    let usage = |arguments: &str| TaskError::InvalidInput(format!("Usage: {} {}", verb, arguments));
+    let text = |words: &[String]| Some(words.join(" ")).filter(|t| !t.trim().is_empty());
+
+    Ok(match (verb.as_str(), rest) {
+        ("add", words) => {
+            Instruction::Add { description: text(words).ok_or_else(|| usage("<description>"))? }
+        }
+        ("complete", ids) if !ids.is_empty() => {
+            Instruction::Complete { ids: parse_ids(&ids.join(" "))? }
+        }
+        ("delete", ids) if !ids.is_empty() => {
+            Instruction::Delete { ids: parse_ids(&ids.join(" "))? }
+        }
+        ("complete" | "delete", _) => return Err(usage("<ids>")),
+        ("restore", [id]) => Instruction::Restore { id: parse_id(id)? },
+        ("touch", [id]) => Instruction::Touch { id: parse_id(id)? },
+        ("restore" | "touch", _) => return Err(usage("<id>")),
+        ("edit", [id, words @ ..]) => Instruction::Edit {
+            id: parse_id(id)?,
+            description: text(words).ok_or_else(|| usage("<id> <description>"))?,
+        },
+        ("edit", _) => return Err(usage("<id> <description>")),
+        ("get", [id, field]) => Instruction::Get { id: parse_id(id)?, field: field.clone() },
+        ("get", _) => return Err(usage("<id> <field>")),
+        (verb, _) if CONFIRMED_VERBS.contains(&verb) => {
+            return Err(TaskError::InvalidInput(format!(
+                "'{}' asks for confirmation, which `tasg do` never gives; run `tasg {}` instead",
+                verb, verb
+            )))
+        }
+        (verb, _) => {
+            return Err(TaskError::InvalidInput(format!(
+                "Unknown verb '{}'; expected one of: {}",
+                verb,
+                VERBS.join(", ")
+            )))
+        }
+    })
+}
+
+/// Splits a command line into arguments, honouring single and double quotes.
+///
+/// # Arguments
+///
+/// * `line` - The command line to split.
+///
+/// # Returns
+///
+/// * `Result<Vec<String>, TaskError>` - The arguments, or `TaskError::InvalidInput` if a quote is left unclosed.
+pub fn split_args(line: &str) -> Result<Vec<String>, TaskError> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(TaskError::InvalidInput("Unclosed quote".into()));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that quoted arguments are kept together.
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            split_args(r#"add "Buy milk"  'and bread'"#).unwrap(),
+            vec!["add", "Buy milk", "and bread"]
+        );
+        assert_eq!(split_args(r#"add """#).unwrap(), vec!["add", ""]);
+        assert!(split_args(r#"add "Buy milk"#).is_err());
+    }
+
+    /// Tests each verb with valid arguments.
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("add buy   milk").unwrap(),
+            Instruction::Add { description: String::from("buy milk") }
+        );
+        assert_eq!(
+            parse("add 'buy  milk'").unwrap(),
+            Instruction::Add { description: String::from("buy  milk") }
+        );
+        assert_eq!(parse("Complete 12").unwrap(), Instruction::Complete { ids: vec![12] });
+        assert_eq!(
+            parse("complete #3, 5-6").unwrap(),
+            Instruction::Complete { ids: vec![3, 5, 6] }
+        );
+        assert_eq!(parse("delete 4 7").unwrap(), Instruction::Delete { ids: vec![4, 7] });
+        assert_eq!(parse("restore #4").unwrap(), Instruction::Restore { id: 4 });
+        assert_eq!(parse("touch 4").unwrap(), Instruction::Touch { id: 4 });
+        assert_eq!(
+            parse("edit 2 call the bank").unwrap(),
+            Instruction::Edit { id: 2, description: String::from("call the bank") }
+        );
+        assert_eq!(
+            parse("get 2 completed").unwrap(),
+            Instruction::Get { id: 2, field: String::from("completed") }
+        );
+    }
+
+    /// Tests that missing or invalid arguments are rejected with a usage message.
+    #[test]
+    fn test_parse_invalid_arguments() {
+        let message = |text: &str| parse(text).unwrap_err().to_string();
+        assert_eq!(message("add"), "Invalid input - Usage: add <description>");
+        assert_eq!(message("complete"), "Invalid input - Usage: complete <ids>");
+        assert_eq!(message("restore 1 2"), "Invalid input - Usage: restore <id>");
+        assert_eq!(message("edit 2"), "Invalid input - Usage: edit <id> <description>");
+        assert_eq!(message("get 2"), "Invalid input - Usage: get <id> <field>");
+        assert_eq!(message("touch x"), "Invalid input - Invalid task ID 'x'");
+        assert_eq!(message("add 'milk"), "Invalid input - Unclosed quote");
+    }
+
+    /// Tests that unknown and confirming verbs are refused.
+    #[test]
+    fn test_parse_unsupported_verbs() {
+        let message = |text: &str| parse(text).unwrap_err().to_string();
+        assert_eq!(
+            message("snooze 7 until friday"),
+            "Invalid input - Unknown verb 'snooze'; expected one of: add, complete, delete, restore, edit, touch, get"
+        );
+        assert!(message("").starts_with("Invalid input - Empty instruction"));
+        assert!(message("nuke").contains("asks for confirmation"));
+        assert!(message("empty-trash").contains("asks for confirmation"));
+    }
+}
@@ -1,4 +1,18 @@
+pub mod apply;
 pub mod cli;
+pub mod completions;
+pub mod config;
+pub mod diff;
+pub mod duration;
 pub mod error;
+pub mod ids;
+pub mod instruction;
+pub mod path_template;
+pub mod render;
+pub mod state;
 pub mod store;
 pub mod task;
+pub mod timestamp;
+pub mod topics;
+pub mod tutorial;
+pub mod undo;
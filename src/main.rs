@@ -2,13 +2,27 @@
 //!
 //! `tasg` is a command-line tool for managing tasks. It provides functionalities to add, list, complete, and delete tasks. The tasks are stored in a JSON file located in the user's configuration directory.
 
-use std::io::{self, Write};
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
 
 use clap::Parser;
 use tasg::{
-    cli::{Cli, Commands},
+    apply::ApplyOptions,
+    cli::{Cli, Commands, CompletionsCommand},
+    completions,
+    config::Config,
     error::TaskError,
-    store::{JsonStore, Store},
+    ids::{self, parse_ids},
+    instruction::{self, Instruction},
+    path_template,
+    render::{self, Column, Output, RenderOptions},
+    state::State,
+    store::{self, copy_permissions, BackupPolicy, JsonStore, ReadOnlyStore, Store},
+    task::{ChangedField, Task},
+    timestamp::TimestampFormat,
+    topics,
+    tutorial::{self, Outcome},
+    undo::{self, History},
 };
 
 /// Gets the default path for the tasks file.
@@ -31,6 +45,60 @@ fn get_default_tasks_file() -> std::path::PathBuf {
     path
 }
 
+/// Gets the path template of the tasks file.
+///
+/// This is the value of the `TASG_FILE` environment variable if it is set, the `tasks_file` key
+/// of the config file, with a leading `~/` standing for the home directory, or the default path
+/// otherwise. The template may contain `strftime` tokens; see `path_template::expand`.
+///
+/// # Arguments
+///
+/// * `config` - The defaults from the config file.
+///
+/// # Returns
+///
+/// * `String` - The path template of the tasks file.
+fn tasks_file_template(config: &Config) -> String {
+    if let Ok(template) = std::env::var("TASG_FILE") {
+        return template;
+    }
+    match (&config.tasks_file, dirs::home_dir()) {
+        (Some(template), Some(home)) if template.starts_with("~/") => {
+            home.join(&template[2..]).to_string_lossy().to_string()
+        }
+        (Some(template), _) => template.clone(),
+        (None, _) => get_default_tasks_file().to_string_lossy().to_string(),
+    }
+}
+
+/// Gets the path of the config file.
+///
+/// This is the value of the `TASG_CONFIG` environment variable if it is set, or `config.toml`
+/// next to the default tasks file otherwise.
+///
+/// # Returns
+///
+/// * `PathBuf` - The path of the config file, which may not exist.
+fn config_file() -> std::path::PathBuf {
+    std::env::var_os("TASG_CONFIG")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| get_default_tasks_file().with_file_name("config.toml"))
+}
+
+/// Gets the path of the state file.
+///
+/// This is the value of the `TASG_STATE` environment variable if it is set, or `state.json`
+/// next to the default tasks file otherwise.
+///
+/// # Returns
+///
+/// * `PathBuf` - The path of the state file, which may not exist.
+fn state_file() -> std::path::PathBuf {
+    std::env::var_os("TASG_STATE")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| get_default_tasks_file().with_file_name("state.json"))
+}
+
 /// Ensures that the tasks file exists.
 ///
 /// This function checks if the tasks JSON file exists at the given path. If it does not exist, the function creates the necessary directories and an empty tasks file.
@@ -56,6 +124,601 @@ fn ensure_tasks_file_exists(path: &str) -> Result<(), TaskError> {
     Ok(())
 }
 
+/// Builds the render options for the current terminal.
+///
+/// Colors are used when standard output is a terminal, unless the `NO_COLOR` environment
+/// variable is set.
+///
+/// # Arguments
+///
+/// * `config` - The defaults from the config file, for the date format and colors.
+/// * `columns` - The table columns, in order.
+/// * `width` - The total width of the table, if fixed.
+///
+/// # Returns
+///
+/// * `RenderOptions` - The options to render tasks with.
+fn render_options(config: &Config, columns: Vec<Column>, width: Option<usize>) -> RenderOptions {
+    let color = config.color.unwrap_or_else(|| io::stdout().is_terminal())
+        && std::env::var_os("NO_COLOR").is_none();
+    let defaults = RenderOptions::default();
+    let date_format = config.date_format.clone().unwrap_or(defaults.date_format);
+    RenderOptions { width, color, columns, date_format, ..defaults }
+}
+
+/// Gets the width of the terminal from the `COLUMNS` environment variable.
+///
+/// # Returns
+///
+/// * `Option<usize>` - The width, or `None` if standard output is not a terminal or `COLUMNS` is not a number.
+fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    std::env::var("COLUMNS").ok()?.trim().parse().ok()
+}
+
+/// Finds a task by its ID, including completed tasks and tasks in the trash.
+///
+/// # Arguments
+///
+/// * `store` - The store to search.
+/// * `id` - The ID of the task.
+///
+/// # Returns
+///
+/// * `Result<Task, TaskError>` - The task, or `TaskError::NotFound` if no task has that ID.
+fn find_task(store: &dyn Store, id: u32) -> Result<Task, TaskError> {
+    store
+        .list(true)?
+        .into_iter()
+        .chain(store.list_trash()?)
+        .find(|t| t.id == id)
+        .ok_or(TaskError::NotFound(id))
+}
+
+/// Refuses to edit a completed task unless allowed, because reports treat completed tasks as
+/// history that should not change retroactively.
+///
+/// # Arguments
+///
+/// * `store` - The store holding the task.
+/// * `id` - The ID of the task to edit.
+/// * `allowed` - Whether completed tasks may be edited, from `--completed-ok` or the config file.
+///
+/// # Returns
+///
+/// * `Result<(), TaskError>` - `Ok(())` if the task may be edited or is not found, or `TaskError::InvalidInput` suggesting how to proceed if it is completed.
+fn check_editable(store: &dyn Store, id: u32, allowed: bool) -> Result<(), TaskError> {
+    if !allowed && store.list(true)?.iter().any(|t| t.id == id && t.is_completed()) {
+        return Err(TaskError::InvalidInput(format!(
+            "Task {} is completed, and completed tasks are kept as history; reopen it first with `tasg set {} completed false`, or pass --completed-ok (or set edit_completed = true in the config file) to edit it anyway",
+            id, id
+        )));
+    }
+    Ok(())
+}
+
+/// Finds the time a given length of time ago.
+///
+/// # Arguments
+///
+/// * `duration` - How long ago.
+/// * `option` - The option the duration was given to, for the error message.
+///
+/// # Returns
+///
+/// * `Result<chrono::DateTime<chrono::Local>, TaskError>` - The time, or `TaskError::InvalidInput` naming the option if it is too far in the past to represent.
+fn time_ago(
+    duration: chrono::Duration,
+    option: &str,
+) -> Result<chrono::DateTime<chrono::Local>, TaskError> {
+    chrono::Local::now().checked_sub_signed(duration).ok_or_else(|| {
+        TaskError::InvalidInput(format!("The duration given to {} is too long", option))
+    })
+}
+
+/// Opens the store at the given path.
+///
+/// Timestamps are written in the format given by the `TASG_TIMESTAMP_FORMAT` environment
+/// variable. Saving through a symlink keeps the link unless `TASG_PRESERVE_SYMLINKS` is `0` or
+/// `false`; when that variable is not set, the `preserve_symlinks` config key decides.
+///
+/// # Arguments
+///
+/// * `path` - A string slice representing the path to the tasks file.
+/// * `read_only` - Whether to wrap the store in a `ReadOnlyStore`, rejecting every mutation.
+/// * `dry_run` - Whether to keep changes in memory instead of saving them.
+/// * `config` - The defaults from the config file.
+///
+/// # Returns
+///
+/// * `Result<Box<dyn Store>, TaskError>` - The store to run commands against, or `TaskError::InvalidInput` if `TASG_TIMESTAMP_FORMAT` is invalid.
+fn open_store(
+    path: &str,
+    read_only: bool,
+    dry_run: bool,
+    config: &Config,
+) -> Result<Box<dyn Store>, TaskError> {
+    let preserve_symlinks = match std::env::var("TASG_PRESERVE_SYMLINKS") {
+        Ok(v) => !(v == "0" || v == "false"),
+        Err(_) => config.preserve_symlinks.unwrap_or(true),
+    };
+    let store = JsonStore::new(path)
+        .with_timestamp_format(TimestampFormat::from_env()?)
+        .with_preserve_symlinks(preserve_symlinks)
+        .with_dry_run(dry_run);
+    if read_only {
+        Ok(Box::new(ReadOnlyStore::new(store)))
+    } else {
+        Ok(Box::new(store))
+    }
+}
+
+/// Runs the interactive tutorial.
+///
+/// This function walks the user through each step in `tutorial::STEPS`. Commands are read from
+/// standard input and executed against a throwaway task list in a fresh temporary directory, which
+/// is removed once the tutorial ends. Only the commands in `tutorial::COMMANDS` can be run. Each command is checked against the current step, and a hint
+/// is printed until the step is completed.
+///
+/// # Returns
+///
+/// * `Result<(), TaskError>` - Returns `Ok(())` when the tutorial is completed or exited. Returns a `TaskError` if the temporary task list cannot be created or read.
+///
+/// # Errors
+///
+/// * This function will return an error if there is an issue with the temporary task list or standard input.
+fn run_tutorial() -> Result<(), TaskError> {
+    let dir = tempfile::Builder::new().prefix("tasg-tutorial-").tempdir()?;
+    let path = dir.path().join("tasks.json").to_string_lossy().to_string();
+    ensure_tasks_file_exists(&path)?;
+    run_tutorial_steps(&path)
+}
+
+/// Runs the tutorial steps against the task list at the given path.
+///
+/// # Arguments
+///
+/// * `path` - A string slice representing the path to the throwaway tasks file.
+///
+/// # Returns
+///
+/// * `Result<(), TaskError>` - Returns `Ok(())` when the tutorial is completed or exited, or a `TaskError` if an error occurs.
+fn run_tutorial_steps(path: &str) -> Result<(), TaskError> {
+    println!("Welcome to the tasg tutorial! Your real tasks will not be touched.");
+    println!("Type the commands as shown (the leading `tasg` is optional), or `quit` to exit.");
+
+    for (index, step) in tutorial::STEPS.iter().enumerate() {
+        println!("\nStep {}/{}: {}", index + 1, tutorial::STEPS.len(), step.title);
+        println!("  {}", step.explanation);
+        println!("  Try: {}", step.example);
+
+        loop {
+            print!("tasg> ");
+            io::stdout().flush()?;
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line)? == 0 {
+                println!("\nTutorial exited.");
+                return Ok(());
+            }
+
+            let mut args = match instruction::split_args(&line) {
+                Ok(args) => args,
+                Err(e) => {
+                    println!("Error: {}", e.chain());
+                    continue;
+                }
+            };
+            if args.first().map(String::as_str) == Some("tasg") {
+                args.remove(0);
+            }
+            match args.first().map(String::as_str) {
+                None => continue,
+                Some("quit" | "exit") => {
+                    println!("Tutorial exited.");
+                    return Ok(());
+                }
+                Some("tutorial") => {
+                    println!("You are already in the tutorial!");
+                    continue;
+                }
+                Some(command) if !tutorial::COMMANDS.contains(&command) => {
+                    println!(
+                        "Only these commands can be run in the tutorial: {}",
+                        tutorial::COMMANDS.join(", ")
+                    );
+                    println!("Hint: {}", step.hint);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let cli = match Cli::try_parse_from(
+                std::iter::once("tasg".to_string()).chain(args.clone()),
+            ) {
+                Ok(cli) => cli,
+                Err(e) => {
+                    println!("{}", e);
+                    continue;
+                }
+            };
+            let store = open_store(path, cli.read_only, cli.dry_run, &Config::default())?;
+            let before = store.list(true)?;
+            if let Err(e) = run(cli, store.as_ref(), &Config::default(), None, None) {
+                println!("Error: {}", e.chain());
+            }
+            let after = store.list(true)?;
+
+            match step.evaluate(&args, &before, &after) {
+                Outcome::Passed => {
+                    println!("Well done!");
+                    break;
+                }
+                Outcome::WrongCommand | Outcome::NotYet => println!("Hint: {}", step.hint),
+            }
+        }
+    }
+
+    println!("\nYou have completed the tutorial. Happy tasking!");
+    Ok(())
+}
+
+/// Splits text read from standard input into a task's description and notes.
+///
+/// The first line is the description, and the remaining lines, if any, are the notes. Trailing
+/// whitespace is trimmed from every line, and blank lines between the description and the notes
+/// are dropped.
+///
+/// # Arguments
+///
+/// * `text` - The text read from standard input.
+///
+/// # Returns
+///
+/// * `Result<(String, Option<String>), TaskError>` - The description and notes, or `TaskError::InvalidInput` if the text is empty.
+fn split_description(text: &str) -> Result<(String, Option<String>), TaskError> {
+    if text.trim().is_empty() {
+        return Err(TaskError::InvalidInput("No description given on standard input".into()));
+    }
+    let mut lines = text.trim_end().lines().map(str::trim_end);
+    let description = lines.next().unwrap_or_default().trim().to_string();
+    let notes: Vec<&str> = lines.skip_while(|line| line.is_empty()).collect();
+    Ok((description, Some(notes.join("\n")).filter(|n| !n.is_empty())))
+}
+
+/// Prompts for a task's description on the terminal.
+///
+/// # Returns
+///
+/// * `Result<String, TaskError>` - The description, or `TaskError::InvalidInput` if standard input is closed before one is given.
+fn prompt_description() -> Result<String, TaskError> {
+    print!("Description: ");
+    io::stdout().flush()?;
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        println!();
+        return Err(TaskError::InvalidInput("No description given".into()));
+    }
+    Ok(line.trim().to_string())
+}
+
+/// Edits a task's fields one by one on the terminal.
+///
+/// Each field that `set` accepts is prompted for with its current value in brackets; an empty
+/// answer keeps it. Every answer is checked before anything is saved, and the changes are then
+/// saved in a single write. Closing standard input, e.g. with Ctrl-D, aborts the edit without
+/// saving.
+///
+/// # Arguments
+///
+/// * `store` - The store holding the task.
+/// * `id` - The ID of the task to edit.
+///
+/// # Returns
+///
+/// * `Result<(), TaskError>` - `Ok(())` if the task was edited or the edit was aborted, or `TaskError::NotFound` if there is no such task, or `TaskError::InvalidInput` if an answer is not a valid value or the task would depend on a missing or trashed task, or on a task that depends on it.
+fn edit_interactively(store: &dyn Store, id: u32) -> Result<(), TaskError> {
+    let mut tasks = store.list(true)?;
+    tasks.extend(store.list_trash()?);
+    let index =
+        tasks.iter().position(|t| t.id == id && !t.is_deleted()).ok_or(TaskError::NotFound(id))?;
+    let task = tasks[index].clone();
+    let mut edited = task.clone();
+    let mut changed = Vec::new();
+    // `completed` is only a shortcut for the `done` status, which is already asked for.
+    for &field in Task::SETTABLE_FIELDS.iter().filter(|&&f| f != "completed") {
+        let current = task.get_field(field)?;
+        let shown = match current.split_once('\n') {
+            Some((first, _)) => format!("{}…", first),
+            None => current.clone(),
+        };
+        print!("{} [{}]: ", field, shown);
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            println!();
+            println!("Edit aborted; task {} was not changed", id);
+            return Ok(());
+        }
+        let answer = line.trim();
+        if !answer.is_empty() && answer != current {
+            edited.set_field(field, answer)?;
+            changed.push(field);
+        }
+    }
+    if changed.is_empty() {
+        println!("Task {} was not changed", id);
+        return Ok(());
+    }
+    if let Some(depends_on) = edited.depends_on.filter(|&d| task.depends_on != Some(d)) {
+        store::check_dependency(&tasks, id, depends_on)?;
+    }
+    edited.updated_at = chrono::Local::now();
+    tasks[index] = edited;
+    store.replace(&tasks)?;
+    println!("Updated {} of task {}", changed.join(", "), id);
+    Ok(())
+}
+
+/// Adds a task, allocating the next free ID.
+///
+/// # Arguments
+///
+/// * `store` - The store to add the task to.
+/// * `description` - The description of the task.
+/// * `notes` - Longer notes about the task, if any.
+/// * `idempotency_key` - A key identifying this add; if a task already has it, no task is added.
+/// * `depends_on_last` - Whether the task depends on the most recently created task.
+///
+/// # Returns
+///
+/// * `Result<(u32, bool), TaskError>` - The task's ID, and whether it already existed with the same idempotency key, or `TaskError::InvalidInput` if the description or key is empty, the key is used by a task with a different description, or there is no previous task to depend on.
+fn add_task(
+    store: &dyn Store,
+    description: String,
+    notes: Option<String>,
+    idempotency_key: Option<String>,
+    depends_on_last: bool,
+) -> Result<(u32, bool), TaskError> {
+    if description.trim().is_empty() {
+        return Err(TaskError::InvalidInput("Description cannot be empty".into()));
+    }
+    if idempotency_key.as_deref().is_some_and(|key| key.trim().is_empty()) {
+        return Err(TaskError::InvalidInput("Idempotency key cannot be empty".into()));
+    }
+    let mut tasks = store.list(true)?;
+    tasks.extend(store.list_trash()?);
+    if let Some(key) = &idempotency_key {
+        if let Some(existing) =
+            tasks.iter().find(|t| t.idempotency_key.as_deref() == Some(key.as_str()))
+        {
+            if existing.description != description {
+                return Err(TaskError::InvalidInput(format!(
+                    "Idempotency key '{}' is already used by task {} with a different description",
+                    key, existing.id
+                )));
+            }
+            return Ok((existing.id, true));
+        }
+    }
+    let depends_on = if depends_on_last {
+        let last = tasks
+            .iter()
+            .filter(|t| !t.is_deleted())
+            .max_by_key(|t| (t.created_at, t.id))
+            .ok_or_else(|| {
+                TaskError::InvalidInput("There is no previous task to depend on".into())
+            })?;
+        Some(last.id)
+    } else {
+        None
+    };
+    let id = ids::next_id(tasks.iter().map(|t| t.id))?;
+    let mut task = Task::new(id, description);
+    task.notes = notes;
+    task.idempotency_key = idempotency_key;
+    task.depends_on = depends_on;
+    store.add(task)?;
+    Ok((id, false))
+}
+
+/// Carries out an instruction given to `tasg do`, printing a single line describing the result.
+///
+/// # Arguments
+///
+/// * `store` - The store to run the instruction against.
+/// * `config` - The defaults from the config file, for whether completed tasks may be edited.
+/// * `instruction` - The parsed instruction.
+///
+/// # Returns
+///
+/// * `Result<(), TaskError>` - `Ok(())` if the instruction was carried out, or the error from the store, e.g. `TaskError::NotFound`.
+fn run_instruction(
+    store: &dyn Store,
+    config: &Config,
+    instruction: Instruction,
+) -> Result<(), TaskError> {
+    let list = |ids: &[u32]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",");
+    match instruction {
+        Instruction::Add { description } => {
+            let (id, _) = add_task(store, description, None, None, false)?;
+            println!("added {}", id);
+        }
+        Instruction::Complete { ids } => {
+            store.complete_many(&ids)?;
+            println!("completed {}", list(&ids));
+        }
+        Instruction::Delete { ids } => {
+            let existing = store.list(true)?;
+            if let Some(&missing) = ids.iter().find(|id| !existing.iter().any(|t| t.id == **id)) {
+                return Err(TaskError::NotFound(missing));
+            }
+            for &id in &ids {
+                store.trash(id)?;
+            }
+            println!("trashed {}", list(&ids));
+        }
+        Instruction::Restore { id } => {
+            store.restore(id)?;
+            println!("restored {}", id);
+        }
+        Instruction::Edit { id, description } => {
+            check_editable(store, id, config.edit_completed)?;
+            store.edit(id, Some(description))?;
+            println!("edited {}", id);
+        }
+        Instruction::Touch { id } => {
+            store.touch(id)?;
+            println!("touched {}", id);
+        }
+        Instruction::Get { id, field } => {
+            println!("{}", find_task(store, id)?.get_field(&field)?);
+        }
+    }
+    Ok(())
+}
+
+/// Tasks left out of `list` by one of its filters.
+///
+/// # Fields
+///
+/// - `reason` - Why the tasks were left out, e.g. `completed`.
+/// - `count` - How many tasks were left out.
+/// - `hint` - How to show them, if there is a flag for it.
+struct Excluded {
+    /// Why the tasks were left out, e.g. `completed`.
+    reason: String,
+
+    /// How many tasks were left out.
+    count: usize,
+
+    /// How to show them, if there is a flag for it.
+    hint: Option<&'static str>,
+}
+
+/// Keeps the tasks that pass a filter of `list`, counting the ones left out.
+///
+/// # Arguments
+///
+/// * `tasks` - The tasks to filter.
+/// * `reason` - Why tasks that fail the filter are left out.
+/// * `hint` - How to show the tasks that are left out, if there is a flag for it.
+/// * `keep` - The filter.
+///
+/// # Returns
+///
+/// * `Excluded` - The tasks that were left out, and why.
+fn exclude(
+    tasks: &mut Vec<Task>,
+    reason: String,
+    hint: Option<&'static str>,
+    keep: impl Fn(&Task) -> bool,
+) -> Excluded {
+    let before = tasks.len();
+    tasks.retain(keep);
+    Excluded { reason, count: before - tasks.len(), hint }
+}
+
+/// Explains why `list` found no tasks: how many were left out by each filter, and how to show
+/// them.
+///
+/// # Arguments
+///
+/// * `excluded` - The tasks left out by each filter, in the order the filters were applied.
+///
+/// # Returns
+///
+/// * `String` - The message, with a trailing newline.
+fn no_tasks_found(excluded: &[Excluded]) -> String {
+    let excluded: Vec<&Excluded> = excluded.iter().filter(|e| e.count > 0).collect();
+    if excluded.is_empty() {
+        return String::from("No tasks found\n");
+    }
+    let total: usize = excluded.iter().map(|e| e.count).sum();
+    let reasons: Vec<String> =
+        excluded.iter().map(|e| format!("{} {}", e.count, e.reason)).collect();
+    let mut text = format!("No tasks found; {} hidden: {}\n", total, reasons.join(", "));
+    for hint in excluded.iter().filter_map(|e| e.hint) {
+        text.push_str(hint);
+        text.push('\n');
+    }
+    text
+}
+
+/// Runs a command, recording the tasks as they were before it in the undo history if it
+/// changed them.
+///
+/// Failing to read or write the history never fails the command; a warning is printed instead,
+/// and a corrupt history is replaced by a new one.
+///
+/// # Arguments
+///
+/// * `store` - The store the command runs against.
+/// * `history_path` - The undo history file.
+/// * `limit` - The number of changes to keep in the history.
+/// * `command` - The command to run.
+///
+/// # Returns
+///
+/// * `Result<(), TaskError>` - The result of the command.
+fn run_recorded(
+    store: &dyn Store,
+    history_path: &std::path::Path,
+    limit: usize,
+    command: impl FnOnce() -> Result<(), TaskError>,
+) -> Result<(), TaskError> {
+    let before = undo::snapshot(store).ok();
+    let result = command();
+    let Some(before) = before else {
+        return result;
+    };
+    if undo::snapshot(store).is_ok_and(|after| after == before) {
+        return result;
+    }
+    let mut history = History::load(history_path).unwrap_or_else(|e| {
+        eprintln!("Warning: {}; starting a new one", e);
+        History::default()
+    });
+    history.record(before, limit);
+    let created = !history_path.exists();
+    if let Err(e) = history.save(history_path) {
+        eprintln!("Warning: cannot save the undo history: {}", e);
+    } else if created {
+        let _ = copy_permissions(std::path::Path::new(store.path()), history_path);
+    }
+    result
+}
+
+/// Runs a command against a store in a dry run, then prints the changes it would have saved to
+/// standard error, one per line as in `tasg diff`.
+///
+/// # Arguments
+///
+/// * `store` - The store the command runs against, keeping its changes in memory.
+/// * `command` - The command to run.
+///
+/// # Returns
+///
+/// * `Result<(), TaskError>` - The result of the command.
+fn run_dry(
+    store: &dyn Store,
+    command: impl FnOnce() -> Result<(), TaskError>,
+) -> Result<(), TaskError> {
+    let before = store.list(true)?;
+    command()?;
+    let diffs = tasg::diff::diff(&before, &store.list(true)?);
+    if diffs.is_empty() {
+        eprintln!("Dry run: nothing would change");
+    } else {
+        eprintln!("Dry run: these changes were not saved:");
+        for diff in diffs {
+            eprintln!("{}", diff);
+        }
+    }
+    Ok(())
+}
+
 /// Runs the CLI commands provided by the user.
 ///
 /// This function executes the command specified by the user via the CLI. The available commands are `Add`, `List`, `Complete`, and `Delete`.
@@ -63,7 +726,10 @@ fn ensure_tasks_file_exists(path: &str) -> Result<(), TaskError> {
 /// # Arguments
 ///
 /// * `cli` - A `Cli` struct containing the parsed command-line arguments.
-/// * `store` - A `JsonStore` instance responsible for managing the tasks data.
+/// * `store` - A `Store` instance responsible for managing the tasks data.
+/// * `config` - The defaults from the config file.
+/// * `state_path` - The state file recording when tasks were last listed, or `None` to neither read nor record it.
+/// * `history_path` - The undo history file, or `None` if changes cannot be undone.
 ///
 /// # Returns
 ///
@@ -72,71 +738,553 @@ fn ensure_tasks_file_exists(path: &str) -> Result<(), TaskError> {
 /// # Errors
 ///
 /// * This function will return an error if there is an issue with adding, listing, completing, or deleting a task.
-fn run(cli: Cli, store: JsonStore) -> Result<(), TaskError> {
+fn run(
+    cli: Cli,
+    store: &dyn Store,
+    config: &Config,
+    state_path: Option<&std::path::Path>,
+    history_path: Option<&std::path::Path>,
+) -> Result<(), TaskError> {
+    let plain = cli.output.or(config.output) == Some(Output::Plain);
     match cli.command {
-        Commands::Add { description } => {
-            if description.trim().is_empty() {
-                return Err(TaskError::InvalidInput("Description cannot be empty".into()));
+        Commands::Add { description, stdin_description, idempotency_key, depends_on_last } => {
+            let (description, notes) = match description {
+                Some(description) => (description, None),
+                None if stdin_description => split_description(&io::read_to_string(io::stdin())?)?,
+                None if io::stdin().is_terminal() => (prompt_description()?, None),
+                None => return Err(TaskError::InvalidInput(
+                    "A description is required; pass it as an argument or use --stdin-description"
+                        .into(),
+                )),
+            };
+            let (id, replayed) =
+                add_task(store, description, notes, idempotency_key, depends_on_last)?;
+            if replayed {
+                println!("{}", id);
             }
-            let id = store.list(true)?.len() as u32 + 1;
-            let task = tasg::task::Task::new(id, description);
-            store.add(task)?;
         }
-        Commands::List { all } => {
-            let tasks = store.list(all)?;
-            if tasks.is_empty() {
-                println!("No tasks found");
+        Commands::List {
+            all,
+            width,
+            fields,
+            json,
+            hide_completed_column,
+            highlight_changed,
+            completed_between,
+            since_last_run,
+            changed_since,
+            field,
+            compact,
+            borders,
+            truncate,
+        } => {
+            let all = all || config.list_all;
+            let viewed_at = chrono::Local::now();
+            let mut state = state_path.map(State::load).transpose()?;
+            let mut columns = if !fields.is_empty() {
+                fields.iter().map(|f| f.parse()).collect::<Result<Vec<Column>, _>>()?
+            } else if json {
+                Column::FIELDS.to_vec()
+            } else if all {
+                vec![
+                    Column::Id,
+                    Column::Description,
+                    Column::CreatedAt,
+                    Column::Status,
+                    Column::Completed,
+                ]
+            } else {
+                RenderOptions::default().columns
+            };
+            if hide_completed_column {
+                columns.retain(|c| *c != Column::Completed);
+            }
+            let options = RenderOptions {
+                highlight_since: highlight_changed
+                    .map(|d| time_ago(d, "--highlight-changed"))
+                    .transpose()?,
+                borders,
+                truncate: truncate.map(|n| n as usize),
+                ..render_options(config, columns, width)
+            };
+            if let Some(width) = width {
+                if width < options.min_width() {
+                    return Err(TaskError::InvalidInput(format!(
+                        "Width must be at least {}",
+                        options.min_width()
+                    )));
+                }
+            }
+            let mut tasks = store.list(true)?;
+            let mut excluded = Vec::new();
+            match completed_between[..] {
+                [start, end] if start > end => {
+                    return Err(TaskError::InvalidInput(format!(
+                        "Start date {} is after end date {}",
+                        start, end
+                    )));
+                }
+                [start, end] => {
+                    let reason = format!("not completed between {} and {}", start, end);
+                    excluded.push(exclude(&mut tasks, reason, None, |t| {
+                        t.completed_between(start, end)
+                    }));
+                }
+                _ if !all && field != Some(ChangedField::Completed) => {
+                    let hint = Some("Use --all to show completed tasks");
+                    excluded
+                        .push(exclude(&mut tasks, "completed".into(), hint, |t| !t.is_completed()));
+                }
+                _ => {}
+            }
+            let last_viewed = state.as_ref().and_then(|s| s.last_viewed(store.path()));
+            if let Some(at) = last_viewed.filter(|_| since_last_run) {
+                let reason = String::from("unchanged since the last run");
+                excluded.push(exclude(&mut tasks, reason, None, |t| {
+                    t.created_at > at || t.updated_at > at
+                }));
+            }
+            if let Some(duration) = changed_since {
+                let since = time_ago(duration, "--changed-since")?;
+                let field = field.unwrap_or_default();
+                let change = match field {
+                    ChangedField::Any => "unchanged",
+                    ChangedField::Description => "description unchanged",
+                    ChangedField::Completed => "not completed",
+                };
+                let reason = format!("{} since {}", change, since.format("%Y-%m-%d %H:%M"));
+                excluded.push(exclude(&mut tasks, reason, None, |t| {
+                    t.changed_at(field).is_some_and(|at| at >= since)
+                }));
+            }
+            if json {
+                print!("{}", render::json(&tasks, &options));
+            } else if tasks.is_empty() {
+                print!("{}", no_tasks_found(&excluded));
+            } else if compact {
+                print!("{}", render::compact(&tasks, &options));
+            } else if plain {
+                print!("{}", render::plain(&tasks, &options, chrono::Local::now()));
+            } else {
+                print!("{}", render::table(&tasks, &options));
+            }
+            if let (Some(state), Some(path)) = (&mut state, state_path) {
+                if !cli.read_only && !cli.dry_run {
+                    state.record_view(store.path(), viewed_at);
+                    state.save(path)?;
+                }
+            }
+        }
+        Commands::Show { id, width, json } => {
+            let task = find_task(store, id)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&task)?);
+            } else if plain {
+                let options = render_options(config, Vec::new(), None);
+                print!("{}", render::plain_detail(&task, &options, chrono::Local::now()));
+            } else {
+                let width = width.or_else(terminal_width);
+                print!("{}", render::detail(&task, &render_options(config, Vec::new(), width)));
+            }
+        }
+        Commands::Count { json } => {
+            let counts = store.count()?;
+            if json {
+                println!("{}", serde_json::to_string(&counts)?);
+            } else if plain {
+                print!("{}", render::plain_counts(&counts));
             } else {
                 println!(
-                    "{:<5} {:<50} {:<20} {}",
-                    "ID",
-                    "Description",
-                    "Created At",
-                    if all { "Completed" } else { "" }
+                    "{} task(s): {} completed, {} pending",
+                    counts.total, counts.completed, counts.pending
                 );
-                for task in tasks {
-                    println!(
-                        "{:<5} {:<50} {:<20} {}",
-                        task.id,
-                        task.description,
-                        task.created_at.format("%Y-%m-%d %H:%M:%S"),
-                        if all {
-                            if task.completed {
-                                "Yes"
-                            } else {
-                                "No"
-                            }
-                        } else {
-                            ""
+            }
+        }
+        Commands::Complete { ids, all, except } => {
+            if !all && !except.is_empty() {
+                return Err(TaskError::InvalidInput("--except can only be used with --all".into()));
+            }
+            if all {
+                let except = parse_ids(&except.join(" "))?;
+                let tasks = store.list(true)?;
+                for id in &except {
+                    if !tasks.iter().any(|t| t.id == *id) {
+                        eprintln!("Warning: task with ID {} not found", id);
+                    }
+                }
+                let ids: Vec<u32> = tasks
+                    .iter()
+                    .filter(|t| !t.is_completed() && !except.contains(&t.id))
+                    .map(|t| t.id)
+                    .collect();
+                store.complete_many(&ids)?;
+                println!("Completed {} task(s)", ids.len());
+            } else {
+                let ids = parse_ids(&ids.join(" "))?;
+                match ids[..] {
+                    [id] => store.complete(id)?,
+                    _ => {
+                        let tasks = store.list(true)?;
+                        let (found, missing): (Vec<u32>, Vec<u32>) =
+                            ids.iter().partition(|id| tasks.iter().any(|t| t.id == **id));
+                        if found.is_empty() {
+                            return Err(TaskError::NotFound(missing[0]));
                         }
+                        store.complete_many(&found)?;
+                        println!("Completed {} task(s)", found.len());
+                        if !missing.is_empty() {
+                            let missing: Vec<String> =
+                                missing.iter().map(|id| id.to_string()).collect();
+                            eprintln!(
+                                "Warning: {} task(s) not found: {}",
+                                missing.len(),
+                                missing.join(", ")
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Commands::Delete { ids, hard } => {
+            let ids = parse_ids(&ids.join(" "))?;
+            let mut existing = store.list(true)?;
+            if hard {
+                existing.extend(store.list_trash()?);
+            }
+            if let Some(&missing) = ids.iter().find(|id| !existing.iter().any(|t| t.id == **id)) {
+                return Err(TaskError::NotFound(missing));
+            }
+            for id in ids {
+                if hard {
+                    store.delete(id)?;
+                } else {
+                    store.trash(id)?;
+                    println!(
+                        "Task {} moved to the trash; use `tasg restore {}` to recover it",
+                        id, id
                     );
                 }
             }
         }
-        Commands::Complete { id } => {
-            store.complete(id)?;
+        Commands::Trash => {
+            let tasks = store.list_trash()?;
+            if tasks.is_empty() {
+                println!("The trash is empty");
+            } else {
+                let columns = vec![Column::Id, Column::Description, Column::DeletedAt];
+                let options = render_options(config, columns, None);
+                if plain {
+                    print!("{}", render::plain(&tasks, &options, chrono::Local::now()));
+                } else {
+                    print!("{}", render::table(&tasks, &options));
+                }
+            }
         }
-        Commands::Delete { id } => {
-            store.delete(id)?;
+        Commands::Restore { id } => {
+            store.restore(id)?;
         }
+        Commands::EmptyTrash { yes, older_than } => {
+            let deleted_before = older_than
+                .map(|days| time_ago(chrono::Duration::days(days.into()), "--older-than"))
+                .transpose()?;
+            let count = store
+                .list_trash()?
+                .iter()
+                .filter(|t| match (t.deleted_at, deleted_before) {
+                    (Some(deleted_at), Some(before)) => deleted_at < before,
+                    _ => true,
+                })
+                .count();
+            if count == 0 {
+                println!("Nothing to delete from the trash");
+                return Ok(());
+            }
+            if !yes {
+                print!(
+                    "Are you sure you want to permanently delete {} task(s) from the trash? (y/N): ",
+                    count
+                );
+                io::stdout().flush()?;
+
+                let mut input = String::new();
+                io::stdin().read_line(&mut input)?;
+                if input.trim().to_lowercase() != "y" {
+                    println!("Operation cancelled.");
+                    return Ok(());
+                }
+            }
+            let purged = store.empty_trash(deleted_before)?;
+            println!("Permanently deleted {} task(s) from the trash.", purged);
+        }
+        Commands::Doctor { check_timestamps, check_dependencies, fix } => {
+            let every = !check_timestamps && !check_dependencies;
+            let now = chrono::Local::now();
+            let mut tasks = store.list(true)?;
+            tasks.extend(store.list_trash()?);
+            let mut problems = 0;
+            if every || check_timestamps {
+                for task in &mut tasks {
+                    let issues =
+                        if fix { task.clamp_timestamps(now) } else { task.timestamp_issues(now) };
+                    for issue in &issues {
+                        println!("Task {}: {}", task.id, issue);
+                    }
+                    problems += issues.len();
+                }
+            }
+            if every || check_dependencies {
+                // Fixes are made one at a time, so removing one link is enough to break a cycle.
+                for index in 0..tasks.len() {
+                    let (id, Some(depends_on)) = (tasks[index].id, tasks[index].depends_on) else {
+                        continue;
+                    };
+                    if let Some(problem) = store::dependency_problem(&tasks, id, depends_on) {
+                        println!("Task {}: depends on task {}, {}", id, depends_on, problem);
+                        problems += 1;
+                        if fix {
+                            tasks[index].depends_on = None;
+                        }
+                    }
+                }
+            }
+            if problems == 0 {
+                println!("No problems found");
+            } else if fix {
+                store.replace(&tasks)?;
+                println!("Fixed {} problem(s)", problems);
+            } else {
+                println!("Found {} problem(s); run with --fix to fix them", problems);
+            }
+        }
+        Commands::Tutorial { non_interactive } => {
+            if non_interactive {
+                print!("{}", tutorial::lesson());
+            } else {
+                run_tutorial()?;
+            }
+        }
+        Commands::Wait { id, interval, timeout } => {
+            let start = std::time::Instant::now();
+            let timeout = timeout.map(std::time::Duration::from_secs);
+            let interval = std::time::Duration::from_secs(interval);
+            let mut found = false;
+            loop {
+                // Another process may be half-way through writing the file; try again later.
+                let tasks = match store.list(true) {
+                    Err(TaskError::SerdeError(_)) => None,
+                    result => Some(result?),
+                };
+                if let Some(tasks) = tasks {
+                    match tasks.iter().find(|t| t.id == id) {
+                        Some(task) if task.is_completed() => {
+                            println!("Task {} completed", id);
+                            break;
+                        }
+                        Some(_) => found = true,
+                        None if found => {
+                            println!("Task {} was deleted", id);
+                            break;
+                        }
+                        None => return Err(TaskError::NotFound(id)),
+                    }
+                }
+                let sleep = match timeout {
+                    Some(timeout) if start.elapsed() >= timeout => {
+                        return Err(TaskError::TimedOut(id));
+                    }
+                    Some(timeout) => interval.min(timeout - start.elapsed()),
+                    None => interval,
+                };
+                std::thread::sleep(sleep);
+            }
+        }
+        Commands::Apply { path, allow_new, prune_missing, completed_ok } => {
+            let edits = tasg::apply::parse(&std::fs::read_to_string(&path)?)?;
+            let current = store.list(true)?;
+            let trash = store.list_trash()?;
+            let used_ids: Vec<u32> = current.iter().chain(&trash).map(|t| t.id).collect();
+            let options = ApplyOptions { allow_new, prune_missing };
+            let plan =
+                tasg::apply::plan(&current, &edits, &used_ids, options, chrono::Local::now())?;
+            if plan.skipped_new > 0 {
+                eprintln!(
+                    "Warning: skipping {} new task(s); use --allow-new to add them",
+                    plan.skipped_new
+                );
+            }
+            for task in &plan.tasks {
+                let edited = current
+                    .iter()
+                    .find(|t| t.id == task.id)
+                    .is_some_and(|t| t.description != task.description || t.notes != task.notes);
+                if edited {
+                    check_editable(store, task.id, completed_ok || config.edit_completed)?;
+                }
+            }
+
+            let diffs = tasg::diff::diff(&current, &plan.remaining());
+            if diffs.is_empty() {
+                println!("No changes to apply");
+                return Ok(());
+            }
+            for diff in &diffs {
+                println!("{}", diff);
+            }
+            store.replace(&[plan.tasks, trash].concat())?;
+            println!("Applied {} change(s)", diffs.len());
+        }
+        Commands::Backup => {
+            let backup = store.backup(&BackupPolicy::from_env()?)?;
+            println!("Backed up the tasks to {}", backup.display());
+        }
+        Commands::Carryover => {
+            let previous_path = path_template::expand_previous(
+                &tasks_file_template(config),
+                chrono::Local::now().naive_local(),
+            )?;
+            if !std::path::Path::new(&previous_path).exists() {
+                println!("No previous task file found at {}", previous_path);
+                return Ok(());
+            }
+            let open = JsonStore::new(previous_path.as_str()).list(false)?;
+            let mut existing = store.list(true)?;
+            existing.extend(store.list_trash()?);
+            let last_id = existing.iter().map(|t| t.id).max().unwrap_or(0);
+
+            // Tasks keep their creation time, which identifies them if carryover runs again.
+            let carried: Vec<&Task> = open
+                .iter()
+                .filter(|t| {
+                    !existing
+                        .iter()
+                        .any(|e| e.created_at == t.created_at && e.description == t.description)
+                })
+                .collect();
+            u32::try_from(carried.len())
+                .ok()
+                .and_then(|count| last_id.checked_add(count))
+                .ok_or(TaskError::IdSpaceExhausted)?;
+            let ids: HashMap<u32, u32> =
+                carried.iter().zip(1..).map(|(t, n)| (t.id, last_id + n)).collect();
+            // Every key is checked before anything is written, so a collision carries nothing over.
+            let mut tasks = existing;
+            for task in &carried {
+                if let Some(key) = &task.idempotency_key {
+                    if let Some(other) =
+                        tasks.iter().find(|t| t.idempotency_key.as_deref() == Some(key.as_str()))
+                    {
+                        return Err(TaskError::InvalidInput(format!(
+                            "Idempotency key '{}' of task {} is already used by task {}; nothing was carried over",
+                            key, task.id, other.id
+                        )));
+                    }
+                }
+                let mut copy = (*task).clone();
+                copy.id = ids[&task.id];
+                copy.depends_on = task.depends_on.and_then(|id| ids.get(&id).copied());
+                tasks.push(copy);
+            }
+            store.replace(&tasks)?;
+            for task in &carried {
+                println!(
+                    "Carried over task {} as task {}: {}",
+                    task.id, ids[&task.id], task.description
+                );
+            }
+            println!("Carried over {} task(s) from {}", carried.len(), previous_path);
+        }
+        Commands::Do { instruction } => {
+            run_instruction(store, config, instruction::parse(&instruction)?)?;
+        }
+        Commands::Undo { steps } | Commands::Redo { steps } => {
+            let undo = matches!(cli.command, Commands::Undo { .. });
+            let path = history_path.ok_or_else(|| {
+                TaskError::InvalidInput("There is no undo history for these tasks".into())
+            })?;
+            let mut history = History::load(path)?;
+            let before = store.list(true)?;
+            let current = undo::snapshot(store)?;
+            let tasks = if undo {
+                history.undo(steps as usize, current)?
+            } else {
+                history.redo(steps as usize, current)?
+            };
+            store.replace(&tasks)?;
+            if !cli.dry_run {
+                history.save(path)?;
+            }
+            println!("{} {} change(s)", if undo { "Undid" } else { "Redid" }, steps);
+            for diff in tasg::diff::diff(&before, &store.list(true)?) {
+                println!("{}", diff);
+            }
+        }
+        Commands::Completions { command } => match command {
+            CompletionsCommand::Generate { shell } => {
+                io::stdout().write_all(&completions::script(shell))?;
+            }
+            CompletionsCommand::Install { shell, path } => {
+                let path = path.map_or_else(|| completions::default_path(shell), Ok)?;
+                if cli.dry_run {
+                    println!("Would install {} completions to {}", shell, path.display());
+                    return Ok(());
+                }
+                completions::install(shell, &path)?;
+                print!("{}", completions::instructions(shell, &path));
+            }
+        },
+        Commands::Help { name } => print!("{}", topics::help(name.as_deref())?),
         Commands::Nuke => {
-            print!(
-                "Are you sure you want to delete all tasks? This action cannot be undone. (y/N): "
-            );
+            print!("Are you sure you want to delete all tasks? (y/N): ");
             io::stdout().flush()?;
 
             let mut input = String::new();
             io::stdin().read_line(&mut input)?;
 
             if input.trim().to_lowercase() == "y" {
-                std::fs::remove_file(store.path())?;
+                store.nuke()?;
                 println!("All tasks have been deleted.");
             } else {
                 println!("Operation cancelled.");
             }
         }
-        Commands::Edit { id, description } => {
-            store.edit(id, description)?;
+        Commands::Edit { id, description, completed_ok, interactive } => {
+            check_editable(store, id, completed_ok || config.edit_completed)?;
+            if interactive {
+                edit_interactively(store, id)?;
+            } else {
+                store.edit(id, description)?;
+            }
+        }
+        Commands::Get { id, field } => {
+            println!("{}", find_task(store, id)?.get_field(&field)?);
+        }
+        Commands::Status { id, status } => {
+            store.set_field(id, "status", status.name())?;
+        }
+        Commands::Set { id, field, value, completed_ok } => {
+            if field == "description" || field == "notes" {
+                check_editable(store, id, completed_ok || config.edit_completed)?;
+            }
+            store.set_field(id, &field, &value)?;
+        }
+        Commands::Touch { id } => {
+            store.touch(id)?;
+        }
+        Commands::Diff { path, stat, json } => {
+            std::fs::metadata(&path)?;
+            let other = JsonStore::new(path);
+            let diffs = tasg::diff::diff(&store.list(true)?, &other.list(true)?);
+            if stat {
+                println!("{}", tasg::diff::stat(&diffs));
+            } else if json {
+                println!("{:#}", tasg::diff::to_json(&diffs));
+            } else if diffs.is_empty() {
+                println!("No differences found");
+            } else {
+                for diff in diffs {
+                    println!("{}", diff);
+                }
+            }
         }
     }
 
@@ -149,31 +1297,64 @@ fn run(cli: Cli, store: JsonStore) -> Result<(), TaskError> {
 ///
 /// # Process
 ///
-/// 1. Determines the tasks file path. If the `TASG_FILE` environment variable is set, its value is used. Otherwise, the default path (`~/.config/tasg/tasks.json`) is used.
-/// 2. Ensures that the tasks file exists by calling `ensure_tasks_file_exists`.
-/// 3. Creates a `JsonStore` to manage task data in the JSON file.
-/// 4. Parses the command-line arguments using `Cli::parse`.
-/// 5. Calls `run` to execute the command provided by the user.
-/// 6. Handles any errors that occur during execution and prints appropriate error messages.
+/// 1. Loads the config file (`~/.config/tasg/config.toml`, or `TASG_CONFIG`), if there is one, warning about any unknown keys.
+/// 2. Determines the tasks file path. If the `TASG_FILE` environment variable is set, its value is used. Otherwise, the `tasks_file` key of the config file or the default path (`~/.config/tasg/tasks.json`) is used. Any `strftime` tokens in the path are expanded for the current date.
+/// 3. Parses the command-line arguments using `Cli::parse`. Read-only mode is enabled by the `--read-only` flag or by setting the `TASG_READONLY` environment variable to `1` or `true`.
+/// 4. Ensures that the tasks file exists by calling `ensure_tasks_file_exists`, unless in read-only mode or a dry run.
+/// 5. Creates a `JsonStore` to manage task data in the JSON file, writing timestamps in the format set by `TASG_TIMESTAMP_FORMAT`, wrapped in a `ReadOnlyStore` in read-only mode. With `--dry-run`, the store keeps changes in memory instead of saving them.
+/// 6. Calls `run` to execute the command provided by the user, with the state file (`~/.config/tasg/state.json`, or `TASG_STATE`) that records when tasks were last listed. Unless in read-only mode or a dry run, the tasks as they were before a command that changes them are recorded in the undo history next to the tasks file.
+/// 7. Handles any errors that occur during execution and prints appropriate error messages. After a successful dry run, the changes that were not saved are printed to standard error.
 ///
 /// # Panics
 ///
 /// * If the tasks file path cannot be determined or created.
 /// * If the application encounters an error while running.
 fn main() {
-    let tasks_file = std::env::var("TASG_FILE")
-        .unwrap_or_else(|_| get_default_tasks_file().to_string_lossy().to_string());
+    let config = match Config::load(&config_file()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {}", e.chain());
+            std::process::exit(1);
+        }
+    };
+    let now = chrono::Local::now().naive_local();
+    let tasks_file = match path_template::expand(&tasks_file_template(&config), now) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Error: {}", e.chain());
+            std::process::exit(1);
+        }
+    };
 
-    if let Err(e) = ensure_tasks_file_exists(&tasks_file) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
-    }
+    let mut cli = Cli::parse();
+    cli.read_only |= std::env::var("TASG_READONLY").is_ok_and(|v| v == "1" || v == "true");
 
-    let store = JsonStore::new(tasks_file);
+    if !cli.read_only && !cli.dry_run {
+        if let Err(e) = ensure_tasks_file_exists(&tasks_file) {
+            eprintln!("Error: {}", e.chain());
+            std::process::exit(1);
+        }
+    }
 
-    let cli = Cli::parse();
-    if let Err(e) = run(cli, store) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+    let history_path = History::path(&tasks_file);
+    let result = open_store(&tasks_file, cli.read_only, cli.dry_run, &config).and_then(|store| {
+        let limit = History::limit_from_env()?;
+        let dry_run = cli.dry_run;
+        let records = !cli.read_only
+            && !dry_run
+            && !matches!(cli.command, Commands::Undo { .. } | Commands::Redo { .. });
+        let command =
+            || run(cli, store.as_ref(), &config, Some(&state_file()), Some(&history_path));
+        if dry_run {
+            run_dry(store.as_ref(), command)
+        } else if records {
+            run_recorded(store.as_ref(), &history_path, limit, command)
+        } else {
+            command()
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("Error: {}", e.chain());
+        std::process::exit(e.exit_code());
     }
 }
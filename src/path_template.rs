@@ -0,0 +1,220 @@
+//! Date-based task file paths.
+//!
+//! A tasks file path may contain `strftime` tokens, e.g. `~/tasks/%Y-%W.json`, to keep a fresh
+//! task list per day, week, month, or year while the old ones stay behind as an archive. This
+//! module expands such templates and works out the path of the previous period's file, which
+//! `tasg carryover` copies open tasks from.
+
+use std::fmt::Write;
+
+use chrono::{Days, NaiveDateTime};
+
+use crate::error::TaskError;
+
+/// The `strftime` specifiers that make a template create a new file every period, by period.
+pub const TOKENS: &[(Period, &str)] = &[
+    (Period::Day, "dejaAuwDFx"),
+    (Period::Week, "UWV"),
+    (Period::Month, "mbBh"),
+    (Period::Year, "YyGgC"),
+];
+
+/// The length of the period a template creates a new file for, given by its smallest date token.
+///
+/// # Variants
+///
+/// - `Day` - A new file every day, e.g. `%Y-%m-%d`.
+/// - `Week` - A new file every week, e.g. `%Y-%W`.
+/// - `Month` - A new file every month, e.g. `%Y-%m`.
+/// - `Year` - A new file every year, e.g. `%Y`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Period {
+    /// A new file every day.
+    Day,
+
+    /// A new file every week.
+    Week,
+
+    /// A new file every month.
+    Month,
+
+    /// A new file every year.
+    Year,
+}
+
+impl Period {
+    /// The period's name, e.g. for help text.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The name, e.g. `week`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+            Period::Year => "year",
+        }
+    }
+}
+
+/// Finds the period of a template from its smallest date token.
+///
+/// # Arguments
+///
+/// * `template` - The path template.
+///
+/// # Returns
+///
+/// * `Option<Period>` - The period, or `None` if the template has no date tokens.
+pub fn period(template: &str) -> Option<Period> {
+    let mut chars = template.chars();
+    let mut smallest = None;
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        let specifier = chars.by_ref().find(|c| !matches!(c, '-' | '_' | '0' | '#'));
+        let Some(period) = specifier.and_then(|specifier| {
+            TOKENS.iter().find(|(_, tokens)| tokens.contains(specifier)).map(|(period, _)| *period)
+        }) else {
+            continue;
+        };
+        smallest = Some(smallest.map_or(period, |p: Period| p.min(period)));
+    }
+    smallest
+}
+
+/// Expands the `strftime` tokens of a template for the given time.
+///
+/// Templates without a `%` are returned unchanged.
+///
+/// # Arguments
+///
+/// * `template` - The path template.
+/// * `at` - The time to expand the template for.
+///
+/// # Returns
+///
+/// * `Result<String, TaskError>` - The expanded path, or `TaskError::InvalidInput` if the template contains an invalid token.
+pub fn expand(template: &str, at: NaiveDateTime) -> Result<String, TaskError> {
+    if !template.contains('%') {
+        return Ok(template.to_string());
+    }
+    let mut path = String::new();
+    write!(path, "{}", at.format(template)).map_err(|_| {
+        TaskError::InvalidInput(format!("Invalid date format in tasks file path '{}'", template))
+    })?;
+    Ok(path)
+}
+
+/// The most days `expand_previous` steps back looking for a different path: a leap year.
+const MAX_PERIOD_DAYS: u64 = 366;
+
+/// Expands a template for the period before the one containing `now`.
+///
+/// The previous period's path is the path of the latest earlier day that expands differently, so
+/// short periods are not skipped, such as week `00` of `%W`, which only covers the days of the
+/// year before its first Monday.
+///
+/// # Arguments
+///
+/// * `template` - The path template.
+/// * `now` - The current time.
+///
+/// # Returns
+///
+/// * `Result<String, TaskError>` - The previous period's path, or `TaskError::InvalidInput` if the template has no date tokens or contains an invalid token.
+pub fn expand_previous(template: &str, now: NaiveDateTime) -> Result<String, TaskError> {
+    let period = period(template).ok_or_else(|| {
+        TaskError::InvalidInput(format!(
+            "Tasks file path '{}' does not contain a date, e.g. %Y-%W",
+            template
+        ))
+    })?;
+    let current = expand(template, now)?;
+    let mut date = now.date();
+    for _ in 0..MAX_PERIOD_DAYS {
+        date = date - Days::new(1);
+        let path = expand(template, date.and_time(now.time()))?;
+        if path != current {
+            return Ok(path);
+        }
+    }
+    Err(TaskError::InvalidInput(format!(
+        "Tasks file path '{}' does not change with the {}",
+        template,
+        period.name()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    /// Builds a time at noon on the given date.
+    fn noon(year: i32, month: u32, day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    /// Tests that the smallest date token determines the period.
+    #[test]
+    fn test_period() {
+        assert_eq!(period("tasks.json"), None);
+        assert_eq!(period("100%.json"), None);
+        assert_eq!(period("%Y/%m-%d.json"), Some(Period::Day));
+        assert_eq!(period("%Y-%W.json"), Some(Period::Week));
+        assert_eq!(period("%Y-%-m.json"), Some(Period::Month));
+        assert_eq!(period("%Y.json"), Some(Period::Year));
+    }
+
+    /// Tests expanding templates, including templates without tokens.
+    #[test]
+    fn test_expand() {
+        let at = noon(2024, 7, 1);
+        assert_eq!(expand("/tmp/tasks.json", at).unwrap(), "/tmp/tasks.json");
+        assert_eq!(expand("/tmp/%Y-%m.json", at).unwrap(), "/tmp/2024-07.json");
+        assert_eq!(expand("/tmp/%Y-%W.json", at).unwrap(), "/tmp/2024-27.json");
+        assert!(expand("/tmp/%Q.json", at).is_err());
+    }
+
+    /// Tests the previous period across year boundaries.
+    #[test]
+    fn test_expand_previous_across_year() {
+        let new_year = noon(2025, 1, 2);
+        assert_eq!(expand_previous("%Y-%m-%d", new_year).unwrap(), "2025-01-01");
+        assert_eq!(expand_previous("%Y-%m-%d", noon(2025, 1, 1)).unwrap(), "2024-12-31");
+        assert_eq!(expand_previous("%Y-%m", new_year).unwrap(), "2024-12");
+        assert_eq!(expand_previous("%Y", new_year).unwrap(), "2024");
+        // 2025-01-02 is in week 00 (before the first Monday); the week before it is the short
+        // week 53 of 2024 (Monday 30 and Tuesday 31 December), not the full week 52
+        assert_eq!(expand("%Y-%W", new_year).unwrap(), "2025-00");
+        assert_eq!(expand_previous("%Y-%W", new_year).unwrap(), "2024-53");
+        assert_eq!(expand("%Y-%W", noon(2025, 1, 6)).unwrap(), "2025-01");
+        assert_eq!(expand_previous("%Y-%W", noon(2025, 1, 6)).unwrap(), "2025-00");
+        assert_eq!(expand_previous("%Y-%W", noon(2024, 12, 30)).unwrap(), "2024-52");
+        // With Sunday-based weeks, 2023 ends with the single-day week 53 (Sunday 31 December)
+        assert_eq!(expand_previous("%Y-%U", noon(2024, 1, 1)).unwrap(), "2023-53");
+        assert_eq!(expand_previous("%Y-%U", noon(2023, 12, 31)).unwrap(), "2023-52");
+        // ISO weeks should be paired with the ISO year: 2024-12-30 is in week 1 of 2025
+        assert_eq!(expand("%G-%V", noon(2025, 1, 6)).unwrap(), "2025-02");
+        assert_eq!(expand_previous("%G-%V", noon(2025, 1, 6)).unwrap(), "2025-01");
+        assert_eq!(expand_previous("%G-%V", noon(2024, 12, 30)).unwrap(), "2024-52");
+    }
+
+    /// Tests the previous month from the end of a longer month.
+    #[test]
+    fn test_expand_previous_month_end() {
+        assert_eq!(expand_previous("%Y-%m", noon(2024, 3, 31)).unwrap(), "2024-02");
+    }
+
+    /// Tests that templates without a date have no previous period.
+    #[test]
+    fn test_expand_previous_without_date() {
+        assert!(matches!(
+            expand_previous("/tmp/tasks.json", noon(2024, 7, 1)),
+            Err(TaskError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,1267 @@
+//! Rendering tasks as text.
+//!
+//! This module turns tasks into the strings the `tasg` binary prints: the table used by `list`
+//! and `trash`, the detail view used by `show`, JSON, and the plain sentences that replace the
+//! table and the detail view with `--output plain`. Everything here returns a `String`
+//! rather than writing to standard output, so other programs can embed `tasg`'s output and the
+//! formatting can be tested directly.
+
+use std::str::FromStr;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+
+use crate::error::TaskError;
+use crate::store::TaskCounts;
+use crate::task::{Status, Task};
+
+/// The narrowest description column allowed when the table width is fixed.
+pub const MIN_DESCRIPTION_WIDTH: usize = 10;
+
+/// The width of the description column when the table width is not fixed.
+const DEFAULT_DESCRIPTION_WIDTH: usize = 50;
+
+/// The width of timestamp columns.
+const DATE_WIDTH: usize = 20;
+
+/// The narrowest ID column, widened to fit the longest ID shown.
+const MIN_ID_WIDTH: usize = 3;
+
+/// The width of the marker column shown when highlighting tasks without colors.
+const MARKER_WIDTH: usize = 1;
+
+/// A column of the task table.
+///
+/// # Variants
+///
+/// - `Id` - The task's ID.
+/// - `Description` - The task's description.
+/// - `CreatedAt` - When the task was created.
+/// - `UpdatedAt` - When the task was last updated.
+/// - `Completed` - Whether the task has been completed.
+/// - `Status` - Where the task stands: to do, in progress, done, or blocked.
+/// - `DeletedAt` - When the task was moved to the trash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// The task's ID.
+    Id,
+
+    /// The task's description.
+    Description,
+
+    /// When the task was created.
+    CreatedAt,
+
+    /// When the task was last updated.
+    UpdatedAt,
+
+    /// Whether the task has been completed.
+    Completed,
+
+    /// Where the task stands: to do, in progress, done, or blocked.
+    Status,
+
+    /// When the task was moved to the trash.
+    DeletedAt,
+}
+
+impl Column {
+    /// The columns that can be selected with `list --fields`, in their default JSON order.
+    pub const FIELDS: &'static [Column] = &[
+        Column::Id,
+        Column::Description,
+        Column::CreatedAt,
+        Column::UpdatedAt,
+        Column::Completed,
+        Column::Status,
+    ];
+
+    /// The column's field name, as used by `list --fields` and as the JSON key.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The name of the corresponding `Task` field.
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Description => "description",
+            Column::CreatedAt => "created_at",
+            Column::UpdatedAt => "updated_at",
+            Column::Completed => "completed",
+            Column::Status => "status",
+            Column::DeletedAt => "deleted_at",
+        }
+    }
+
+    /// The column's heading.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The heading shown in the first line of the table.
+    pub fn header(self) -> &'static str {
+        match self {
+            Column::Id => "ID",
+            Column::Description => "Description",
+            Column::CreatedAt => "Created At",
+            Column::UpdatedAt => "Updated At",
+            Column::Completed => "Completed",
+            Column::Status => "Status",
+            Column::DeletedAt => "Deleted At",
+        }
+    }
+
+    /// The column's width, or `None` for the description, whose width depends on the options.
+    /// The ID column is at least this wide, and wider when a longer ID is shown.
+    fn width(self) -> Option<usize> {
+        match self {
+            Column::Id => Some(MIN_ID_WIDTH),
+            Column::Description => None,
+            Column::CreatedAt | Column::UpdatedAt | Column::DeletedAt => Some(DATE_WIDTH),
+            Column::Completed => Some(9),
+            Column::Status => Some(11),
+        }
+    }
+
+    /// Converts the column's value for a task to JSON, in the same format as the task file.
+    fn json_value(self, task: &Task) -> serde_json::Value {
+        match self {
+            Column::Id => task.id.into(),
+            Column::Description => task.description.clone().into(),
+            Column::CreatedAt => serde_json::json!(task.created_at),
+            Column::UpdatedAt => serde_json::json!(task.updated_at),
+            Column::Completed => task.is_completed().into(),
+            Column::Status => task.status.name().into(),
+            Column::DeletedAt => serde_json::json!(task.deleted_at),
+        }
+    }
+
+    /// Formats the column's value for a task, using the date format parsed by `date_items`.
+    fn value(self, task: &Task, date_format: &[Item]) -> String {
+        match self {
+            Column::Id => task.id.to_string(),
+            Column::Description => task.description.clone(),
+            Column::CreatedAt => format_date(task.created_at, date_format),
+            Column::UpdatedAt => format_date(task.updated_at, date_format),
+            Column::Completed => yes_no(task.is_completed()).to_string(),
+            Column::Status => task.status.to_string(),
+            Column::DeletedAt => {
+                format_date(task.deleted_at.unwrap_or(task.updated_at), date_format)
+            }
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = TaskError;
+
+    /// Parses a field name, as accepted by `list --fields`.
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The field name, e.g. `created_at`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Self::Err>` - The column, or `TaskError::InvalidInput` naming the valid fields.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Column::FIELDS.iter().copied().find(|c| c.name() == s.trim()).ok_or_else(|| {
+            let names: Vec<&str> = Column::FIELDS.iter().map(|c| c.name()).collect();
+            TaskError::InvalidInput(format!(
+                "Unknown field '{}'; expected one of: {}",
+                s.trim(),
+                names.join(", ")
+            ))
+        })
+    }
+}
+
+/// How `list`, `trash`, `show`, and `count` print their results.
+///
+/// # Variants
+///
+/// - `Standard` - Aligned tables and labels, with colors on a terminal.
+/// - `Plain` - One line of short sentences per task, without padding, symbols, or colors, for screen readers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Output {
+    /// Aligned tables and labels, with colors on a terminal.
+    #[default]
+    Standard,
+
+    /// One line of short sentences per task, without padding, symbols, or colors.
+    Plain,
+}
+
+impl FromStr for Output {
+    type Err = TaskError;
+
+    /// Parses an output style, as accepted by `--output`.
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The name of the style: `standard` or `plain`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Self::Err>` - The style, or `TaskError::InvalidInput` naming the valid styles.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "standard" => Ok(Output::Standard),
+            "plain" => Ok(Output::Plain),
+            _ => Err(TaskError::InvalidInput(format!(
+                "Unknown output '{}'; expected one of: standard, plain",
+                s.trim()
+            ))),
+        }
+    }
+}
+
+/// Options controlling how tasks are rendered.
+///
+/// # Fields
+///
+/// - `width` - The total width of the table; longer descriptions are truncated to fit. If `None`, descriptions are never truncated.
+/// - `color` - Whether to use ANSI colors.
+/// - `columns` - The table columns, in order.
+/// - `date_format` - The `strftime`-style format used for timestamps.
+/// - `highlight_since` - Highlight tasks updated at or after this time: in color, or with a `*` marker column when colors are off.
+/// - `borders` - Whether to draw borders around the columns of the table, and above and below its rows.
+/// - `truncate` - Cut descriptions longer than this many characters, ending them with an ellipsis. Takes precedence over `width`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// The total width of the table, or `None` to never truncate descriptions.
+    pub width: Option<usize>,
+
+    /// Whether to use ANSI colors.
+    pub color: bool,
+
+    /// The table columns, in order.
+    pub columns: Vec<Column>,
+
+    /// The `strftime`-style format used for timestamps.
+    pub date_format: String,
+
+    /// Highlight tasks updated at or after this time, or `None` to highlight nothing.
+    pub highlight_since: Option<DateTime<Local>>,
+
+    /// Whether to draw borders around the columns of the table, and above and below its rows.
+    pub borders: bool,
+
+    /// Cut descriptions longer than this many characters, or `None` to size them by `width`.
+    pub truncate: Option<usize>,
+}
+
+impl Default for RenderOptions {
+    /// Creates the options used by `tasg list`: no fixed width, no colors, and the ID,
+    /// description, and creation time columns.
+    fn default() -> Self {
+        RenderOptions {
+            width: None,
+            color: false,
+            columns: vec![Column::Id, Column::Description, Column::CreatedAt],
+            date_format: String::from("%Y-%m-%d %H:%M:%S"),
+            highlight_since: None,
+            borders: false,
+            truncate: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// The narrowest `width` the table can be rendered at.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The width taken by every column but the description, plus `MIN_DESCRIPTION_WIDTH` if the description is shown.
+    pub fn min_width(&self) -> usize {
+        if self.columns.contains(&Column::Description) {
+            self.fixed_width(MIN_ID_WIDTH) + MIN_DESCRIPTION_WIDTH
+        } else {
+            self.fixed_width(MIN_ID_WIDTH)
+        }
+    }
+
+    /// The width taken by every column but the description, including the separators and borders,
+    /// when the ID column is `id_width` wide.
+    fn fixed_width(&self, id_width: usize) -> usize {
+        let widths: usize = self
+            .columns
+            .iter()
+            .filter_map(|&c| if c == Column::Id { Some(id_width) } else { c.width() })
+            .sum();
+        let marker = if self.shows_marker() { MARKER_WIDTH } else { 0 };
+        let columns = self.columns.len() + usize::from(self.shows_marker());
+        let separators = if self.borders { 3 * columns + 1 } else { columns.saturating_sub(1) };
+        widths + marker + separators
+    }
+
+    /// Whether the table starts with a marker column, used instead of colors to highlight tasks.
+    fn shows_marker(&self) -> bool {
+        self.highlight_since.is_some() && !self.color
+    }
+
+    /// Whether a task was updated recently enough to be highlighted.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task to check.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if `highlight_since` is set and the task was updated at or after it.
+    pub fn is_highlighted(&self, task: &Task) -> bool {
+        self.highlight_since.is_some_and(|since| task.updated_at >= since)
+    }
+
+    /// The width of the description column when the ID column is `id_width` wide.
+    fn description_width(&self, id_width: usize) -> usize {
+        if let Some(truncate) = self.truncate {
+            return truncate;
+        }
+        match self.width {
+            Some(width) => {
+                width.saturating_sub(self.fixed_width(id_width)).max(MIN_DESCRIPTION_WIDTH)
+            }
+            None => DEFAULT_DESCRIPTION_WIDTH,
+        }
+    }
+}
+
+/// Parses a `strftime`-style format once, so that it is not parsed again for every timestamp.
+fn date_items(format: &str) -> Vec<Item<'_>> {
+    StrftimeItems::new(format).collect()
+}
+
+/// The width of the ID column needed to show the given tasks.
+fn id_width(tasks: &[Task]) -> usize {
+    tasks.iter().map(|t| t.id.to_string().len()).fold(MIN_ID_WIDTH, usize::max)
+}
+
+/// Formats a timestamp with a format parsed by `date_items`.
+fn format_date(at: DateTime<Local>, date_format: &[Item]) -> String {
+    at.format_with_items(date_format.iter()).to_string()
+}
+
+/// Renders tasks as a table with a heading line.
+///
+/// Completed tasks are dimmed when colors are enabled. Tasks updated since `highlight_since` are
+/// shown in yellow instead, or marked with a `*` in an extra first column when colors are off.
+/// With `borders`, columns are separated by `|` and ruled off with `+---+` lines above and below
+/// the heading and after the last row, and widen to fit their longest value.
+///
+/// # Arguments
+///
+/// * `tasks` - The tasks to render, one per line.
+/// * `options` - The columns, width, and formatting to use.
+///
+/// # Returns
+///
+/// * `String` - The table, with a trailing newline after every line.
+pub fn table(tasks: &[Task], options: &RenderOptions) -> String {
+    let id_width = id_width(tasks);
+    let description_width = options.description_width(id_width);
+    let date_format = date_items(&options.date_format);
+    let header: Vec<String> = options.columns.iter().map(|c| c.header().to_string()).collect();
+    let rows: Vec<Vec<String>> = tasks
+        .iter()
+        .map(|task| {
+            options
+                .columns
+                .iter()
+                .map(|column| match column {
+                    Column::Description
+                        if options.width.is_some() || options.truncate.is_some() =>
+                    {
+                        truncate(&task.description, description_width)
+                    }
+                    _ => column.value(task, &date_format),
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut widths: Vec<usize> =
+        options.columns.iter().map(|c| c.width().unwrap_or(description_width)).collect();
+    if let Some(i) = options.columns.iter().position(|&c| c == Column::Id) {
+        widths[i] = id_width;
+    }
+    if options.borders {
+        for cells in rows.iter().chain([&header]) {
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+    }
+    if options.shows_marker() {
+        widths.insert(0, MARKER_WIDTH);
+    }
+
+    let row = |marker: &str, cells: &[String]| {
+        let marker = options.shows_marker().then(|| marker.to_string());
+        let padded: Vec<String> = marker
+            .iter()
+            .chain(cells)
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<w$}", cell, w = width))
+            .collect();
+        if options.borders {
+            format!("| {} |", padded.join(" | "))
+        } else {
+            padded.join(" ").trim_end().to_string()
+        }
+    };
+    let rule = || {
+        let dashes: Vec<String> = widths.iter().map(|w| "-".repeat(w + 2)).collect();
+        format!("+{}+\n", dashes.join("+"))
+    };
+
+    let mut text = String::new();
+    if options.borders {
+        text.push_str(&rule());
+    }
+    text.push_str(&paint(&row("", &header), BOLD, options.color));
+    text.push('\n');
+    if options.borders {
+        text.push_str(&rule());
+    }
+    for (task, cells) in tasks.iter().zip(&rows) {
+        let highlighted = options.is_highlighted(task);
+        let line = row(if highlighted { "*" } else { "" }, cells);
+        let line = if highlighted {
+            paint(&line, HIGHLIGHT, options.color)
+        } else if task.is_completed() {
+            paint(&line, DIM, options.color)
+        } else {
+            line
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    if options.borders && !tasks.is_empty() {
+        text.push_str(&rule());
+    }
+    text
+}
+
+/// Renders tasks one per line, as `#<id> [x] description`.
+///
+/// Pending tasks are marked `[ ]` and completed ones `[x]`. Nothing is padded, so lines are only
+/// as long as their content. Completed tasks are dimmed when colors are enabled.
+///
+/// # Arguments
+///
+/// * `tasks` - The tasks to render, one per line.
+/// * `options` - The formatting to use; only `color` and `truncate` are used.
+///
+/// # Returns
+///
+/// * `String` - The lines, with a trailing newline after each.
+pub fn compact(tasks: &[Task], options: &RenderOptions) -> String {
+    let mut text = String::new();
+    for task in tasks {
+        let marker = if task.is_completed() { 'x' } else { ' ' };
+        let description = match options.truncate {
+            Some(width) => truncate(&task.description, width),
+            None => task.description.clone(),
+        };
+        let line = format!("#{} [{}] {}", task.id, marker, description);
+        text.push_str(&paint(&line, DIM, options.color && task.is_completed()));
+        text.push('\n');
+    }
+    text
+}
+
+/// Renders every field of a single task, one per line.
+///
+/// Labels are aligned, and fields that are not set, such as `Deleted At` for a task that is not
+/// in the trash, are left out. Values that span several lines, such as notes, and long values
+/// wrapped to a fixed `width` continue with a hanging indent under the first line of the value.
+/// With colors, labels are bold and the values of
+/// completed tasks are dimmed, as in the table.
+///
+/// # Arguments
+///
+/// * `task` - The task to render.
+/// * `options` - The formatting to use; `columns` is ignored.
+///
+/// # Returns
+///
+/// * `String` - The task's fields as `Label: value` lines, with a trailing newline.
+pub fn detail(task: &Task, options: &RenderOptions) -> String {
+    let date_format = date_items(&options.date_format);
+    let mut fields = vec![(Column::Id.header(), task.id.to_string())];
+    if !task.description.trim().is_empty() {
+        fields.push((Column::Description.header(), task.description.clone()));
+    }
+    if let Some(notes) = &task.notes {
+        fields.push(("Notes", notes.clone()));
+    }
+    let value = |c: Column| (c.header(), c.value(task, &date_format));
+    fields.extend([Column::CreatedAt, Column::UpdatedAt].map(value));
+    if let Some(at) = task.description_updated_at {
+        fields.push(("Renamed At", format_date(at, &date_format)));
+    }
+    fields.extend([Column::Status, Column::Completed].map(value));
+    if let Some(completed_at) = task.completed_at {
+        fields.push(("Completed At", format_date(completed_at, &date_format)));
+    }
+    if task.is_deleted() {
+        fields.push((Column::DeletedAt.header(), Column::DeletedAt.value(task, &date_format)));
+    }
+    if let Some(id) = task.depends_on {
+        fields.push(("Depends On", id.to_string()));
+    }
+    if let Some(key) = &task.idempotency_key {
+        fields.push(("Idempotency Key", key.clone()));
+    }
+
+    let label_width = fields.iter().map(|(header, _)| header.len() + 1).max().unwrap_or(0);
+    let value_width =
+        options.width.map(|width| width.saturating_sub(label_width + 1).max(MIN_DESCRIPTION_WIDTH));
+    let indent = format!("\n{:w$}", "", w = label_width + 1);
+    fields
+        .into_iter()
+        .map(|(header, value)| {
+            let label = format!("{:<w$}", format!("{}:", header), w = label_width);
+            let lines: Vec<String> = match value_width {
+                Some(width) => value.lines().flat_map(|line| wrap(line, width)).collect(),
+                None => value.lines().map(String::from).collect(),
+            };
+            let value = lines.join(&indent);
+            let value = if task.is_completed() { paint(&value, DIM, options.color) } else { value };
+            format!("{} {}\n", paint(&label, BOLD, options.color), value)
+        })
+        .collect()
+}
+
+/// Renders tasks as plain sentences, one line per task, for screen readers.
+///
+/// Each column becomes a short sentence, e.g. `Task 12. Buy milk. Created 3 days ago.`, with
+/// timestamps given relative to `now`. Unless the completed or status column is shown, the
+/// task's status is always stated, since the table only conveys it by dimming done tasks. Tasks
+/// that would be highlighted end with `Changed recently.` Nothing is padded, truncated, or colored.
+///
+/// # Arguments
+///
+/// * `tasks` - The tasks to render, one per line.
+/// * `options` - The formatting to use; only `columns`, `date_format`, and `highlight_since` are used.
+/// * `now` - The current time, which timestamps are described relative to.
+///
+/// # Returns
+///
+/// * `String` - The lines, with a trailing newline after each.
+pub fn plain(tasks: &[Task], options: &RenderOptions, now: DateTime<Local>) -> String {
+    let date_format = date_items(&options.date_format);
+    let mut text = String::new();
+    for task in tasks {
+        let mut sentences: Vec<String> = options
+            .columns
+            .iter()
+            .filter_map(|&column| plain_sentence(column, task, &date_format, now))
+            .collect();
+        if !options.columns.iter().any(|c| matches!(c, Column::Completed | Column::Status)) {
+            sentences.push(status_sentence(task.status));
+        }
+        if options.is_highlighted(task) {
+            sentences.push(String::from("Changed recently."));
+        }
+        text.push_str(&sentences.join(" "));
+        text.push('\n');
+    }
+    text
+}
+
+/// Renders every field of a single task as plain `Label: value` lines, for screen readers.
+///
+/// The fields are the same as in `detail`, but labels are not aligned, values are not wrapped or
+/// colored, timestamps are given relative to `now`, and the lines of multi-line notes are joined
+/// into one.
+///
+/// # Arguments
+///
+/// * `task` - The task to render.
+/// * `options` - The formatting to use; only `date_format` is used.
+/// * `now` - The current time, which timestamps are described relative to.
+///
+/// # Returns
+///
+/// * `String` - The task's fields as `Label: value` lines, with a trailing newline.
+pub fn plain_detail(task: &Task, options: &RenderOptions, now: DateTime<Local>) -> String {
+    let date_format = date_items(&options.date_format);
+    let when = |at| ago(at, now, &date_format);
+    let mut fields = vec![("Task", task.id.to_string())];
+    if !task.description.trim().is_empty() {
+        fields.push(("Description", task.description.clone()));
+    }
+    if let Some(notes) = &task.notes {
+        fields.push(("Notes", notes.lines().map(str::trim).collect::<Vec<_>>().join(" ")));
+    }
+    fields.push(("Created", when(task.created_at)));
+    fields.push(("Updated", when(task.updated_at)));
+    if let Some(at) = task.description_updated_at {
+        fields.push(("Renamed", when(at)));
+    }
+    fields.push(("Status", task.status.to_string()));
+    if let Some(completed_at) = task.completed_at {
+        fields.push(("Completed", when(completed_at)));
+    }
+    if let Some(deleted_at) = task.deleted_at {
+        fields.push(("Moved to the trash", when(deleted_at)));
+    }
+    if let Some(id) = task.depends_on {
+        fields.push(("Depends on task", id.to_string()));
+    }
+    if let Some(key) = &task.idempotency_key {
+        fields.push(("Idempotency key", key.clone()));
+    }
+    fields.into_iter().map(|(label, value)| format!("{}: {}\n", label, value)).collect()
+}
+
+/// Renders task counts as plain sentences, e.g. `3 tasks. 1 completed. 2 pending.`
+///
+/// # Arguments
+///
+/// * `counts` - The counts to render.
+///
+/// # Returns
+///
+/// * `String` - The sentences on one line, with a trailing newline.
+pub fn plain_counts(counts: &TaskCounts) -> String {
+    let tasks = if counts.total == 1 { "task" } else { "tasks" };
+    format!(
+        "{} {}. {} completed. {} pending.\n",
+        counts.total, tasks, counts.completed, counts.pending
+    )
+}
+
+/// The sentence describing one column of a task in `plain`, or `None` for an empty description.
+fn plain_sentence(
+    column: Column,
+    task: &Task,
+    date_format: &[Item],
+    now: DateTime<Local>,
+) -> Option<String> {
+    Some(match column {
+        Column::Id => format!("Task {}.", task.id),
+        Column::Description => {
+            let description = task.description.trim();
+            if description.is_empty() {
+                return None;
+            }
+            if description.ends_with(['.', '!', '?']) {
+                description.to_string()
+            } else {
+                format!("{}.", description)
+            }
+        }
+        Column::CreatedAt => format!("Created {}.", ago(task.created_at, now, date_format)),
+        Column::UpdatedAt => format!("Updated {}.", ago(task.updated_at, now, date_format)),
+        Column::Completed if task.is_completed() => String::from("Completed."),
+        Column::Completed => String::from("Not completed."),
+        Column::Status => String::from(match task.status {
+            Status::Todo => "To do.",
+            Status::InProgress => "In progress.",
+            Status::Blocked => "Blocked.",
+            Status::Done => "Done.",
+        }),
+        Column::DeletedAt => {
+            let deleted_at = task.deleted_at.unwrap_or(task.updated_at);
+            format!("Moved to the trash {}.", ago(deleted_at, now, date_format))
+        }
+    })
+}
+
+/// The sentence stating a task's status in `plain` when no column shows it, e.g. `Not completed.`
+fn status_sentence(status: Status) -> String {
+    String::from(match status {
+        Status::Todo => "Not completed.",
+        Status::InProgress => "In progress.",
+        Status::Blocked => "Blocked.",
+        Status::Done => "Completed.",
+    })
+}
+
+/// Describes a past time relative to `now` in words, e.g. `3 days ago`.
+///
+/// Times less than a minute ago are `just now`. Times 60 days or more ago, and times in the
+/// future, are given as `on` and the date in `date_format`.
+fn ago(at: DateTime<Local>, now: DateTime<Local>, date_format: &[Item]) -> String {
+    let elapsed = now - at;
+    let count = |n: i64, unit: &str| match n {
+        1 => format!("1 {} ago", unit),
+        n => format!("{} {}s ago", n, unit),
+    };
+    match elapsed {
+        e if e < chrono::Duration::zero() || e.num_days() >= 60 => {
+            format!("on {}", format_date(at, date_format))
+        }
+        e if e.num_minutes() < 1 => String::from("just now"),
+        e if e.num_hours() < 1 => count(e.num_minutes(), "minute"),
+        e if e.num_days() < 1 => count(e.num_hours(), "hour"),
+        e => count(e.num_days(), "day"),
+    }
+}
+
+/// Renders tasks as a pretty-printed JSON array of objects.
+///
+/// Each object has one key per column, named after the corresponding `Task` field and in the
+/// order of `options.columns`. Values use the same format as the task file.
+///
+/// # Arguments
+///
+/// * `tasks` - The tasks to render.
+/// * `options` - The columns to include; the other options are ignored.
+///
+/// # Returns
+///
+/// * `String` - The JSON array, with a trailing newline.
+pub fn json(tasks: &[Task], options: &RenderOptions) -> String {
+    if tasks.is_empty() {
+        return String::from("[]\n");
+    }
+    let objects: Vec<String> = tasks
+        .iter()
+        .map(|task| {
+            let fields: Vec<String> = options
+                .columns
+                .iter()
+                .map(|column| {
+                    format!(
+                        "    {}: {}",
+                        serde_json::Value::from(column.name()),
+                        column.json_value(task)
+                    )
+                })
+                .collect();
+            format!("  {{\n{}\n  }}", fields.join(",\n"))
+        })
+        .collect();
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+/// The ANSI escape code for bold text.
+const BOLD: &str = "\x1b[1m";
+
+/// The ANSI escape code for dimmed text.
+const DIM: &str = "\x1b[2m";
+
+/// The ANSI escape code for yellow text, used for highlighted tasks.
+const HIGHLIGHT: &str = "\x1b[33m";
+
+/// Wraps text in an ANSI style if colors are enabled.
+fn paint(text: &str, style: &str, color: bool) -> String {
+    if color {
+        format!("{}{}\x1b[0m", style, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Formats a completion flag as `Yes` or `No`.
+fn yes_no(completed: bool) -> &'static str {
+    if completed {
+        "Yes"
+    } else {
+        "No"
+    }
+}
+
+/// Wraps text into lines of at most `width` characters, breaking between words.
+///
+/// Words longer than `width` are kept whole on a line of their own.
+///
+/// # Arguments
+///
+/// * `text` - The text to wrap.
+/// * `width` - The maximum number of characters per line.
+///
+/// # Returns
+///
+/// * `Vec<String>` - The lines, without trailing whitespace; a single empty line if the text is empty.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in text.split_whitespace() {
+        let line = lines.last_mut().unwrap();
+        if line.is_empty() {
+            line.push_str(word);
+        } else if line.chars().count() + 1 + word.chars().count() <= width {
+            line.push(' ');
+            line.push_str(word);
+        } else {
+            lines.push(word.to_string());
+        }
+    }
+    lines
+}
+
+/// Truncates text to at most `width` characters, marking the cut with an ellipsis.
+///
+/// # Arguments
+///
+/// * `text` - The text to truncate.
+/// * `width` - The maximum number of characters, including the ellipsis.
+///
+/// # Returns
+///
+/// * `String` - The text, truncated if it was too long.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Status;
+
+    /// Builds a task with a fixed creation time, so rendered output is predictable.
+    fn task(id: u32, description: &str) -> Task {
+        let mut task = Task::new(id, description.to_string());
+        task.created_at = "2024-07-01T09:30:00+00:00".parse().unwrap();
+        task.updated_at = task.created_at;
+        task
+    }
+
+    /// Tests the default table layout.
+    #[test]
+    fn test_table_default() {
+        let tasks = vec![task(1, "Buy milk"), task(12, "Walk the dog")];
+        let text = table(&tasks, &RenderOptions::default());
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], format!("{:<3} {:<50} {}", "ID", "Description", "Created At"));
+        assert!(lines[1].starts_with(&format!("{:<3} {:<50} ", "1", "Buy milk")));
+        assert!(lines[2].starts_with(&format!("{:<3} {:<50} ", "12", "Walk the dog")));
+        let created_at = tasks[0].created_at.format("%Y-%m-%d %H:%M:%S").to_string();
+        assert!(lines[1].ends_with(&created_at));
+    }
+
+    /// Tests that the completed column shows `Yes` or `No`.
+    #[test]
+    fn test_table_completed_column() {
+        let mut done = task(2, "Done");
+        done.status = Status::Done;
+        let options = RenderOptions {
+            columns: vec![Column::Id, Column::Description, Column::Completed],
+            ..RenderOptions::default()
+        };
+        let text = table(&[task(1, "Pending"), done], &options);
+
+        assert!(text.contains(&format!("{:<3} {:<50} {}\n", "1", "Pending", "No")));
+        assert!(text.contains(&format!("{:<3} {:<50} {}\n", "2", "Done", "Yes")));
+    }
+
+    /// Tests that the ID column widens to fit the longest ID shown.
+    #[test]
+    fn test_table_wide_ids() {
+        let tasks = vec![task(7, "Buy milk"), task(123456, "Walk the dog")];
+        let text = table(&tasks, &RenderOptions::default());
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with(&format!("{:<6} {:<50} ", "ID", "Description")));
+        assert!(lines[1].starts_with(&format!("{:<6} {:<50} ", "7", "Buy milk")));
+        assert!(lines[2].starts_with(&format!("{:<6} {:<50} ", "123456", "Walk the dog")));
+
+        let options = RenderOptions { width: Some(50), ..RenderOptions::default() };
+        let text = table(&tasks, &options);
+        assert!(text.lines().all(|line| line.chars().count() <= 50), "{}", text);
+    }
+
+    /// Tests that descriptions are cut at exactly `truncate` characters, in tables and compact lines.
+    #[test]
+    fn test_truncate() {
+        let tasks = vec![task(1, "Buy milk"), task(2, "Walk the dog around the park")];
+        let options = RenderOptions { truncate: Some(12), ..RenderOptions::default() };
+        let text = table(&tasks, &options);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("ID  Description  Created At"));
+        assert!(lines[1].starts_with("1   Buy milk     2024"));
+        assert!(lines[2].starts_with("2   Walk the do… 2024"));
+
+        assert_eq!(compact(&tasks, &options), "#1 [ ] Buy milk\n#2 [ ] Walk the do…\n");
+    }
+
+    /// Tests that a fixed width truncates descriptions so every line fits.
+    #[test]
+    fn test_table_fixed_width() {
+        let description = "A very long task description that will not fit in a narrow list";
+        let options = RenderOptions { width: Some(50), ..RenderOptions::default() };
+        let text = table(&[task(1, description)], &options);
+
+        for line in text.lines() {
+            assert!(line.chars().count() <= 50, "line {:?} is too wide", line);
+        }
+        assert!(text.contains("A very long task descrip…"), "{}", text);
+        assert!(!text.contains(description));
+    }
+
+    /// Tests the narrowest allowed width for the default and `--all` columns.
+    #[test]
+    fn test_min_width() {
+        assert_eq!(RenderOptions::default().min_width(), 35);
+        let options = RenderOptions {
+            columns: vec![Column::Id, Column::Description, Column::CreatedAt, Column::Completed],
+            ..RenderOptions::default()
+        };
+        assert_eq!(options.min_width(), 45);
+    }
+
+    /// Tests that colors are only emitted when enabled.
+    #[test]
+    fn test_table_color() {
+        let tasks = vec![task(1, "Buy milk")];
+        assert!(!table(&tasks, &RenderOptions::default()).contains('\x1b'));
+
+        let options = RenderOptions { color: true, ..RenderOptions::default() };
+        assert!(table(&tasks, &options).starts_with("\x1b[1mID"));
+    }
+
+    /// Tests the detail view, including the trash timestamp.
+    #[test]
+    fn test_detail() {
+        let mut deleted = task(3, "Old task");
+        let text = detail(&deleted, &RenderOptions::default());
+        assert!(text.starts_with("ID:          3\nDescription: Old task\n"));
+        assert!(text.contains("Status:      Todo\nCompleted:   No\n"));
+        assert!(!text.contains("Deleted At"));
+
+        deleted.deleted_at = Some(deleted.created_at);
+        let text = detail(&deleted, &RenderOptions::default());
+        assert!(text.contains("Deleted At:  "));
+
+        deleted.status = Status::Done;
+        deleted.completed_at = Some(deleted.created_at);
+        let text = detail(&deleted, &RenderOptions::default());
+        assert!(text.contains("Status:       Done\nCompleted:    Yes\nCompleted At: 2024-07-01"));
+
+        deleted.idempotency_key = Some(String::from("cleanup-2024"));
+        let text = detail(&deleted, &RenderOptions::default());
+        assert!(text.contains("Idempotency Key: cleanup-2024\n"));
+
+        deleted.description_updated_at = Some(deleted.created_at);
+        let text = detail(&deleted, &RenderOptions::default());
+        assert!(text.contains("Updated At:      2024-07-01"));
+        assert!(text.contains("\nRenamed At:      2024-07-01"));
+    }
+
+    /// Tests the detail view of a task with every field set, wrapped to a fixed width.
+    #[test]
+    fn test_detail_full() {
+        let mut full = task(7, "Renew the passport before the summer holidays start");
+        full.status = Status::Done;
+        full.completed_at = Some(full.created_at);
+        full.deleted_at = Some(full.created_at);
+        full.depends_on = Some(3);
+        full.idempotency_key = Some(String::from("passport"));
+        let options = RenderOptions {
+            width: Some(40),
+            date_format: String::from("%Y"),
+            ..RenderOptions::default()
+        };
+
+        assert_eq!(
+            detail(&full, &options),
+            concat!(
+                "ID:              7\n",
+                "Description:     Renew the passport\n",
+                "                 before the summer\n",
+                "                 holidays start\n",
+                "Created At:      2024\n",
+                "Updated At:      2024\n",
+                "Status:          Done\n",
+                "Completed:       Yes\n",
+                "Completed At:    2024\n",
+                "Deleted At:      2024\n",
+                "Depends On:      3\n",
+                "Idempotency Key: passport\n",
+            )
+        );
+    }
+
+    /// Tests the plain list, with the status stated unless a column shows it.
+    #[test]
+    fn test_plain() {
+        let now = "2024-07-04T10:00:00+00:00".parse().unwrap();
+        let mut blocked = task(12, "Call the bank!");
+        blocked.status = Status::Blocked;
+        blocked.updated_at = "2024-07-04T09:15:00+00:00".parse().unwrap();
+        let tasks = vec![task(1, "Buy milk"), blocked];
+        let options = RenderOptions {
+            highlight_since: Some("2024-07-04T09:00:00+00:00".parse().unwrap()),
+            ..RenderOptions::default()
+        };
+
+        assert_eq!(
+            plain(&tasks, &options, now),
+            concat!(
+                "Task 1. Buy milk. Created 3 days ago. Not completed.\n",
+                "Task 12. Call the bank! Created 3 days ago. Blocked. Changed recently.\n",
+            )
+        );
+
+        let columns = vec![Column::Id, Column::UpdatedAt, Column::Status, Column::Completed];
+        let options = RenderOptions { columns, ..RenderOptions::default() };
+        assert_eq!(
+            plain(&tasks, &options, now),
+            concat!(
+                "Task 1. Updated 3 days ago. To do. Not completed.\n",
+                "Task 12. Updated 45 minutes ago. Blocked. Not completed.\n",
+            )
+        );
+    }
+
+    /// Tests that times are described relative to now, and far or future ones by date.
+    #[test]
+    fn test_ago() {
+        let now: DateTime<Local> = "2024-07-04T10:00:00+00:00".parse().unwrap();
+        let date_format = date_items("%Y");
+        let before =
+            |seconds: i64| ago(now - chrono::Duration::seconds(seconds), now, &date_format);
+        assert_eq!(before(59), "just now");
+        assert_eq!(before(60), "1 minute ago");
+        assert_eq!(before(2 * 3600 + 59), "2 hours ago");
+        assert_eq!(before(86400), "1 day ago");
+        assert_eq!(before(59 * 86400), "59 days ago");
+        assert_eq!(before(60 * 86400), "on 2024");
+        assert_eq!(before(-60), "on 2024");
+    }
+
+    /// Tests that the plain detail view has one unpadded `Label: value` line per field.
+    #[test]
+    fn test_plain_detail() {
+        let now = "2024-07-04T10:00:00+00:00".parse().unwrap();
+        let mut full = task(7, "Renew the passport");
+        full.notes = Some(String::from("Photos first.\n  Then the form."));
+        full.set_status(Status::Done, "2024-07-02T10:00:00+00:00".parse().unwrap());
+        full.depends_on = Some(3);
+
+        assert_eq!(
+            plain_detail(&full, &RenderOptions::default(), now),
+            concat!(
+                "Task: 7\n",
+                "Description: Renew the passport\n",
+                "Notes: Photos first. Then the form.\n",
+                "Created: 3 days ago\n",
+                "Updated: 3 days ago\n",
+                "Status: Done\n",
+                "Completed: 2 days ago\n",
+                "Depends on task: 3\n",
+            )
+        );
+    }
+
+    /// Tests the plain counts, with a singular for a single task.
+    #[test]
+    fn test_plain_counts() {
+        let counts = TaskCounts { total: 3, completed: 1, pending: 2 };
+        assert_eq!(plain_counts(&counts), "3 tasks. 1 completed. 2 pending.\n");
+        let counts = TaskCounts { total: 1, completed: 0, pending: 1 };
+        assert_eq!(plain_counts(&counts), "1 task. 0 completed. 1 pending.\n");
+    }
+
+    /// Tests parsing output styles.
+    #[test]
+    fn test_output_from_str() {
+        assert_eq!("plain".parse::<Output>().unwrap(), Output::Plain);
+        assert_eq!(" Standard ".parse::<Output>().unwrap(), Output::Standard);
+        assert_eq!(
+            "fancy".parse::<Output>().unwrap_err().to_string(),
+            "Invalid input - Unknown output 'fancy'; expected one of: standard, plain"
+        );
+    }
+
+    /// Tests that fields that are not set, including an empty description, are left out.
+    #[test]
+    fn test_detail_minimal() {
+        let options = RenderOptions { date_format: String::from("%Y"), ..RenderOptions::default() };
+        assert_eq!(
+            detail(&task(1, ""), &options),
+            "ID:         1\nCreated At: 2024\nUpdated At: 2024\nStatus:     Todo\nCompleted:  No\n"
+        );
+    }
+
+    /// Tests that the values of completed tasks are dimmed, as in the table.
+    #[test]
+    fn test_detail_color() {
+        let mut done = task(1, "Buy milk");
+        done.status = Status::Done;
+        let options = RenderOptions { color: true, ..RenderOptions::default() };
+        let text = detail(&done, &options);
+        assert!(text.starts_with("\x1b[1mID:         \x1b[0m \x1b[2m1\x1b[0m\n"));
+    }
+
+    /// Tests wrapping text between words.
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("one two three", 7), vec!["one two", "three"]);
+        assert_eq!(wrap("a extraordinarily b", 5), vec!["a", "extraordinarily", "b"]);
+        assert_eq!(wrap("", 5), vec![""]);
+    }
+
+    /// Tests that borders are drawn around every column and widen to fit long values.
+    #[test]
+    fn test_table_borders() {
+        let options = RenderOptions {
+            columns: vec![Column::Id, Column::Description],
+            borders: true,
+            ..RenderOptions::default()
+        };
+        let description = "A description longer than the default column width";
+        let text = table(&[task(1, description)], &options);
+        let rule = format!("+-----+-{}-+\n", "-".repeat(description.len()));
+        let expected = format!(
+            "{rule}| ID  | {:<w$} |\n{rule}| 1   | {} |\n{rule}",
+            "Description",
+            description,
+            w = description.len()
+        );
+        assert_eq!(text, expected);
+    }
+
+    /// Tests that a bordered table fits a fixed width exactly.
+    #[test]
+    fn test_table_borders_fixed_width() {
+        let options = RenderOptions { width: Some(60), borders: true, ..RenderOptions::default() };
+        let text = table(&[task(1, &"x".repeat(100))], &options);
+        assert!(text.lines().all(|line| line.chars().count() == 60), "{}", text);
+        assert_eq!(
+            options.min_width(),
+            60 - options.description_width(MIN_ID_WIDTH) + MIN_DESCRIPTION_WIDTH
+        );
+    }
+
+    /// Tests the compact format with and without colors.
+    #[test]
+    fn test_compact() {
+        let mut done = task(2, "Walk the dog");
+        done.status = Status::Done;
+        let tasks = vec![task(1, "Buy milk"), done];
+        assert_eq!(
+            compact(&tasks, &RenderOptions::default()),
+            "#1 [ ] Buy milk\n#2 [x] Walk the dog\n"
+        );
+
+        let options = RenderOptions { color: true, ..RenderOptions::default() };
+        assert_eq!(
+            compact(&tasks, &options),
+            "#1 [ ] Buy milk\n\x1b[2m#2 [x] Walk the dog\x1b[0m\n"
+        );
+    }
+
+    /// Tests that dates formatted with the parsed format match `DateTime::format`.
+    #[test]
+    fn test_format_date() {
+        let at = task(1, "Buy milk").created_at;
+        for format in ["%Y-%m-%d %H:%M:%S", "%d/%m/%Y %I:%M %p", "%G-W%V-%u", "week %U of %Y %%"] {
+            assert_eq!(format_date(at, &date_items(format)), at.format(format).to_string());
+        }
+    }
+
+    /// Tests that the date format option is honoured.
+    #[test]
+    fn test_date_format() {
+        let options = RenderOptions { date_format: String::from("%Y"), ..RenderOptions::default() };
+        let text = detail(&task(1, "Buy milk"), &options);
+        assert!(text.contains("Created At:  2024\n"));
+    }
+
+    /// Tests that JSON output with every field round-trips.
+    #[test]
+    fn test_json() {
+        let tasks = vec![task(1, "Buy milk"), task(2, "Walk the dog")];
+        let options =
+            RenderOptions { columns: Column::FIELDS.to_vec(), ..RenderOptions::default() };
+        let text = json(&tasks, &options);
+        let parsed: Vec<Task> = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed, tasks);
+        assert_eq!(json(&[], &options), "[]\n");
+    }
+
+    /// Tests that JSON output only contains the selected fields, in order.
+    #[test]
+    fn test_json_fields() {
+        let options = RenderOptions {
+            columns: vec![Column::Completed, Column::Id],
+            ..RenderOptions::default()
+        };
+        let text = json(&[task(1, "Buy milk")], &options);
+        assert_eq!(text, "[\n  {\n    \"completed\": false,\n    \"id\": 1\n  }\n]\n");
+    }
+
+    /// Tests that a table only contains the selected columns, in order.
+    #[test]
+    fn test_table_fields() {
+        let options = RenderOptions {
+            columns: vec![Column::Description, Column::Id],
+            ..RenderOptions::default()
+        };
+        let text = table(&[task(7, "Buy milk")], &options);
+        assert_eq!(text, format!("{:<50} ID\n{:<50} 7\n", "Description", "Buy milk"));
+    }
+
+    /// Tests which tasks are highlighted.
+    #[test]
+    fn test_is_highlighted() {
+        let since = task(1, "Buy milk").created_at;
+        let mut changed = task(1, "Buy milk");
+        assert!(!RenderOptions::default().is_highlighted(&changed));
+
+        let options = RenderOptions { highlight_since: Some(since), ..RenderOptions::default() };
+        assert!(options.is_highlighted(&changed));
+        changed.updated_at = since - chrono::Duration::seconds(1);
+        assert!(!options.is_highlighted(&changed));
+    }
+
+    /// Tests the marker column used to highlight tasks without colors.
+    #[test]
+    fn test_table_highlight_plain() {
+        let mut old = task(2, "Walk the dog");
+        old.updated_at = old.created_at - chrono::Duration::days(2);
+        let options = RenderOptions {
+            columns: vec![Column::Id, Column::Description],
+            highlight_since: Some(task(1, "Buy milk").created_at),
+            ..RenderOptions::default()
+        };
+        let text = table(&[task(1, "Buy milk"), old], &options);
+        assert_eq!(text, "  ID  Description\n* 1   Buy milk\n  2   Walk the dog\n");
+        assert_eq!(options.min_width(), 2 + 3 + 1 + MIN_DESCRIPTION_WIDTH);
+    }
+
+    /// Tests that highlighted tasks are colored instead of marked when colors are enabled.
+    #[test]
+    fn test_table_highlight_color() {
+        let mut old = task(2, "Walk the dog");
+        old.updated_at = old.created_at - chrono::Duration::days(2);
+        old.status = Status::Done;
+        let options = RenderOptions {
+            color: true,
+            columns: vec![Column::Id, Column::Description],
+            highlight_since: Some(task(1, "Buy milk").created_at),
+            ..RenderOptions::default()
+        };
+        let text = table(&[task(1, "Buy milk"), old], &options);
+        assert_eq!(
+            text,
+            "\x1b[1mID  Description\x1b[0m\n\x1b[33m1   Buy milk\x1b[0m\n\x1b[2m2   Walk the dog\x1b[0m\n"
+        );
+    }
+
+    /// Tests parsing field names.
+    #[test]
+    fn test_column_from_str() {
+        assert_eq!("created_at".parse::<Column>().unwrap(), Column::CreatedAt);
+        assert_eq!(" id ".parse::<Column>().unwrap(), Column::Id);
+        let err = "due".parse::<Column>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid input - Unknown field 'due'; expected one of: id, description, created_at, updated_at, completed, status"
+        );
+        assert!("deleted_at".parse::<Column>().is_err());
+    }
+}
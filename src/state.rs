@@ -0,0 +1,115 @@
+//! Remembering when tasks were last listed.
+//!
+//! `tasg list` records when it last ran in a small state file, so that
+//! `tasg list --since-last-run` can show only the tasks that changed since then. Each tasks file
+//! has its own mark, so a per-project file set with `TASG_FILE` is tracked separately.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::error::TaskError;
+
+/// The contents of the state file.
+///
+/// # Fields
+///
+/// - `last_viewed` - When each tasks file was last listed, keyed by its path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    /// When each tasks file was last listed, keyed by its path.
+    last_viewed: BTreeMap<String, DateTime<Local>>,
+}
+
+impl State {
+    /// Loads the state file at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the state file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, TaskError>` - The state, an empty state if the file does not exist, or `TaskError::InvalidInput` if it cannot be parsed.
+    pub fn load(path: &Path) -> Result<Self, TaskError> {
+        if !path.exists() {
+            return Ok(State::default());
+        }
+        serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| TaskError::InvalidInput(format!("Invalid state file: {}", e)))
+    }
+
+    /// Writes the state to the given path, creating any missing directories.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the state file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - `Ok(())` if the state was written, or a `TaskError` if an error occurs.
+    pub fn save(&self, path: &Path) -> Result<(), TaskError> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// When a tasks file was last listed.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks_file` - The path to the tasks file.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<DateTime<Local>>` - The time, or `None` if it has never been listed.
+    pub fn last_viewed(&self, tasks_file: &str) -> Option<DateTime<Local>> {
+        self.last_viewed.get(tasks_file).copied()
+    }
+
+    /// Records that a tasks file was listed.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks_file` - The path to the tasks file.
+    /// * `at` - When it was listed.
+    pub fn record_view(&mut self, tasks_file: &str, at: DateTime<Local>) {
+        self.last_viewed.insert(tasks_file.to_string(), at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that views are recorded per tasks file and survive a round trip.
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("state.json");
+        assert_eq!(State::load(&path).unwrap(), State::default());
+
+        let at = Local::now();
+        let mut state = State::default();
+        state.record_view("a/tasks.json", at);
+        state.save(&path).unwrap();
+
+        let state = State::load(&path).unwrap();
+        assert_eq!(state.last_viewed("a/tasks.json"), Some(at));
+        assert_eq!(state.last_viewed("b/tasks.json"), None);
+    }
+
+    /// Tests that an invalid state file is rejected.
+    #[test]
+    fn test_load_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        std::fs::write(&path, "{").unwrap();
+        assert!(State::load(&path).unwrap_err().to_string().contains("Invalid state file"));
+    }
+}
@@ -3,8 +3,23 @@
 //! This module provides a JSON-based implementation of the `Store` trait for managing tasks in a task management CLI application.
 //! Tasks are stored in a JSON file, and operations such as adding, listing, completing, and deleting tasks are supported.
 
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
 use crate::error::TaskError;
 use crate::task::Task;
+use crate::timestamp::TimestampFormat;
+
+/// The number of attempts made at a read or write that fails with a transient error.
+const RETRY_ATTEMPTS: u32 = 4;
+
+/// The delay before the first retry; it doubles after each further attempt.
+const RETRY_BACKOFF: Duration = Duration::from_millis(10);
 
 /// Trait defining the operations for task storage.
 ///
@@ -19,19 +34,39 @@ pub trait Store {
     /// # Returns
     ///
     /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully added, or a `TaskError` if an error occurs.
+    ///
+    /// # Errors
+    ///
+    /// * Returns `TaskError::InvalidInput` if the task's idempotency key is already used by another task, including tasks in the trash.
     fn add(&self, task: Task) -> Result<(), TaskError>;
 
     /// Lists all tasks or only incomplete tasks.
     ///
+    /// Tasks in the trash are never listed; see `list_trash`.
+    ///
+    /// # Ordering
+    ///
+    /// Implementations must return the tasks in ascending ID order, regardless of how they are
+    /// stored, so that output built on this method is deterministic.
+    ///
     /// # Arguments
     ///
     /// * `all` - If true, lists all tasks. If false, lists only incomplete tasks.
     ///
     /// # Returns
     ///
-    /// * `Result<Vec<Task>, TaskError>` - Returns a vector of tasks, or a `TaskError` if an error occurs.
+    /// * `Result<Vec<Task>, TaskError>` - Returns a vector of tasks ordered by ID, or a `TaskError` if an error occurs.
     fn list(&self, all: bool) -> Result<Vec<Task>, TaskError>;
 
+    /// Counts the tasks, without listing them.
+    ///
+    /// Tasks in the trash are not counted.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<TaskCounts, TaskError>` - Returns the number of tasks, completed tasks, and pending tasks, or a `TaskError` if an error occurs.
+    fn count(&self) -> Result<TaskCounts, TaskError>;
+
     /// Marks a task as complete.
     ///
     /// # Arguments
@@ -43,7 +78,20 @@ pub trait Store {
     /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully marked as complete, or a `TaskError` if an error occurs.
     fn complete(&self, id: u32) -> Result<(), TaskError>;
 
-    /// Deletes a task from the store.
+    /// Marks several tasks as complete in a single operation.
+    ///
+    /// Either all of the tasks are marked as complete, or, if any of them is not found, none are.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the tasks to be marked as complete.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the tasks are successfully marked as complete, or a `TaskError` if an error occurs.
+    fn complete_many(&self, ids: &[u32]) -> Result<(), TaskError>;
+
+    /// Permanently deletes a task from the store, whether or not it is in the trash.
     ///
     /// # Arguments
     ///
@@ -54,6 +102,51 @@ pub trait Store {
     /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully deleted, or a `TaskError` if an error occurs.
     fn delete(&self, id: u32) -> Result<(), TaskError>;
 
+    /// Moves a task to the trash.
+    ///
+    /// Tasks in the trash are hidden from `list` and cannot be modified until they are restored.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task to move to the trash.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully moved to the trash, or a `TaskError` if the task is not found.
+    fn trash(&self, id: u32) -> Result<(), TaskError>;
+
+    /// Restores a task from the trash.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task to restore.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully restored, or a `TaskError` if no task with that ID is in the trash.
+    fn restore(&self, id: u32) -> Result<(), TaskError>;
+
+    /// Lists the tasks in the trash.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Task>, TaskError>` - Returns a vector of the tasks in the trash, or a `TaskError` if an error occurs.
+    fn list_trash(&self) -> Result<Vec<Task>, TaskError>;
+
+    /// Permanently deletes tasks in the trash.
+    ///
+    /// # Arguments
+    ///
+    /// * `deleted_before` - If set, only tasks moved to the trash before this time are deleted. If `None`, the whole trash is emptied.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, TaskError>` - Returns the number of tasks deleted, or a `TaskError` if an error occurs.
+    fn empty_trash(
+        &self,
+        deleted_before: Option<chrono::DateTime<chrono::Local>>,
+    ) -> Result<usize, TaskError>;
+
     /// Path to the store.
     ///
     /// # Returns
@@ -72,6 +165,223 @@ pub trait Store {
     ///
     /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully edited, or a `TaskError` if the task is not found.
     fn edit(&self, id: u32, description: Option<String>) -> Result<(), TaskError>;
+
+    /// Sets a named field of a task from its string form; see `Task::set_field`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task to change.
+    /// * `field` - The name of the field, e.g. `description`.
+    /// * `value` - The new value of the field.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the field is successfully set, `TaskError::NotFound` if the task is not found, or `TaskError::InvalidInput` if the field is unknown or the value cannot be parsed.
+    fn set_field(&self, id: u32, field: &str, value: &str) -> Result<(), TaskError>;
+
+    /// Bumps a task's `updated_at` to the current time without changing anything else.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task to touch.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully touched, or a `TaskError` if the task is not found.
+    fn touch(&self, id: u32) -> Result<(), TaskError>;
+
+    /// Replaces all tasks in the store, including those in the trash.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - The tasks that make up the new contents of the store.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the tasks are successfully written, or a `TaskError` if an error occurs.
+    fn replace(&self, tasks: &[Task]) -> Result<(), TaskError>;
+
+    /// Deletes all tasks by removing the store entirely.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the store is successfully removed, or a `TaskError` if an error occurs.
+    fn nuke(&self) -> Result<(), TaskError>;
+
+    /// Copies the store to a timestamped backup file, then prunes old backups.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Where backups are kept and how many of them.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf, TaskError>` - Returns the path of the new backup, or a `TaskError` if an error occurs.
+    fn backup(&self, policy: &BackupPolicy) -> Result<PathBuf, TaskError>;
+}
+
+/// The number of tasks in a store, not counting the trash.
+///
+/// # Fields
+///
+/// - `total` - The number of tasks.
+/// - `completed` - The number of completed tasks.
+/// - `pending` - The number of tasks that are not completed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TaskCounts {
+    /// The number of tasks.
+    pub total: usize,
+
+    /// The number of completed tasks.
+    pub completed: usize,
+
+    /// The number of tasks that are not completed.
+    pub pending: usize,
+}
+
+/// Where backups of the store are kept, and how many of them.
+///
+/// # Fields
+///
+/// - `keep` - The number of most recent backups to keep; older ones are deleted.
+/// - `dir` - The directory for backups, or `None` for the directory containing the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupPolicy {
+    /// The number of most recent backups to keep.
+    pub keep: usize,
+
+    /// The directory for backups, or `None` for the directory containing the store.
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for BackupPolicy {
+    /// Keeps the 5 most recent backups alongside the store.
+    fn default() -> Self {
+        Self { keep: 5, dir: None }
+    }
+}
+
+impl BackupPolicy {
+    /// Reads the policy from the `TASG_BACKUP_KEEP` and `TASG_BACKUP_DIR` environment variables,
+    /// falling back to the defaults for any that are not set.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, TaskError>` - The policy, or `TaskError::InvalidInput` if `TASG_BACKUP_KEEP` is not a positive number.
+    pub fn from_env() -> Result<Self, TaskError> {
+        let mut policy = Self::default();
+        if let Ok(keep) = std::env::var("TASG_BACKUP_KEEP") {
+            policy.keep = keep.trim().parse().ok().filter(|&keep| keep > 0).ok_or_else(|| {
+                TaskError::InvalidInput(format!(
+                    "TASG_BACKUP_KEEP must be a positive number, not '{}'",
+                    keep
+                ))
+            })?;
+        }
+        if let Some(dir) = std::env::var_os("TASG_BACKUP_DIR") {
+            policy.dir = Some(PathBuf::from(dir));
+        }
+        Ok(policy)
+    }
+}
+
+/// The `strftime` format of the timestamp in backup file names, which sorts chronologically.
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.6f";
+
+/// Checks that a task may depend on another: the other task must exist, must not be in the
+/// trash, and must not already depend on the task, directly or through other tasks.
+///
+/// # Arguments
+///
+/// * `tasks` - All tasks in the store, including those in the trash.
+/// * `id` - The ID of the dependent task.
+/// * `depends_on` - The ID of the task it is to depend on.
+///
+/// # Returns
+///
+/// * `Result<(), TaskError>` - `Ok(())` if the dependency is allowed, or `TaskError::InvalidInput` saying why not.
+pub fn check_dependency(tasks: &[Task], id: u32, depends_on: u32) -> Result<(), TaskError> {
+    match dependency_problem(tasks, id, depends_on) {
+        Some(problem) => Err(TaskError::InvalidInput(format!(
+            "Task {} cannot depend on task {}, {}",
+            id, depends_on, problem
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Finds what is wrong with a task depending on another, if anything.
+///
+/// # Arguments
+///
+/// * `tasks` - All tasks in the store, including those in the trash.
+/// * `id` - The ID of the dependent task.
+/// * `depends_on` - The ID of the task it depends on.
+///
+/// # Returns
+///
+/// * `Option<String>` - `None` if the dependency is allowed, or a clause saying why not, e.g. `which does not exist`.
+pub fn dependency_problem(tasks: &[Task], id: u32, depends_on: u32) -> Option<String> {
+    let mut next = Some(depends_on);
+    let mut seen = Vec::new();
+    while let Some(current) = next {
+        if current == id {
+            return Some(format!("which already depends on task {}", id));
+        }
+        if seen.contains(&current) {
+            break;
+        }
+        seen.push(current);
+        let Some(task) = tasks.iter().find(|t| t.id == current) else {
+            if current == depends_on {
+                return Some(String::from("which does not exist"));
+            }
+            break;
+        };
+        if current == depends_on && task.is_deleted() {
+            return Some(String::from("which is in the trash"));
+        }
+        next = task.depends_on;
+    }
+    None
+}
+
+/// Deletes all but the `keep` most recent backups of a store.
+///
+/// Backups are files in `dir` named `<stem>-<timestamp>.json`, ordered by the timestamp in their
+/// name. Other files are left alone.
+///
+/// # Arguments
+///
+/// * `dir` - The directory containing the backups.
+/// * `stem` - The file stem of the store, e.g. `tasks`.
+/// * `keep` - The number of most recent backups to keep.
+///
+/// # Returns
+///
+/// * `Result<Vec<PathBuf>, TaskError>` - Returns the paths of the deleted backups, or a `TaskError` if an error occurs.
+fn prune_backups(dir: &Path, stem: &str, keep: usize) -> Result<Vec<PathBuf>, TaskError> {
+    let prefix = format!("{}-", stem);
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let timestamp = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(&prefix)?.strip_suffix(".json"))
+            .and_then(|t| chrono::NaiveDateTime::parse_from_str(t, BACKUP_TIMESTAMP_FORMAT).ok());
+        if let Some(timestamp) = timestamp {
+            backups.push((timestamp, path));
+        }
+    }
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(keep);
+    let pruned: Vec<PathBuf> = backups.into_iter().take(excess).map(|(_, path)| path).collect();
+    for path in &pruned {
+        std::fs::remove_file(path)?;
+    }
+    Ok(pruned)
 }
 
 /// JSON-based implementation of the `Store` trait.
@@ -82,6 +392,21 @@ pub trait Store {
 pub struct JsonStore {
     /// The path to the JSON file where tasks are stored.
     path: String,
+
+    /// How timestamps are written to the file.
+    timestamp_format: TimestampFormat,
+
+    /// Whether saving through a symlink replaces its target rather than the link itself.
+    preserve_symlinks: bool,
+
+    /// Whether changes are kept in memory instead of being written to the file.
+    dry_run: bool,
+
+    /// The tasks as changed during a dry run, or `None` if nothing has changed yet.
+    unsaved: RefCell<Option<Vec<Task>>>,
+
+    /// Whether the warning about timestamps in the future has been printed.
+    warned_future: Cell<bool>,
 }
 
 impl JsonStore {
@@ -95,18 +420,83 @@ impl JsonStore {
     ///
     /// * `JsonStore` - A new instance of `JsonStore`.
     pub fn new(path: impl Into<String>) -> Self {
-        Self { path: path.into() }
+        Self {
+            path: path.into(),
+            timestamp_format: TimestampFormat::default(),
+            preserve_symlinks: true,
+            dry_run: false,
+            unsaved: RefCell::new(None),
+            warned_future: Cell::new(false),
+        }
+    }
+
+    /// Sets whether saving through a symlink keeps the link.
+    ///
+    /// Saves write a temporary file and rename it over the store, so that a crash never leaves
+    /// a half-written file. If the store is a symlink, e.g. into a dotfiles repository, the
+    /// rename happens in the link's target directory by default, so the link keeps pointing at
+    /// the updated file. Without this, the link is replaced by a regular file.
+    ///
+    /// # Arguments
+    ///
+    /// * `preserve_symlinks` - Whether to save into a symlink's target.
+    ///
+    /// # Returns
+    ///
+    /// * `JsonStore` - The store, saving symlinks as given.
+    pub fn with_preserve_symlinks(self, preserve_symlinks: bool) -> Self {
+        Self { preserve_symlinks, ..self }
+    }
+
+    /// Sets how timestamps are written to the file; they are read in either format.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_format` - The format to write timestamps in.
+    ///
+    /// # Returns
+    ///
+    /// * `JsonStore` - The store, writing timestamps in the given format.
+    pub fn with_timestamp_format(self, timestamp_format: TimestampFormat) -> Self {
+        Self { timestamp_format, ..self }
+    }
+
+    /// Sets whether changes are kept in memory instead of being written to the file.
+    ///
+    /// In a dry run, every operation behaves as usual, and later reads see earlier changes, but
+    /// the file is never written or removed, and backups are refused.
+    ///
+    /// # Arguments
+    ///
+    /// * `dry_run` - Whether to keep changes in memory.
+    ///
+    /// # Returns
+    ///
+    /// * `JsonStore` - The store, saving to the file or not as given.
+    pub fn with_dry_run(self, dry_run: bool) -> Self {
+        Self { dry_run, ..self }
     }
 
     /// Loads tasks from the JSON file.
     ///
+    /// A leading UTF-8 byte order mark is ignored, and UTF-16 files are transcoded with a warning.
+    /// The next save writes the file back as plain UTF-8.
+    ///
     /// # Returns
     ///
     /// * `Result<Vec<Task>, TaskError>` - Returns a vector of tasks loaded from the JSON file, or a `TaskError` if an error occurs.
     fn load(&self) -> Result<Vec<Task>, TaskError> {
+        if let Some(tasks) = self.unsaved.borrow().as_ref() {
+            return Ok(tasks.clone());
+        }
         let path = std::path::Path::new(&self.path);
         if path.exists() {
-            let data = std::fs::read_to_string(path)?;
+            let bytes = retry(|| std::fs::read(path))?;
+            let (data, transcoded) =
+                decode(&bytes).ok_or_else(|| TaskError::Encoding(self.path.clone()))?;
+            if transcoded {
+                eprintln!("Warning: {} is UTF-16 encoded and will be saved as UTF-8", self.path);
+            }
             Ok(serde_json::from_str(&data)?)
         } else {
             Ok(Vec::new())
@@ -115,6 +505,15 @@ impl JsonStore {
 
     /// Saves tasks to the JSON file.
     ///
+    /// Before writing, any `updated_at` that lies before its task's `created_at` is raised to
+    /// `created_at`. If any timestamps lie in the future, a warning is printed the first time the
+    /// store saves them; use `tasg doctor --fix` to clamp them. Timestamps are written in the
+    /// store's `TimestampFormat`.
+    ///
+    /// The file is replaced atomically; see `with_preserve_symlinks` for how symlinks are handled.
+    /// In a dry run, the tasks are kept in memory instead; see `with_dry_run`.
+    /// If a symlink cannot be resolved, a warning is printed and the file is written through it.
+    ///
     /// # Arguments
     ///
     /// * `tasks` - A slice of tasks to be saved to the JSON file.
@@ -123,8 +522,152 @@ impl JsonStore {
     ///
     /// * `Result<(), TaskError>` - Returns `Ok(())` if the tasks are successfully saved, or a `TaskError` if an error occurs.
     fn save(&self, tasks: &[Task]) -> Result<(), TaskError> {
-        let data = serde_json::to_string(tasks)?;
-        Ok(std::fs::write(&self.path, data)?)
+        let now = chrono::Local::now();
+        let mut tasks = tasks.to_vec();
+        for task in &mut tasks {
+            task.updated_at = task.updated_at.max(task.created_at);
+        }
+        let future = tasks.iter().filter(|t| t.created_at > now || t.updated_at > now).count();
+        if future > 0 && !self.warned_future.replace(true) {
+            eprintln!(
+                "Warning: {} task(s) have a timestamp in the future; run `tasg doctor --fix` to clamp them",
+                future
+            );
+        }
+        if self.dry_run {
+            *self.unsaved.borrow_mut() = Some(tasks);
+            return Ok(());
+        }
+        let data = match self.timestamp_format {
+            TimestampFormat::Rfc3339 => serde_json::to_string(&tasks)?,
+            TimestampFormat::Epoch => {
+                let mut value = serde_json::to_value(&tasks)?;
+                self.timestamp_format.apply(&mut value);
+                serde_json::to_string(&value)?
+            }
+        };
+        let path = Path::new(&self.path);
+        if !self.preserve_symlinks || !path.is_symlink() {
+            return Ok(write_atomically(path, &data)?);
+        }
+        match std::fs::canonicalize(path) {
+            Ok(target) => Ok(write_atomically(&target, &data)?),
+            Err(_) => {
+                eprintln!(
+                    "Warning: cannot resolve the symlink {}; writing through it in place",
+                    self.path
+                );
+                Ok(retry(|| std::fs::write(path, &data))?)
+            }
+        }
+    }
+}
+
+/// Writes a file by writing a temporary file next to it and renaming it into place.
+///
+/// Readers see either the old or the new contents, never a partial write. A symlink at `path`
+/// is replaced, not followed; resolve it first to write its target.
+///
+/// # Arguments
+///
+/// * `path` - The file to write.
+/// * `data` - The new contents of the file.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - `Ok(())` if the file was replaced, or the error from writing or renaming; the temporary file is removed on failure.
+pub(crate) fn write_atomically(path: &Path, data: &str) -> io::Result<()> {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    let result = retry(|| std::fs::write(&temp, data))
+        .and_then(|_| copy_permissions(path, &temp))
+        .and_then(|_| std::fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Gives a file the permissions of another, so that a file written in its place is no more
+/// readable than the original.
+///
+/// # Arguments
+///
+/// * `from` - The file whose permissions are copied; nothing is done if it does not exist.
+/// * `to` - The file to change.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - `Ok(())` if the permissions were copied or `from` does not exist, or the error from changing them.
+pub fn copy_permissions(from: &Path, to: &Path) -> io::Result<()> {
+    match std::fs::metadata(from) {
+        Ok(metadata) => std::fs::set_permissions(to, metadata.permissions()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether an I/O error is likely to go away if the operation is retried.
+fn is_transient(error: &io::Error) -> bool {
+    matches!(error.kind(), io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock)
+}
+
+/// Runs an I/O operation, retrying it with exponential backoff while it fails with a transient error.
+///
+/// Non-transient errors are returned immediately. After `RETRY_ATTEMPTS` attempts, the last
+/// transient error is returned.
+///
+/// # Arguments
+///
+/// * `operation` - The operation to run, e.g. reading or writing the tasks file.
+///
+/// # Returns
+///
+/// * `io::Result<T>` - The result of the first attempt that did not fail transiently, or of the last attempt.
+fn retry<T>(mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut backoff = RETRY_BACKOFF;
+    for _ in 1..RETRY_ATTEMPTS {
+        match operation() {
+            Err(e) if is_transient(&e) => {
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+    operation()
+}
+
+/// Decodes the contents of a tasks file.
+///
+/// UTF-8 is expected, optionally preceded by a byte order mark. UTF-16 is recognised by its byte
+/// order mark or, failing that, by a zero byte in the first code unit (JSON always starts with an
+/// ASCII character).
+///
+/// # Arguments
+///
+/// * `bytes` - The raw contents of the file.
+///
+/// # Returns
+///
+/// * `Option<(String, bool)>` - The decoded text and whether it was transcoded from UTF-16, or `None` if the contents are not valid UTF-8 or UTF-16.
+fn decode(bytes: &[u8]) -> Option<(String, bool)> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let chunks = bytes.chunks_exact(2);
+        if !chunks.remainder().is_empty() {
+            return None;
+        }
+        let units: Vec<u16> = chunks.map(|c| from_bytes([c[0], c[1]])).collect();
+        String::from_utf16(&units).ok().map(|text| (text, true))
+    };
+
+    match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8(rest.to_vec()).ok().map(|t| (t, false)),
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [first, 0, ..] if *first != 0 => utf16(bytes, u16::from_le_bytes),
+        [0, second, ..] if *second != 0 => utf16(bytes, u16::from_be_bytes),
+        _ => String::from_utf8(bytes.to_vec()).ok().map(|t| (t, false)),
     }
 }
 
@@ -140,11 +683,21 @@ impl Store for JsonStore {
     /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully added, or a `TaskError` if an error occurs.
     fn add(&self, task: Task) -> Result<(), TaskError> {
         let mut tasks = self.load()?;
+        if let Some(key) = &task.idempotency_key {
+            if let Some(existing) =
+                tasks.iter().find(|t| t.idempotency_key.as_deref() == Some(key.as_str()))
+            {
+                return Err(TaskError::InvalidInput(format!(
+                    "Idempotency key '{}' is already used by task {}",
+                    key, existing.id
+                )));
+            }
+        }
         tasks.push(task);
         self.save(&tasks)
     }
 
-    /// Lists all tasks or only incomplete tasks.
+    /// Lists all tasks or only incomplete tasks, in ascending ID order.
     ///
     /// # Arguments
     ///
@@ -152,10 +705,29 @@ impl Store for JsonStore {
     ///
     /// # Returns
     ///
-    /// * `Result<Vec<Task>, TaskError>` - Returns a vector of tasks, or a `TaskError` if an error occurs.
+    /// * `Result<Vec<Task>, TaskError>` - Returns a vector of tasks ordered by ID, or a `TaskError` if an error occurs.
     fn list(&self, all: bool) -> Result<Vec<Task>, TaskError> {
+        let mut tasks: Vec<Task> = self
+            .load()?
+            .into_iter()
+            .filter(|t| !t.is_deleted() && (all || !t.is_completed()))
+            .collect();
+        tasks.sort_by_key(|t| t.id);
+        Ok(tasks)
+    }
+
+    /// Counts the tasks in the JSON store, without sorting or copying them.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<TaskCounts, TaskError>` - Returns the counts, or a `TaskError` if the file cannot be read.
+    fn count(&self) -> Result<TaskCounts, TaskError> {
         let tasks = self.load()?;
-        Ok(if all { tasks } else { tasks.into_iter().filter(|t| !t.completed).collect() })
+        let (total, completed) =
+            tasks.iter().filter(|t| !t.is_deleted()).fold((0, 0), |(total, completed), t| {
+                (total + 1, completed + usize::from(t.is_completed()))
+            });
+        Ok(TaskCounts { total, completed, pending: total - completed })
     }
 
     /// Marks a task as complete in the JSON store.
@@ -169,15 +741,38 @@ impl Store for JsonStore {
     /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully marked as complete, or a `TaskError` if the task is not found.
     fn complete(&self, id: u32) -> Result<(), TaskError> {
         let mut tasks = self.load()?;
-        if let Some(task) = tasks.iter_mut().find(|t| t.id == id) {
-            task.completed = true;
+        if let Some(task) = tasks.iter_mut().find(|t| t.id == id && !t.is_deleted()) {
+            task.set_completed(true, chrono::Local::now());
             self.save(&tasks)
         } else {
             Err(TaskError::NotFound(id))
         }
     }
 
-    /// Deletes a task from the JSON store.
+    /// Marks several tasks as complete in the JSON store, loading and saving the file only once.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the tasks to be marked as complete.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the tasks are successfully marked as complete, or a `TaskError` if any task is not found.
+    fn complete_many(&self, ids: &[u32]) -> Result<(), TaskError> {
+        let mut tasks = self.load()?;
+        if let Some(&id) =
+            ids.iter().find(|&&id| !tasks.iter().any(|t| t.id == id && !t.is_deleted()))
+        {
+            return Err(TaskError::NotFound(id));
+        }
+        let now = chrono::Local::now();
+        for task in tasks.iter_mut().filter(|t| ids.contains(&t.id) && !t.is_deleted()) {
+            task.set_completed(true, now);
+        }
+        self.save(&tasks)
+    }
+
+    /// Permanently deletes a task from the JSON store.
     ///
     /// # Arguments
     ///
@@ -197,35 +792,500 @@ impl Store for JsonStore {
         }
     }
 
-    /// Path to the store.
+    /// Moves a task to the trash in the JSON store by setting its `deleted_at`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task to move to the trash.
     ///
     /// # Returns
     ///
-    /// * `&str` containing the file path to the store.
-    fn path(&self) -> &str {
-        &self.path
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully moved to the trash, or a `TaskError` if the task is not found.
+    fn trash(&self, id: u32) -> Result<(), TaskError> {
+        let mut tasks = self.load()?;
+        if let Some(task) = tasks.iter_mut().find(|t| t.id == id && !t.is_deleted()) {
+            task.deleted_at = Some(chrono::Local::now());
+            self.save(&tasks)
+        } else {
+            Err(TaskError::NotFound(id))
+        }
     }
 
-    fn edit(&self, id: u32, description: Option<String>) -> Result<(), TaskError> {
+    /// Restores a task from the trash in the JSON store by clearing its `deleted_at`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task to restore.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully restored, or a `TaskError` if no task with that ID is in the trash.
+    fn restore(&self, id: u32) -> Result<(), TaskError> {
         let mut tasks = self.load()?;
-        if let Some(task) = tasks.iter_mut().find(|t| t.id == id) {
-            if let Some(new_description) = description {
-                task.description = new_description;
-            }
+        if let Some(task) = tasks.iter_mut().find(|t| t.id == id && t.is_deleted()) {
+            task.deleted_at = None;
             task.updated_at = chrono::Local::now();
             self.save(&tasks)
         } else {
             Err(TaskError::NotFound(id))
         }
     }
-}
 
-#[cfg(test)]
+    /// Lists the tasks in the trash of the JSON store.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Task>, TaskError>` - Returns a vector of the tasks in the trash, or a `TaskError` if an error occurs.
+    fn list_trash(&self) -> Result<Vec<Task>, TaskError> {
+        Ok(self.load()?.into_iter().filter(|t| t.is_deleted()).collect())
+    }
+
+    /// Permanently deletes tasks in the trash of the JSON store.
+    ///
+    /// # Arguments
+    ///
+    /// * `deleted_before` - If set, only tasks moved to the trash before this time are deleted. If `None`, the whole trash is emptied.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, TaskError>` - Returns the number of tasks deleted, or a `TaskError` if an error occurs.
+    fn empty_trash(
+        &self,
+        deleted_before: Option<chrono::DateTime<chrono::Local>>,
+    ) -> Result<usize, TaskError> {
+        let mut tasks = self.load()?;
+        let initial_len = tasks.len();
+        tasks.retain(|t| match (t.deleted_at, deleted_before) {
+            (Some(deleted_at), Some(before)) => deleted_at >= before,
+            (Some(_), None) => false,
+            (None, _) => true,
+        });
+        let purged = initial_len - tasks.len();
+        if purged > 0 {
+            self.save(&tasks)?;
+        }
+        Ok(purged)
+    }
+
+    /// Path to the store.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` containing the file path to the store.
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn edit(&self, id: u32, description: Option<String>) -> Result<(), TaskError> {
+        let mut tasks = self.load()?;
+        if let Some(task) = tasks.iter_mut().find(|t| t.id == id && !t.is_deleted()) {
+            let now = chrono::Local::now();
+            if let Some(new_description) = description {
+                task.set_description(new_description, now);
+            }
+            task.updated_at = now;
+            self.save(&tasks)
+        } else {
+            Err(TaskError::NotFound(id))
+        }
+    }
+
+    /// Sets a named field of a task in the JSON store.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task to change.
+    /// * `field` - The name of the field, e.g. `description`.
+    /// * `value` - The new value of the field.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the field is successfully set, `TaskError::NotFound` if the task is not found, or `TaskError::InvalidInput` if the field is unknown, the value cannot be parsed, or a dependency is on a missing or trashed task or would form a cycle.
+    fn set_field(&self, id: u32, field: &str, value: &str) -> Result<(), TaskError> {
+        let mut tasks = self.load()?;
+        if let Some(task) = tasks.iter_mut().find(|t| t.id == id && !t.is_deleted()) {
+            task.set_field(field, value)?;
+            task.updated_at = chrono::Local::now();
+            if let Some(depends_on) = task.depends_on.filter(|_| field == "depends_on") {
+                check_dependency(&tasks, id, depends_on)?;
+            }
+            self.save(&tasks)
+        } else {
+            Err(TaskError::NotFound(id))
+        }
+    }
+
+    /// Bumps a task's `updated_at` in the JSON store.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task to touch.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the task is successfully touched, or a `TaskError` if the task is not found.
+    fn touch(&self, id: u32) -> Result<(), TaskError> {
+        let mut tasks = self.load()?;
+        if let Some(task) = tasks.iter_mut().find(|t| t.id == id && !t.is_deleted()) {
+            task.updated_at = chrono::Local::now();
+            self.save(&tasks)
+        } else {
+            Err(TaskError::NotFound(id))
+        }
+    }
+
+    /// Replaces all tasks in the JSON store.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - The tasks that make up the new contents of the store.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the tasks are successfully written, or a `TaskError` if an error occurs.
+    fn replace(&self, tasks: &[Task]) -> Result<(), TaskError> {
+        self.save(tasks)
+    }
+
+    /// Deletes all tasks by removing the JSON file, if it exists. In a dry run, the file is kept
+    /// and the tasks are only forgotten in memory.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - Returns `Ok(())` if the file is successfully removed, or a `TaskError` if an error occurs.
+    fn nuke(&self) -> Result<(), TaskError> {
+        if self.dry_run {
+            return self.save(&[]);
+        }
+        if std::path::Path::new(&self.path).exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+
+    /// Copies the JSON file to `<stem>-<timestamp>.json` in the backup directory, then prunes
+    /// old backups.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Where backups are kept and how many of them.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf, TaskError>` - Returns the path of the new backup, or a `TaskError` if an error occurs, including `TaskError::InvalidInput` in a dry run.
+    fn backup(&self, policy: &BackupPolicy) -> Result<PathBuf, TaskError> {
+        if self.dry_run {
+            return Err(TaskError::InvalidInput("Backups are not written in a dry run".into()));
+        }
+        let path = Path::new(&self.path);
+        let dir = match &policy.dir {
+            Some(dir) => dir.clone(),
+            None => path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .map_or_else(|| PathBuf::from("."), Path::to_path_buf),
+        };
+        std::fs::create_dir_all(&dir)?;
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("tasks");
+        let timestamp = chrono::Local::now().format(BACKUP_TIMESTAMP_FORMAT);
+        let backup = dir.join(format!("{}-{}.json", stem, timestamp));
+        let data = serde_json::to_string(&self.load()?)?;
+        retry(|| std::fs::write(&backup, &data))?;
+        copy_permissions(path, &backup)?;
+        prune_backups(&dir, stem, policy.keep)?;
+        Ok(backup)
+    }
+}
+
+/// Read-only wrapper around another `Store`.
+///
+/// The `ReadOnlyStore` struct forwards read operations to the wrapped store and rejects every
+/// mutating operation with `TaskError::ReadOnlyMode`, guaranteeing that nothing is written.
+#[derive(Debug)]
+pub struct ReadOnlyStore<S: Store> {
+    /// The wrapped store.
+    inner: S,
+}
+
+impl<S: Store> ReadOnlyStore<S> {
+    /// Creates a new `ReadOnlyStore` wrapping the given store.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The store to wrap.
+    ///
+    /// # Returns
+    ///
+    /// * `ReadOnlyStore<S>` - A new instance of `ReadOnlyStore`.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S: Store> Store for ReadOnlyStore<S> {
+    fn add(&self, _task: Task) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn list(&self, all: bool) -> Result<Vec<Task>, TaskError> {
+        self.inner.list(all)
+    }
+
+    fn count(&self) -> Result<TaskCounts, TaskError> {
+        self.inner.count()
+    }
+
+    fn complete(&self, _id: u32) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn complete_many(&self, _ids: &[u32]) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn delete(&self, _id: u32) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn trash(&self, _id: u32) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn restore(&self, _id: u32) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn list_trash(&self) -> Result<Vec<Task>, TaskError> {
+        self.inner.list_trash()
+    }
+
+    fn empty_trash(
+        &self,
+        _deleted_before: Option<chrono::DateTime<chrono::Local>>,
+    ) -> Result<usize, TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn path(&self) -> &str {
+        self.inner.path()
+    }
+
+    fn edit(&self, _id: u32, _description: Option<String>) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn set_field(&self, _id: u32, _field: &str, _value: &str) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn touch(&self, _id: u32) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn replace(&self, _tasks: &[Task]) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn nuke(&self) -> Result<(), TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+
+    fn backup(&self, _policy: &BackupPolicy) -> Result<PathBuf, TaskError> {
+        Err(TaskError::ReadOnlyMode)
+    }
+}
+
+/// A change made to a store, as reported to the observers of an `ObservedStore`.
+///
+/// # Variants
+///
+/// - `Added` - A task was added.
+/// - `Completed` - Tasks were marked as completed.
+/// - `Deleted` - A task was deleted permanently.
+/// - `Trashed` - A task was moved to the trash.
+/// - `Restored` - A task was restored from the trash.
+/// - `Edited` - A task was edited.
+/// - `FieldSet` - A single field of a task was set.
+/// - `Touched` - A task's update time was bumped.
+/// - `Replaced` - Every task was replaced.
+/// - `Nuked` - Every task was deleted.
+/// - `TrashEmptied` - Tasks in the trash were deleted permanently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A task was added.
+    Added { id: u32 },
+
+    /// Tasks were marked as completed.
+    Completed { ids: Vec<u32> },
+
+    /// A task was deleted permanently.
+    Deleted { id: u32 },
+
+    /// A task was moved to the trash.
+    Trashed { id: u32 },
+
+    /// A task was restored from the trash.
+    Restored { id: u32 },
+
+    /// A task was edited.
+    Edited { id: u32 },
+
+    /// A single field of a task was set.
+    FieldSet { id: u32, field: String },
+
+    /// A task's update time was bumped.
+    Touched { id: u32 },
+
+    /// Every task was replaced.
+    Replaced,
+
+    /// Every task was deleted.
+    Nuked,
+
+    /// Tasks in the trash were deleted permanently.
+    TrashEmptied { count: usize },
+}
+
+/// A callback invoked with each change made to an `ObservedStore`.
+pub type Observer = Box<dyn Fn(&Change)>;
+
+/// Wrapper around another `Store` that notifies observers of changes.
+///
+/// The `ObservedStore` struct forwards every operation to the wrapped store and, after each
+/// mutation that succeeds, calls its observers with the `Change` that was made, e.g. so that a
+/// user interface can refresh. Failed mutations are not reported.
+pub struct ObservedStore<S: Store> {
+    /// The wrapped store.
+    inner: S,
+
+    /// The callbacks invoked after each change, in the order they were registered.
+    observers: Vec<Observer>,
+}
+
+impl<S: Store> ObservedStore<S> {
+    /// Creates a new `ObservedStore` wrapping the given store, with no observers.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The store to wrap.
+    ///
+    /// # Returns
+    ///
+    /// * `ObservedStore<S>` - A new instance of `ObservedStore`.
+    pub fn new(inner: S) -> Self {
+        Self { inner, observers: Vec::new() }
+    }
+
+    /// Registers a callback to be invoked after each change.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - The callback, which is given the change that was made.
+    pub fn observe(&mut self, observer: impl Fn(&Change) + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Notifies the observers of a change if the operation that made it succeeded.
+    fn notify<T>(
+        &self,
+        result: Result<T, TaskError>,
+        change: impl FnOnce(&T) -> Change,
+    ) -> Result<T, TaskError> {
+        if let Ok(value) = &result {
+            let change = change(value);
+            for observer in &self.observers {
+                observer(&change);
+            }
+        }
+        result
+    }
+}
+
+impl<S: Store> Store for ObservedStore<S> {
+    fn add(&self, task: Task) -> Result<(), TaskError> {
+        let id = task.id;
+        self.notify(self.inner.add(task), |_| Change::Added { id })
+    }
+
+    fn list(&self, all: bool) -> Result<Vec<Task>, TaskError> {
+        self.inner.list(all)
+    }
+
+    fn count(&self) -> Result<TaskCounts, TaskError> {
+        self.inner.count()
+    }
+
+    fn complete(&self, id: u32) -> Result<(), TaskError> {
+        self.notify(self.inner.complete(id), |_| Change::Completed { ids: vec![id] })
+    }
+
+    fn complete_many(&self, ids: &[u32]) -> Result<(), TaskError> {
+        self.notify(self.inner.complete_many(ids), |_| Change::Completed { ids: ids.to_vec() })
+    }
+
+    fn delete(&self, id: u32) -> Result<(), TaskError> {
+        self.notify(self.inner.delete(id), |_| Change::Deleted { id })
+    }
+
+    fn trash(&self, id: u32) -> Result<(), TaskError> {
+        self.notify(self.inner.trash(id), |_| Change::Trashed { id })
+    }
+
+    fn restore(&self, id: u32) -> Result<(), TaskError> {
+        self.notify(self.inner.restore(id), |_| Change::Restored { id })
+    }
+
+    fn list_trash(&self) -> Result<Vec<Task>, TaskError> {
+        self.inner.list_trash()
+    }
+
+    fn empty_trash(
+        &self,
+        deleted_before: Option<chrono::DateTime<chrono::Local>>,
+    ) -> Result<usize, TaskError> {
+        self.notify(self.inner.empty_trash(deleted_before), |&count| Change::TrashEmptied { count })
+    }
+
+    fn path(&self) -> &str {
+        self.inner.path()
+    }
+
+    fn edit(&self, id: u32, description: Option<String>) -> Result<(), TaskError> {
+        self.notify(self.inner.edit(id, description), |_| Change::Edited { id })
+    }
+
+    fn set_field(&self, id: u32, field: &str, value: &str) -> Result<(), TaskError> {
+        self.notify(self.inner.set_field(id, field, value), |_| Change::FieldSet {
+            id,
+            field: field.to_string(),
+        })
+    }
+
+    fn touch(&self, id: u32) -> Result<(), TaskError> {
+        self.notify(self.inner.touch(id), |_| Change::Touched { id })
+    }
+
+    fn replace(&self, tasks: &[Task]) -> Result<(), TaskError> {
+        self.notify(self.inner.replace(tasks), |_| Change::Replaced)
+    }
+
+    fn nuke(&self) -> Result<(), TaskError> {
+        self.notify(self.inner.nuke(), |_| Change::Nuked)
+    }
+
+    fn backup(&self, policy: &BackupPolicy) -> Result<PathBuf, TaskError> {
+        self.inner.backup(policy)
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
     use crate::error::TaskError;
     use crate::task::Task;
+    use std::cell::RefCell;
     use std::fs;
+    use std::rc::Rc;
     use tempfile::tempdir;
 
     /// Tests the `add` method of `JsonStore`.
@@ -246,7 +1306,7 @@ mod tests {
         assert_eq!(tasks.len(), 1);
         assert_eq!(tasks[0].id, 1);
         assert_eq!(tasks[0].description, "Test task");
-        assert!(!tasks[0].completed);
+        assert!(!tasks[0].is_completed());
     }
 
     /// Tests the `list` method of `JsonStore`.
@@ -288,7 +1348,7 @@ mod tests {
         let tasks: Vec<Task> = serde_json::from_str(&data).unwrap();
 
         assert_eq!(tasks.len(), 1);
-        assert!(tasks[0].completed);
+        assert!(tasks[0].is_completed());
     }
 
     /// Tests the `complete` method of `JsonStore` when the task is not found.
@@ -406,4 +1466,774 @@ mod tests {
         assert_eq!(tasks.len(), 1);
         assert_eq!(tasks[0].description, "Original task");
     }
+
+    /// Tests that saving never writes an `updated_at` before `created_at`.
+    ///
+    /// This test verifies that the write path raises an invalid `updated_at` to `created_at`.
+    #[test]
+    fn test_save_clamps_updated_before_created() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        let mut task = Task::new(1, String::from("Test task"));
+        task.updated_at = task.created_at - chrono::Duration::hours(1);
+        store.add(task.clone()).unwrap();
+
+        let tasks = store.list(true).unwrap();
+        assert_eq!(tasks[0].updated_at, task.created_at);
+    }
+
+    /// Tests the `replace` method of `JsonStore`.
+    ///
+    /// This test verifies that the entire contents of the store are replaced.
+    #[test]
+    fn test_replace_tasks() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        store.add(Task::new(1, String::from("Old task"))).unwrap();
+        store.replace(&[Task::new(2, String::from("New task"))]).unwrap();
+
+        let tasks = store.list(true).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].description, "New task");
+    }
+
+    /// Tests that a dry run sees its own changes but never writes the file.
+    #[test]
+    fn test_dry_run() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        JsonStore::new(file_path.to_str().unwrap()).add(Task::new(1, "Saved".into())).unwrap();
+        let before = fs::read_to_string(&file_path).unwrap();
+
+        let store = JsonStore::new(file_path.to_str().unwrap()).with_dry_run(true);
+        store.add(Task::new(2, String::from("Unsaved"))).unwrap();
+        store.complete(1).unwrap();
+        let tasks = store.list(true).unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks[0].is_completed());
+
+        store.nuke().unwrap();
+        assert!(store.list(true).unwrap().is_empty());
+        assert!(store.backup(&BackupPolicy::default()).is_err());
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), before);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    /// Tests the `complete_many` method of `JsonStore`.
+    ///
+    /// This test verifies that several tasks can be marked as complete at once.
+    #[test]
+    fn test_complete_many_tasks() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        for id in 1..=3 {
+            store.add(Task::new(id, format!("Test task {}", id))).unwrap();
+        }
+        store.complete_many(&[1, 3]).unwrap();
+
+        let incomplete_tasks = store.list(false).unwrap();
+        assert_eq!(incomplete_tasks.len(), 1);
+        assert_eq!(incomplete_tasks[0].id, 2);
+    }
+
+    /// Tests the `complete_many` method of `JsonStore` when a task is not found.
+    ///
+    /// This test verifies that no task is completed if any of the IDs is not found.
+    #[test]
+    fn test_complete_many_tasks_not_found() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        store.add(Task::new(1, String::from("Test task"))).unwrap();
+
+        let result = store.complete_many(&[1, 2]);
+        assert!(matches!(result, Err(TaskError::NotFound(2))));
+        assert_eq!(store.list(false).unwrap().len(), 1);
+    }
+
+    /// Tests the `count` method of `JsonStore`.
+    ///
+    /// This test verifies that completed and pending tasks are counted, and the trash is not.
+    #[test]
+    fn test_count() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+        assert_eq!(store.count().unwrap(), TaskCounts::default());
+
+        for id in 1..=4 {
+            store.add(Task::new(id, format!("Task {}", id))).unwrap();
+        }
+        store.complete(1).unwrap();
+        store.trash(2).unwrap();
+
+        assert_eq!(store.count().unwrap(), TaskCounts { total: 3, completed: 1, pending: 2 });
+    }
+
+    /// Tests that `ReadOnlyStore` rejects mutations and allows reads.
+    ///
+    /// This test verifies that the wrapped file is left untouched by every mutating method.
+    #[test]
+    fn test_read_only_store() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let inner = JsonStore::new(file_path.to_str().unwrap().to_string());
+        inner.add(Task::new(1, String::from("Test task"))).unwrap();
+        let before = fs::read_to_string(&file_path).unwrap();
+
+        let store = ReadOnlyStore::new(inner);
+        assert!(matches!(
+            store.add(Task::new(2, String::from("New"))),
+            Err(TaskError::ReadOnlyMode)
+        ));
+        assert!(matches!(store.complete(1), Err(TaskError::ReadOnlyMode)));
+        assert!(matches!(store.complete_many(&[1]), Err(TaskError::ReadOnlyMode)));
+        assert!(matches!(store.delete(1), Err(TaskError::ReadOnlyMode)));
+        assert!(matches!(store.trash(1), Err(TaskError::ReadOnlyMode)));
+        assert!(matches!(store.restore(1), Err(TaskError::ReadOnlyMode)));
+        assert!(matches!(store.empty_trash(None), Err(TaskError::ReadOnlyMode)));
+        assert!(matches!(store.set_field(1, "completed", "true"), Err(TaskError::ReadOnlyMode)));
+        assert!(matches!(store.edit(1, None), Err(TaskError::ReadOnlyMode)));
+        assert!(matches!(store.touch(1), Err(TaskError::ReadOnlyMode)));
+        assert!(matches!(store.replace(&[]), Err(TaskError::ReadOnlyMode)));
+        assert!(matches!(store.nuke(), Err(TaskError::ReadOnlyMode)));
+        let backups = dir.path().join("backups");
+        let policy = BackupPolicy { dir: Some(backups.clone()), ..BackupPolicy::default() };
+        assert!(matches!(store.backup(&policy), Err(TaskError::ReadOnlyMode)));
+        assert!(!backups.exists());
+
+        assert_eq!(store.list(true).unwrap().len(), 1);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), before);
+    }
+
+    /// Tests that `ObservedStore` reports successful changes to its observers.
+    ///
+    /// This test verifies that add, complete, and delete are reported, and failures are not.
+    #[test]
+    fn test_observed_store() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let mut store = ObservedStore::new(JsonStore::new(file_path.to_str().unwrap().to_string()));
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::clone(&changes);
+        store.observe(move |change| seen.borrow_mut().push(change.clone()));
+
+        store.add(Task::new(1, String::from("First"))).unwrap();
+        store.add(Task::new(2, String::from("Second"))).unwrap();
+        store.complete(1).unwrap();
+        store.complete_many(&[2]).unwrap();
+        store.delete(1).unwrap();
+        assert!(store.delete(9).is_err());
+        store.list(true).unwrap();
+
+        assert_eq!(
+            *changes.borrow(),
+            vec![
+                Change::Added { id: 1 },
+                Change::Added { id: 2 },
+                Change::Completed { ids: vec![1] },
+                Change::Completed { ids: vec![2] },
+                Change::Deleted { id: 1 },
+            ]
+        );
+    }
+
+    /// Tests loading a file written by a newer version with an unknown field.
+    ///
+    /// This test verifies that unknown fields are ignored rather than failing the load.
+    #[test]
+    fn test_load_ignores_unknown_fields() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        fs::write(
+            &file_path,
+            r#"[{"id":1,"description":"Test task","created_at":"2024-07-01T12:00:00+00:00","updated_at":"2024-07-01T12:00:00+00:00","completed":true,"priority":"high"}]"#,
+        )
+        .unwrap();
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        let tasks = store.list(true).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].description, "Test task");
+        assert!(tasks[0].is_completed());
+    }
+
+    /// Tests loading a file with missing optional fields.
+    ///
+    /// This test verifies that missing fields fall back to their defaults.
+    #[test]
+    fn test_load_defaults_missing_fields() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        fs::write(&file_path, r#"[{"id":1,"description":"Test task"}]"#).unwrap();
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        let tasks = store.list(true).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, 1);
+        assert!(!tasks[0].is_completed());
+    }
+
+    /// Tests that unknown fields survive a load-mutate-save round trip.
+    ///
+    /// This test verifies that saving does not erase data written by a newer version.
+    #[test]
+    fn test_save_preserves_unknown_fields() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        fs::write(
+            &file_path,
+            r#"[{"id":1,"description":"Task 1","priority":"high"},{"id":2,"description":"Task 2","tags":["work"]}]"#,
+        )
+        .unwrap();
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        store.complete(1).unwrap();
+
+        let data = fs::read_to_string(&file_path).unwrap();
+        let tasks: Vec<serde_json::Value> = serde_json::from_str(&data).unwrap();
+        assert_eq!(tasks[0]["completed"], true);
+        assert_eq!(tasks[0]["priority"], "high");
+        assert_eq!(tasks[1]["tags"], serde_json::json!(["work"]));
+    }
+
+    /// Tests the `touch` method of `JsonStore`.
+    ///
+    /// This test verifies that only `updated_at` changes, and that it advances.
+    #[test]
+    fn test_touch_task() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        let mut task = Task::new(1, String::from("Test task"));
+        task.created_at -= chrono::Duration::hours(1);
+        task.updated_at = task.created_at;
+        store.add(task.clone()).unwrap();
+
+        store.touch(1).unwrap();
+
+        let touched = store.list(true).unwrap().remove(0);
+        assert!(touched.updated_at > task.updated_at);
+        assert_eq!(Task { updated_at: task.updated_at, ..touched }, task);
+    }
+
+    /// Tests the `touch` method of `JsonStore` when the task is not found.
+    ///
+    /// This test verifies that an error is returned when attempting to touch a non-existent task.
+    #[test]
+    fn test_touch_task_not_found() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        let result = store.touch(1);
+        assert!(matches!(result, Err(TaskError::NotFound(1))));
+    }
+
+    /// Copies an encoding fixture into a temporary store.
+    fn fixture_store(dir: &tempfile::TempDir, fixture: &[u8]) -> JsonStore {
+        let file_path = dir.path().join("tasks.json");
+        fs::write(&file_path, fixture).unwrap();
+        JsonStore::new(file_path.to_str().unwrap().to_string())
+    }
+
+    /// Tests loading a UTF-8 file with a byte order mark.
+    ///
+    /// This test verifies that the byte order mark is ignored and dropped on save.
+    #[test]
+    fn test_load_utf8_bom() {
+        let dir = tempdir().unwrap();
+        let store = fixture_store(&dir, include_bytes!("../tests/fixtures/encoding/utf8_bom.json"));
+
+        let tasks = store.list(true).unwrap();
+        assert_eq!(tasks[0].description, "Café task");
+
+        store.complete(1).unwrap();
+        let data = fs::read(&store.path).unwrap();
+        assert_eq!(data[0], b'[');
+    }
+
+    /// Tests loading UTF-16 files in either byte order.
+    ///
+    /// This test verifies that UTF-16 is transcoded and saved back as UTF-8.
+    #[test]
+    fn test_load_utf16() {
+        for fixture in [
+            &include_bytes!("../tests/fixtures/encoding/utf16le.json")[..],
+            &include_bytes!("../tests/fixtures/encoding/utf16be.json")[..],
+        ] {
+            let dir = tempdir().unwrap();
+            let store = fixture_store(&dir, fixture);
+
+            let tasks = store.list(true).unwrap();
+            assert_eq!(tasks[0].description, "Café task");
+
+            store.complete(1).unwrap();
+            let data = fs::read_to_string(&store.path).unwrap();
+            assert!(data.starts_with('['));
+        }
+    }
+
+    /// Tests decoding UTF-16 without a byte order mark.
+    #[test]
+    fn test_decode_utf16_without_bom() {
+        let le: Vec<u8> = "[]".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let be: Vec<u8> = "[]".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(decode(&le), Some((String::from("[]"), true)));
+        assert_eq!(decode(&be), Some((String::from("[]"), true)));
+    }
+
+    /// Tests loading a file in an unsupported encoding.
+    ///
+    /// This test verifies that a specific encoding error naming the file is returned.
+    #[test]
+    fn test_load_unsupported_encoding() {
+        let dir = tempdir().unwrap();
+        let store = fixture_store(&dir, include_bytes!("../tests/fixtures/encoding/latin1.json"));
+
+        match store.list(true) {
+            Err(TaskError::Encoding(path)) => assert_eq!(path, store.path),
+            other => panic!("Expected TaskError::Encoding, got {:?}", other),
+        }
+    }
+
+    /// Tests the `trash` method of `JsonStore`.
+    ///
+    /// This test verifies that a task in the trash is hidden from `list` but kept in the file.
+    #[test]
+    fn test_trash_task() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        store.add(Task::new(1, String::from("Test task 1"))).unwrap();
+        store.add(Task::new(2, String::from("Test task 2"))).unwrap();
+        store.trash(1).unwrap();
+
+        let tasks = store.list(true).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, 2);
+
+        let trash = store.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, 1);
+        assert!(trash[0].deleted_at.is_some());
+
+        assert!(matches!(store.complete(1), Err(TaskError::NotFound(1))));
+        assert!(matches!(store.trash(1), Err(TaskError::NotFound(1))));
+    }
+
+    /// Tests the `restore` method of `JsonStore`.
+    ///
+    /// This test verifies that a task restored from the trash is listed again.
+    #[test]
+    fn test_restore_task() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        store.add(Task::new(1, String::from("Test task"))).unwrap();
+        store.trash(1).unwrap();
+        store.restore(1).unwrap();
+
+        let tasks = store.list(true).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert!(tasks[0].deleted_at.is_none());
+        assert!(store.list_trash().unwrap().is_empty());
+
+        assert!(matches!(store.restore(1), Err(TaskError::NotFound(1))));
+    }
+
+    /// Tests the `delete` method of `JsonStore` on a task in the trash.
+    ///
+    /// This test verifies that a task in the trash can be permanently deleted.
+    #[test]
+    fn test_delete_trashed_task() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        store.add(Task::new(1, String::from("Test task"))).unwrap();
+        store.trash(1).unwrap();
+        store.delete(1).unwrap();
+
+        assert!(store.list_trash().unwrap().is_empty());
+    }
+
+    /// Tests the `empty_trash` method of `JsonStore`.
+    ///
+    /// This test verifies that only tasks in the trash are permanently deleted.
+    #[test]
+    fn test_empty_trash() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        for id in 1..=3 {
+            store.add(Task::new(id, format!("Test task {}", id))).unwrap();
+        }
+        store.trash(1).unwrap();
+        store.trash(3).unwrap();
+
+        assert_eq!(store.empty_trash(None).unwrap(), 2);
+
+        let data = fs::read_to_string(&store.path).unwrap();
+        let tasks: Vec<Task> = serde_json::from_str(&data).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, 2);
+    }
+
+    /// Tests the `empty_trash` method of `JsonStore` with a cut-off time.
+    ///
+    /// This test verifies that only tasks moved to the trash before the cut-off are deleted.
+    #[test]
+    fn test_empty_trash_deleted_before() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        let now = chrono::Local::now();
+        let mut old = Task::new(1, String::from("Old task"));
+        old.deleted_at = Some(now - chrono::Duration::days(10));
+        let mut recent = Task::new(2, String::from("Recent task"));
+        recent.deleted_at = Some(now - chrono::Duration::days(1));
+        store.replace(&[old, recent]).unwrap();
+
+        assert_eq!(store.empty_trash(Some(now - chrono::Duration::days(7))).unwrap(), 1);
+
+        let trash = store.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, 2);
+    }
+
+    /// Tests that the `add` method of `JsonStore` enforces unique idempotency keys.
+    ///
+    /// This test verifies that a key cannot be reused, even by a task in the trash.
+    #[test]
+    fn test_add_duplicate_idempotency_key() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        let mut task = Task::new(1, String::from("Deploy v2"));
+        task.idempotency_key = Some(String::from("deploy-v2"));
+        store.add(task).unwrap();
+        store.trash(1).unwrap();
+
+        let mut duplicate = Task::new(2, String::from("Deploy v2 again"));
+        duplicate.idempotency_key = Some(String::from("deploy-v2"));
+        let result = store.add(duplicate);
+        assert!(matches!(result, Err(TaskError::InvalidInput(_))));
+
+        let mut other = Task::new(2, String::from("Deploy v3"));
+        other.idempotency_key = Some(String::from("deploy-v3"));
+        store.add(other).unwrap();
+        store.add(Task::new(3, String::from("No key"))).unwrap();
+        store.add(Task::new(4, String::from("No key either"))).unwrap();
+        assert_eq!(store.list(true).unwrap().len(), 3);
+    }
+
+    /// Tests that the `list` method of `JsonStore` returns tasks in ID order.
+    ///
+    /// This test verifies that tasks stored out of order, e.g. after a manual edit, are sorted.
+    #[test]
+    fn test_list_sorted_by_id() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+
+        let tasks: Vec<Task> =
+            [3, 1, 10, 2].iter().map(|&id| Task::new(id, format!("Task {}", id))).collect();
+        fs::write(&file_path, serde_json::to_string(&tasks).unwrap()).unwrap();
+
+        let ids: Vec<u32> = store.list(true).unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 10]);
+    }
+
+    /// Tests the `set_field` method of `JsonStore`.
+    ///
+    /// This test verifies that the field is saved and that unknown tasks are reported.
+    #[test]
+    fn test_set_field() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+        store.add(Task::new(1, String::from("Test task"))).unwrap();
+
+        store.set_field(1, "description", "Renamed task").unwrap();
+        store.set_field(1, "completed", "true").unwrap();
+        let tasks = store.list(true).unwrap();
+        assert_eq!(tasks[0].description, "Renamed task");
+        assert!(tasks[0].is_completed());
+
+        assert!(matches!(store.set_field(2, "completed", "true"), Err(TaskError::NotFound(2))));
+        assert!(matches!(store.set_field(1, "priority", "high"), Err(TaskError::InvalidInput(_))));
+    }
+
+    /// Tests that `set_field` only lets a task depend on an existing task outside the trash,
+    /// without forming a cycle.
+    #[test]
+    fn test_set_field_depends_on() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+        for id in 1..=4 {
+            store.add(Task::new(id, format!("Task {}", id))).unwrap();
+        }
+        store.trash(4).unwrap();
+
+        store.set_field(2, "depends_on", "1").unwrap();
+        store.set_field(3, "depends_on", "2").unwrap();
+        assert_eq!(store.list(true).unwrap()[2].depends_on, Some(2));
+
+        let cases = [
+            (1, "9", "Task 1 cannot depend on task 9, which does not exist"),
+            (1, "4", "Task 1 cannot depend on task 4, which is in the trash"),
+            (1, "2", "Task 1 cannot depend on task 2, which already depends on task 1"),
+            (1, "3", "Task 1 cannot depend on task 3, which already depends on task 1"),
+        ];
+        for (id, value, message) in cases {
+            let err = store.set_field(id, "depends_on", value).unwrap_err();
+            assert_eq!(err.to_string(), format!("Invalid input - {}", message));
+        }
+        assert_eq!(store.list(true).unwrap()[0].depends_on, None);
+
+        store.set_field(3, "depends_on", "none").unwrap();
+        store.set_field(1, "depends_on", "3").unwrap();
+    }
+
+    /// A reader and writer that fails with a given error a number of times before succeeding.
+    struct Faulty {
+        kind: io::ErrorKind,
+        failures: u32,
+        calls: u32,
+    }
+
+    impl Faulty {
+        fn new(kind: io::ErrorKind, failures: u32) -> Self {
+            Self { kind, failures, calls: 0 }
+        }
+
+        /// Fails while there are failures left, then returns `len`.
+        fn attempt(&mut self, len: usize) -> io::Result<usize> {
+            self.calls += 1;
+            if self.calls <= self.failures {
+                Err(io::Error::from(self.kind))
+            } else {
+                Ok(len)
+            }
+        }
+    }
+
+    impl io::Read for Faulty {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.attempt(buf.len().min(2))?;
+            buf[..len].copy_from_slice(&b"[]"[..len]);
+            Ok(len)
+        }
+    }
+
+    impl io::Write for Faulty {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.attempt(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Tests that a read and a write that fail transiently once are retried and succeed.
+    #[test]
+    fn test_retry_transient_error() {
+        use std::io::{Read, Write};
+
+        let mut reader = Faulty::new(io::ErrorKind::WouldBlock, 1);
+        let mut buf = [0; 2];
+        assert_eq!(retry(|| reader.read(&mut buf)).unwrap(), 2);
+        assert_eq!(&buf, b"[]");
+        assert_eq!(reader.calls, 2);
+
+        let mut writer = Faulty::new(io::ErrorKind::Interrupted, 1);
+        assert_eq!(retry(|| writer.write(b"[]")).unwrap(), 2);
+        assert_eq!(writer.calls, 2);
+    }
+
+    /// Tests that retrying gives up with the original error after a few attempts.
+    #[test]
+    fn test_retry_gives_up() {
+        use std::io::Write;
+
+        let mut writer = Faulty::new(io::ErrorKind::WouldBlock, u32::MAX);
+        let error = retry(|| writer.write(b"[]")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(writer.calls, RETRY_ATTEMPTS);
+    }
+
+    /// Tests that non-transient errors are not retried.
+    #[test]
+    fn test_retry_non_transient_error() {
+        use std::io::Write;
+
+        let mut writer = Faulty::new(io::ErrorKind::PermissionDenied, 1);
+        let error = retry(|| writer.write(b"[]")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(writer.calls, 1);
+    }
+
+    /// Tests that `prune_backups` keeps the most recent backups by the timestamp in their name.
+    ///
+    /// This test verifies that the oldest backups are deleted and unrelated files are left alone.
+    #[test]
+    fn test_prune_backups() {
+        let dir = tempdir().unwrap();
+        let names = [
+            "tasks-20240703T090000.000000.json",
+            "tasks-20240701T090000.000000.json",
+            "tasks-20240704T090000.000000.json",
+            "tasks-20240702T090000.000000.json",
+            "tasks.json",
+            "notes-20240601T090000.000000.json",
+        ];
+        for name in names {
+            fs::write(dir.path().join(name), "[]").unwrap();
+        }
+
+        let pruned = prune_backups(dir.path(), "tasks", 2).unwrap();
+        let mut pruned: Vec<_> = pruned.iter().map(|p| p.file_name().unwrap().to_owned()).collect();
+        pruned.sort();
+        assert_eq!(
+            pruned,
+            ["tasks-20240701T090000.000000.json", "tasks-20240702T090000.000000.json"]
+        );
+
+        let mut remaining: Vec<_> =
+            fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            [
+                "notes-20240601T090000.000000.json",
+                "tasks-20240703T090000.000000.json",
+                "tasks-20240704T090000.000000.json",
+                "tasks.json",
+            ]
+        );
+    }
+
+    /// Tests the `backup` method of `JsonStore` with more backups than the policy keeps.
+    ///
+    /// This test verifies that the newest backups are kept in the configured directory.
+    #[test]
+    fn test_backup_rotation() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        let store = JsonStore::new(file_path.to_str().unwrap().to_string());
+        let policy = BackupPolicy { keep: 3, dir: Some(dir.path().join("backups")) };
+
+        let mut backups = Vec::new();
+        for id in 1..=5 {
+            store.add(Task::new(id, format!("Test task {}", id))).unwrap();
+            backups.push(store.backup(&policy).unwrap());
+            thread::sleep(Duration::from_millis(2));
+        }
+
+        for (index, backup) in backups.iter().enumerate() {
+            assert_eq!(backup.exists(), index >= 2, "{}", backup.display());
+        }
+        let latest: Vec<Task> =
+            serde_json::from_str(&fs::read_to_string(&backups[4]).unwrap()).unwrap();
+        assert_eq!(latest.len(), 5);
+    }
+
+    /// Creates a store at `tasks.json` in `dir` that is a symlink to `target.json`.
+    #[cfg(unix)]
+    fn symlinked_store(dir: &Path) -> (PathBuf, PathBuf) {
+        let target = dir.join("target.json");
+        let link = dir.join("tasks.json");
+        fs::write(&target, "[]").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        (link, target)
+    }
+
+    /// Tests that saving through a symlink keeps the link and updates its target.
+    #[cfg(unix)]
+    #[test]
+    fn test_save_preserves_symlink() {
+        let dir = tempdir().unwrap();
+        let (link, target) = symlinked_store(dir.path());
+        let store = JsonStore::new(link.to_str().unwrap());
+
+        store.add(Task::new(1, String::from("First"))).unwrap();
+        store.add(Task::new(2, String::from("Second"))).unwrap();
+        store.complete(1).unwrap();
+
+        assert!(link.is_symlink());
+        assert_eq!(fs::read_link(&link).unwrap(), target);
+        let tasks: Vec<Task> = serde_json::from_str(&fs::read_to_string(&target).unwrap()).unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks[0].is_completed());
+        let leftovers = fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(leftovers, 2, "temporary files were left behind");
+    }
+
+    /// Tests that the link is replaced by a regular file when symlinks are not preserved.
+    #[cfg(unix)]
+    #[test]
+    fn test_save_replaces_symlink() {
+        let dir = tempdir().unwrap();
+        let (link, target) = symlinked_store(dir.path());
+        let store = JsonStore::new(link.to_str().unwrap()).with_preserve_symlinks(false);
+
+        store.add(Task::new(1, String::from("First"))).unwrap();
+
+        assert!(!link.is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "[]");
+        assert_eq!(store.list(false).unwrap().len(), 1);
+    }
+
+    /// Tests that a dangling symlink is written through rather than replaced.
+    #[cfg(unix)]
+    #[test]
+    fn test_save_dangling_symlink() {
+        let dir = tempdir().unwrap();
+        let (link, target) = symlinked_store(dir.path());
+        fs::remove_file(&target).unwrap();
+        let store = JsonStore::new(link.to_str().unwrap());
+
+        store.add(Task::new(1, String::from("First"))).unwrap();
+
+        assert!(link.is_symlink());
+        assert_eq!(store.list(false).unwrap().len(), 1);
+        assert!(target.exists());
+    }
+
+    /// Tests that saving and backing up keep the permissions of the tasks file.
+    #[cfg(unix)]
+    #[test]
+    fn test_save_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tasks.json");
+        fs::write(&file_path, "[]").unwrap();
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o600)).unwrap();
+        let store = JsonStore::new(file_path.to_str().unwrap());
+
+        store.add(Task::new(1, String::from("First"))).unwrap();
+        let policy = BackupPolicy { keep: 1, dir: None };
+        let backup = store.backup(&policy).unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&file_path), 0o600);
+        assert_eq!(mode(&backup), 0o600);
+    }
 }
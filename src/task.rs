@@ -4,22 +4,47 @@
 //! encapsulating the `Task` struct, which represents individual tasks in the system.
 //! It includes the structure of a task along with methods for creating and managing tasks.
 
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 
+use crate::error::TaskError;
+
 /// Represents a task in the system.
 ///
 /// The `Task` struct is the core data model for the task management application. It contains
 /// the essential information about a task, including its unique identifier, description, creation
-/// and update timestamps, and completion status.
+/// and update timestamps, and status.
 ///
 /// # Fields
 ///
 /// - `id` - A unique identifier for the task.
 /// - `description` - A brief description of the task.
+/// - `notes` - Longer notes about the task, if any, which may span several lines.
 /// - `created_at` - The timestamp when the task was created.
 /// - `updated_at` - The timestamp when the task was last updated.
-/// - `completed` - A boolean indicating whether the task has been completed.
+/// - `description_updated_at` - The timestamp when the description was last changed, if it has been since `tasg` started recording it.
+/// - `status` - Where the task stands: to do, in progress, done, or blocked.
+/// - `completed_at` - The timestamp when the task was completed, if it has been since `tasg` started recording it.
+/// - `deleted_at` - The timestamp when the task was moved to the trash, if it has been.
+/// - `idempotency_key` - A key that identifies the `add` command that created the task, if one was given.
+/// - `depends_on` - The ID of the task this task depends on, if any.
+/// - `extra` - Fields this version of `tasg` does not know about.
+///
+/// # Compatibility
+///
+/// Only `id` is required when deserializing; every other field falls back to its default if
+/// missing. Unknown fields are captured in `extra` and written back on serialization, so task
+/// files written by newer versions of `tasg` can be loaded and saved without losing data.
+/// Timestamps are read as RFC 3339 strings or Unix epoch seconds; see `crate::timestamp`.
+///
+/// Tasks are written with both `status` and the `completed` flag that older versions of `tasg`
+/// used instead, so those versions still see which tasks are done. See `TaskRecord` for how the
+/// two are reconciled when a task is read.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(from = "TaskRecord", into = "TaskRecord")]
 pub struct Task {
     /// A unique identifier for the task.
     pub id: u32,
@@ -27,22 +52,294 @@ pub struct Task {
     /// A brief description of the task.
     pub description: String,
 
+    /// Longer notes about the task, which may span several lines, or `None` if it has none.
+    pub notes: Option<String>,
+
     /// The timestamp when the task was created.
     pub created_at: chrono::DateTime<chrono::Local>,
 
     /// The timestamp when the task was last updated.
     pub updated_at: chrono::DateTime<chrono::Local>,
 
-    /// Indicates whether the task has been completed.
-    pub completed: bool,
+    /// The timestamp when the description was last changed, or `None` if it has not changed
+    /// since the task was created or was changed before this field existed.
+    pub description_updated_at: Option<chrono::DateTime<chrono::Local>>,
+
+    /// Where the task stands.
+    pub status: Status,
+
+    /// The timestamp when the task was completed, or `None` if it is not done or was completed
+    /// before this field existed.
+    pub completed_at: Option<chrono::DateTime<chrono::Local>>,
+
+    /// The timestamp when the task was moved to the trash, or `None` if it has not been.
+    pub deleted_at: Option<chrono::DateTime<chrono::Local>>,
+
+    /// A key that identifies the `add` command that created the task, unique within the store.
+    pub idempotency_key: Option<String>,
+
+    /// The ID of the task this task depends on, or `None` if it has no dependency.
+    pub depends_on: Option<u32>,
+
+    /// Fields this version of `tasg` does not know about, preserved for round-trip safety.
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A task as it is stored in the task file.
+///
+/// This mirrors `Task`, but keeps both `status` and the older `completed` flag. When a task is
+/// read, `status` is used if present, unless `completed` contradicts it, which happens when an
+/// older version of `tasg` completed or reopened the task: a completed task is then `Done`, and
+/// a reopened one `Todo`. Files from before `status` existed only have `completed`, which maps
+/// to `Done` or `Todo`.
+#[derive(Serialize, Deserialize)]
+struct TaskRecord {
+    id: u32,
+
+    #[serde(default)]
+    description: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+
+    #[serde(default, with = "crate::timestamp")]
+    created_at: chrono::DateTime<chrono::Local>,
+
+    #[serde(default, with = "crate::timestamp")]
+    updated_at: chrono::DateTime<chrono::Local>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::timestamp::option")]
+    description_updated_at: Option<chrono::DateTime<chrono::Local>>,
+
+    #[serde(default)]
+    completed: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<Status>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::timestamp::option")]
+    completed_at: Option<chrono::DateTime<chrono::Local>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::timestamp::option")]
+    deleted_at: Option<chrono::DateTime<chrono::Local>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    idempotency_key: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    depends_on: Option<u32>,
+
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl From<TaskRecord> for Task {
+    /// Reads a task from its stored form, reconciling `status` with `completed`.
+    fn from(record: TaskRecord) -> Self {
+        let status = match (record.status, record.completed) {
+            (Some(Status::Done), Some(false)) => Status::Todo,
+            (Some(status), Some(true)) if status != Status::Done => Status::Done,
+            (Some(status), _) => status,
+            (None, Some(true)) => Status::Done,
+            (None, _) => Status::Todo,
+        };
+        Task {
+            id: record.id,
+            description: record.description,
+            notes: record.notes,
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+            description_updated_at: record.description_updated_at,
+            status,
+            completed_at: record.completed_at,
+            deleted_at: record.deleted_at,
+            idempotency_key: record.idempotency_key,
+            depends_on: record.depends_on,
+            extra: record.extra,
+        }
+    }
+}
+
+impl From<Task> for TaskRecord {
+    /// Writes a task in its stored form, with `completed` derived from the status.
+    fn from(task: Task) -> Self {
+        TaskRecord {
+            id: task.id,
+            description: task.description,
+            notes: task.notes,
+            created_at: task.created_at,
+            updated_at: task.updated_at,
+            description_updated_at: task.description_updated_at,
+            completed: Some(task.status == Status::Done),
+            status: Some(task.status),
+            completed_at: task.completed_at,
+            deleted_at: task.deleted_at,
+            idempotency_key: task.idempotency_key,
+            depends_on: task.depends_on,
+            extra: task.extra,
+        }
+    }
+}
+
+/// Where a task stands.
+///
+/// # Variants
+///
+/// - `Todo` - The task has not been started.
+/// - `InProgress` - The task is being worked on.
+/// - `Done` - The task has been completed.
+/// - `Blocked` - The task cannot go ahead until something else happens.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// The task has not been started.
+    #[default]
+    Todo,
+
+    /// The task is being worked on.
+    InProgress,
+
+    /// The task has been completed.
+    Done,
+
+    /// The task cannot go ahead until something else happens.
+    Blocked,
+}
+
+impl Status {
+    /// Every status, in the order a task usually moves through them.
+    pub const ALL: &'static [Status] =
+        &[Status::Todo, Status::InProgress, Status::Blocked, Status::Done];
+
+    /// The status's name, as written in the task file and accepted on the command line.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The name, e.g. `in_progress`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in_progress",
+            Status::Done => "done",
+            Status::Blocked => "blocked",
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    /// Formats the `Status` for display purposes, e.g. `In progress`.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter used to write the formatted string.
+    ///
+    /// # Returns
+    ///
+    /// * `fmt::Result` - The result of the formatting operation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Todo => "Todo",
+            Status::InProgress => "In progress",
+            Status::Done => "Done",
+            Status::Blocked => "Blocked",
+        })
+    }
+}
+
+impl FromStr for Status {
+    type Err = TaskError;
+
+    /// Parses a status name, ignoring case and accepting `-` or a space in place of `_`.
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The status name, e.g. `in-progress`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Self::Err>` - The status, or `TaskError::InvalidInput` naming the valid statuses.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase().replace(['-', ' '], "_");
+        Status::ALL.iter().copied().find(|status| status.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = Status::ALL.iter().map(|status| status.name()).collect();
+            TaskError::InvalidInput(format!(
+                "Unknown status '{}'; expected one of: {}",
+                s.trim(),
+                names.join(", ")
+            ))
+        })
+    }
+}
+
+/// Which change to a task `list --changed-since` looks at.
+///
+/// # Variants
+///
+/// - `Any` - Any change, as recorded by `updated_at`.
+/// - `Description` - A change to the description, as recorded by `description_updated_at`.
+/// - `Completed` - Completing the task, as recorded by `completed_at`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChangedField {
+    /// Any change.
+    #[default]
+    Any,
+
+    /// A change to the description.
+    Description,
+
+    /// Completing the task.
+    Completed,
+}
+
+impl ChangedField {
+    /// Every kind of change, in the order they are listed in help and error messages.
+    pub const ALL: &'static [ChangedField] =
+        &[ChangedField::Any, ChangedField::Description, ChangedField::Completed];
+
+    /// The name accepted on the command line.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The name, e.g. `description`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChangedField::Any => "any",
+            ChangedField::Description => "description",
+            ChangedField::Completed => "completed",
+        }
+    }
+}
+
+impl FromStr for ChangedField {
+    type Err = TaskError;
+
+    /// Parses the name of a kind of change, ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The name, e.g. `description`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Self::Err>` - The kind of change, or `TaskError::InvalidInput` naming the valid ones.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        ChangedField::ALL.iter().copied().find(|field| field.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = ChangedField::ALL.iter().map(|field| field.name()).collect();
+            TaskError::InvalidInput(format!(
+                "Unknown field '{}'; expected one of: {}",
+                s.trim(),
+                names.join(", ")
+            ))
+        })
+    }
 }
 
 impl Task {
     /// Creates a new task with the given ID and description.
     ///
     /// This function initializes a new task with the provided ID and description. The `created_at`
-    /// and `updated_at` fields are set to the current local time, and the `completed` field is set
-    /// to `false` by default.
+    /// and `updated_at` fields are set to the current local time, and the status is `Todo`.
     ///
     /// # Arguments
     ///
@@ -54,6 +351,666 @@ impl Task {
     /// A `Task` instance with the provided ID and description, and the current time as the creation and update times.
     pub fn new(id: u32, description: String) -> Self {
         let now = chrono::Local::now();
-        Self { id, description, created_at: now, updated_at: now, completed: false }
+        Self {
+            id,
+            description,
+            notes: None,
+            created_at: now,
+            updated_at: now,
+            description_updated_at: None,
+            status: Status::Todo,
+            completed_at: None,
+            deleted_at: None,
+            idempotency_key: None,
+            depends_on: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Whether two tasks are the same task, ignoring every field but the ID.
+    ///
+    /// `Task` equality compares every field, so an edited copy of a task is not equal to the
+    /// original; use this where identity is meant instead.
+    ///
+    /// # Arguments
+    ///
+    /// - `other` - The task to compare with.
+    ///
+    /// # Returns
+    ///
+    /// `true` if both tasks have the same ID, `false` otherwise.
+    pub fn same_id(&self, other: &Task) -> bool {
+        self.id == other.id
+    }
+
+    /// Whether the task has been moved to the trash.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the task has been soft-deleted, `false` otherwise.
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Whether the task is done.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the task's status is `Done`, `false` otherwise.
+    pub fn is_completed(&self) -> bool {
+        self.status == Status::Done
+    }
+
+    /// Changes the task's status, recording when it was completed.
+    ///
+    /// Marking a task that is already done as `Done` keeps its original `completed_at`; any other
+    /// status clears it.
+    ///
+    /// # Arguments
+    ///
+    /// - `status` - The new status.
+    /// - `now` - The current time.
+    pub fn set_status(&mut self, status: Status, now: DateTime<Local>) {
+        if status == Status::Done && !self.is_completed() {
+            self.completed_at = Some(now);
+        } else if status != Status::Done {
+            self.completed_at = None;
+        }
+        self.status = status;
+    }
+
+    /// Marks the task as completed or reopens it, recording when it was completed.
+    ///
+    /// Completing a task that is already completed keeps it unchanged; reopening a task sets it
+    /// to `Todo`, and reopening one that is not done keeps its status.
+    ///
+    /// # Arguments
+    ///
+    /// - `completed` - Whether the task is completed.
+    /// - `now` - The current time.
+    pub fn set_completed(&mut self, completed: bool, now: DateTime<Local>) {
+        if completed {
+            self.set_status(Status::Done, now);
+        } else if self.is_completed() {
+            self.set_status(Status::Todo, now);
+        }
+    }
+
+    /// Changes the description, recording when it changed if it is different.
+    ///
+    /// # Arguments
+    ///
+    /// - `description` - The new description.
+    /// - `now` - The current time.
+    pub fn set_description(&mut self, description: String, now: DateTime<Local>) {
+        if description != self.description {
+            self.description = description;
+            self.description_updated_at = Some(now);
+        }
+    }
+
+    /// When the task last had a given kind of change.
+    ///
+    /// A description that has not changed since `description_updated_at` was introduced counts
+    /// as changed when the task was created, so older task files still work.
+    ///
+    /// # Arguments
+    ///
+    /// - `field` - The kind of change.
+    ///
+    /// # Returns
+    ///
+    /// The time of the change, or `None` if the task is not completed or was completed before
+    /// `completed_at` was recorded.
+    pub fn changed_at(&self, field: ChangedField) -> Option<DateTime<Local>> {
+        match field {
+            ChangedField::Any => Some(self.updated_at),
+            ChangedField::Description => {
+                Some(self.description_updated_at.unwrap_or(self.created_at))
+            }
+            ChangedField::Completed => self.completed_at.filter(|_| self.is_completed()),
+        }
+    }
+
+    /// Whether the task was completed on a date within an inclusive range.
+    ///
+    /// # Arguments
+    ///
+    /// - `start` - The first date of the range, in local time.
+    /// - `end` - The last date of the range, in local time.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the task is completed and its `completed_at` falls within the range, `false`
+    /// otherwise, including for tasks completed before `completed_at` was recorded.
+    pub fn completed_between(&self, start: NaiveDate, end: NaiveDate) -> bool {
+        self.is_completed()
+            && self.completed_at.is_some_and(|at| (start..=end).contains(&at.date_naive()))
+    }
+
+    /// The fields that can be changed with `set_field`.
+    pub const SETTABLE_FIELDS: &'static [&'static str] =
+        &["description", "notes", "status", "completed", "depends_on"];
+
+    /// Sets a field of the task from its string form.
+    ///
+    /// The settable fields are `description`, `notes`, `status` (e.g. `in_progress`), `completed`
+    /// (`true`/`false` or `yes`/`no`, a shortcut for the `done` status), and `depends_on` (a task
+    /// ID, or `none` to clear it). `updated_at` is not changed; that is up
+    /// to the caller.
+    ///
+    /// # Arguments
+    ///
+    /// - `field` - The name of the field, as in the task file.
+    /// - `value` - The new value of the field.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the field was set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TaskError::InvalidInput` if the field is unknown or cannot be set, or if the value
+    /// cannot be parsed.
+    pub fn set_field(&mut self, field: &str, value: &str) -> Result<(), TaskError> {
+        let invalid = |expected: &str| {
+            TaskError::InvalidInput(format!(
+                "Invalid value '{}' for {}: expected {}",
+                value, field, expected
+            ))
+        };
+        match field {
+            "description" => {
+                if value.trim().is_empty() {
+                    return Err(TaskError::InvalidInput("Description cannot be empty".into()));
+                }
+                self.set_description(value.to_string(), Local::now());
+            }
+            "notes" => {
+                self.notes = Some(value.trim_end().to_string()).filter(|n| !n.is_empty());
+            }
+            "status" => {
+                let status = value.parse::<Status>().map_err(|_| {
+                    let names: Vec<&str> = Status::ALL.iter().map(|s| s.name()).collect();
+                    invalid(&names.join(", "))
+                })?;
+                self.set_status(status, Local::now());
+            }
+            "completed" => {
+                let completed = match value.to_lowercase().as_str() {
+                    "true" | "yes" => true,
+                    "false" | "no" => false,
+                    _ => return Err(invalid("true or false")),
+                };
+                self.set_completed(completed, Local::now());
+            }
+            "depends_on" => {
+                self.depends_on = match value.to_lowercase().as_str() {
+                    "none" | "" => None,
+                    id => match id.parse::<u32>() {
+                        Ok(id) if id != self.id => Some(id),
+                        _ => return Err(invalid("another task's ID or none")),
+                    },
+                };
+            }
+            _ => {
+                return Err(TaskError::InvalidInput(format!(
+                    "Unknown field '{}'; expected one of: {}",
+                    field,
+                    Task::SETTABLE_FIELDS.join(", ")
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// The fields that can be read with `get_field`.
+    pub const GETTABLE_FIELDS: &'static [&'static str] = &[
+        "id",
+        "description",
+        "notes",
+        "created_at",
+        "updated_at",
+        "description_updated_at",
+        "status",
+        "completed",
+        "completed_at",
+        "deleted_at",
+        "idempotency_key",
+        "depends_on",
+    ];
+
+    /// Gets a field of the task in its raw string form.
+    ///
+    /// Timestamps are formatted as RFC 3339, `status` by its name (e.g. `in_progress`),
+    /// `completed` as `true` or `false`, and fields that are not set as an empty string.
+    ///
+    /// # Arguments
+    ///
+    /// - `field` - The name of the field, as in the task file.
+    ///
+    /// # Returns
+    ///
+    /// The value of the field.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TaskError::InvalidInput` if the field is unknown.
+    pub fn get_field(&self, field: &str) -> Result<String, TaskError> {
+        Ok(match field {
+            "id" => self.id.to_string(),
+            "description" => self.description.clone(),
+            "notes" => self.notes.clone().unwrap_or_default(),
+            "created_at" => self.created_at.to_rfc3339(),
+            "updated_at" => self.updated_at.to_rfc3339(),
+            "description_updated_at" => {
+                self.description_updated_at.map(|t| t.to_rfc3339()).unwrap_or_default()
+            }
+            "status" => self.status.name().to_string(),
+            "completed" => self.is_completed().to_string(),
+            "completed_at" => self.completed_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            "deleted_at" => self.deleted_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            "idempotency_key" => self.idempotency_key.clone().unwrap_or_default(),
+            "depends_on" => self.depends_on.map(|id| id.to_string()).unwrap_or_default(),
+            _ => {
+                return Err(TaskError::InvalidInput(format!(
+                    "Unknown field '{}'; expected one of: {}",
+                    field,
+                    Task::GETTABLE_FIELDS.join(", ")
+                )))
+            }
+        })
+    }
+
+    /// Reports problems with the task's timestamps.
+    ///
+    /// A timestamp is considered invalid if it lies in the future relative to `now`, or if
+    /// `updated_at` lies before `created_at`. Timestamps are compared as instants, so tasks
+    /// created and updated on either side of a daylight saving time change are handled correctly.
+    ///
+    /// # Arguments
+    ///
+    /// - `now` - The current time.
+    ///
+    /// # Returns
+    ///
+    /// A vector of the problems found, empty if the timestamps are valid.
+    pub fn timestamp_issues(&self, now: DateTime<Local>) -> Vec<TimestampIssue> {
+        let mut issues = Vec::new();
+        if self.created_at > now {
+            issues.push(TimestampIssue::CreatedInFuture);
+        }
+        if self.updated_at > now {
+            issues.push(TimestampIssue::UpdatedInFuture);
+        }
+        if self.updated_at < self.created_at {
+            issues.push(TimestampIssue::UpdatedBeforeCreated);
+        }
+        issues
+    }
+
+    /// Clamps the task's timestamps into a valid state.
+    ///
+    /// The rules are applied in order:
+    ///
+    /// 1. A `created_at` in the future is clamped to `now`.
+    /// 2. An `updated_at` in the future is clamped to `now`.
+    /// 3. An `updated_at` before `created_at` is raised to `created_at`.
+    ///
+    /// # Arguments
+    ///
+    /// - `now` - The current time.
+    ///
+    /// # Returns
+    ///
+    /// The problems that were found (and fixed), empty if the timestamps were already valid.
+    pub fn clamp_timestamps(&mut self, now: DateTime<Local>) -> Vec<TimestampIssue> {
+        let issues = self.timestamp_issues(now);
+        self.created_at = self.created_at.min(now);
+        self.updated_at = self.updated_at.min(now).max(self.created_at);
+        issues
+    }
+}
+
+/// A problem with a task's timestamps.
+///
+/// # Variants
+///
+/// - `CreatedInFuture` - The creation timestamp lies in the future.
+/// - `UpdatedInFuture` - The update timestamp lies in the future.
+/// - `UpdatedBeforeCreated` - The update timestamp lies before the creation timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampIssue {
+    /// The creation timestamp lies in the future.
+    CreatedInFuture,
+
+    /// The update timestamp lies in the future.
+    UpdatedInFuture,
+
+    /// The update timestamp lies before the creation timestamp.
+    UpdatedBeforeCreated,
+}
+
+impl fmt::Display for TimestampIssue {
+    /// Formats the `TimestampIssue` for display purposes.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter used to write the formatted string.
+    ///
+    /// # Returns
+    ///
+    /// * `fmt::Result` - The result of the formatting operation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimestampIssue::CreatedInFuture => write!(f, "created_at is in the future"),
+            TimestampIssue::UpdatedInFuture => write!(f, "updated_at is in the future"),
+            TimestampIssue::UpdatedBeforeCreated => write!(f, "updated_at is before created_at"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses an RFC 3339 timestamp into local time.
+    fn at(timestamp: &str) -> DateTime<Local> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Local)
+    }
+
+    /// Builds a task with the given timestamps.
+    fn task_at(created_at: &str, updated_at: &str) -> Task {
+        let mut task = Task::new(1, String::from("Test task"));
+        task.created_at = at(created_at);
+        task.updated_at = at(updated_at);
+        task
+    }
+
+    /// Tests that identity only depends on the ID, while equality compares every field.
+    #[test]
+    fn test_same_id() {
+        let task = task_at("2024-06-01T12:00:00+00:00", "2024-06-02T12:00:00+00:00");
+        let mut edited = task.clone();
+        edited.updated_at = at("2024-06-03T12:00:00+00:00");
+        edited.status = Status::Done;
+
+        assert!(task.same_id(&edited));
+        assert_ne!(task, edited);
+        assert!(!task.same_id(&Task { id: 2, ..task.clone() }));
+        assert_ne!(task, Task { id: 2, ..task.clone() });
+    }
+
+    /// Tests that completing a task records when, and reopening it clears that.
+    #[test]
+    fn test_set_completed() {
+        let mut task = Task::new(1, String::from("Test task"));
+        let first = at("2024-07-01T12:00:00+00:00");
+        task.set_completed(true, first);
+        assert_eq!(task.completed_at, Some(first));
+
+        task.set_completed(true, at("2024-07-02T12:00:00+00:00"));
+        assert_eq!(task.completed_at, Some(first));
+
+        task.set_completed(false, first);
+        assert!(!task.is_completed());
+        assert_eq!(task.completed_at, None);
+
+        task.status = Status::Blocked;
+        task.set_completed(false, first);
+        assert_eq!(task.status, Status::Blocked);
+    }
+
+    /// Tests that only the `Done` status records a completion time.
+    #[test]
+    fn test_set_status() {
+        let mut task = Task::new(1, String::from("Test task"));
+        let now = at("2024-07-01T12:00:00+00:00");
+        task.set_status(Status::InProgress, now);
+        assert_eq!((task.status, task.completed_at), (Status::InProgress, None));
+
+        task.set_status(Status::Done, now);
+        assert!(task.is_completed());
+        assert_eq!(task.completed_at, Some(now));
+
+        task.set_status(Status::Blocked, now);
+        assert_eq!((task.status, task.completed_at), (Status::Blocked, None));
+    }
+
+    /// Tests that only a different description records when it changed, and that each kind of
+    /// change is read from its own timestamp.
+    #[test]
+    fn test_changed_at() {
+        let mut task = Task::new(1, String::from("Test task"));
+        let created = task.created_at;
+        let now = at("2030-07-01T12:00:00+00:00");
+        assert_eq!(task.changed_at(ChangedField::Description), Some(created));
+        assert_eq!(task.changed_at(ChangedField::Completed), None);
+
+        task.set_description(String::from("Test task"), now);
+        assert_eq!(task.description_updated_at, None);
+        task.set_description(String::from("Renamed task"), now);
+        assert_eq!(task.description_updated_at, Some(now));
+        assert_eq!(task.changed_at(ChangedField::Description), Some(now));
+        assert_eq!(task.changed_at(ChangedField::Any), Some(task.updated_at));
+
+        task.set_status(Status::Done, created);
+        assert_eq!(task.changed_at(ChangedField::Completed), Some(created));
+        assert_eq!(task.changed_at(ChangedField::Description), Some(now));
+
+        let old: Task = serde_json::from_str(r#"{"id": 1, "description": "Old task"}"#).unwrap();
+        assert_eq!(old.description_updated_at, None);
+        let json = serde_json::to_value(&task).unwrap();
+        assert!(json["description_updated_at"].is_string());
+        assert_eq!(serde_json::from_value::<Task>(json).unwrap(), task);
+    }
+
+    /// Tests parsing the kinds of change accepted by `list --field`.
+    #[test]
+    fn test_changed_field_from_str() {
+        assert_eq!("Description".parse::<ChangedField>().unwrap(), ChangedField::Description);
+        assert_eq!(
+            "notes".parse::<ChangedField>().unwrap_err().to_string(),
+            "Invalid input - Unknown field 'notes'; expected one of: any, description, completed"
+        );
+        for &field in ChangedField::ALL {
+            assert_eq!(field.name().parse::<ChangedField>().unwrap(), field);
+        }
+    }
+
+    /// Tests parsing status names, with the separators and case accepted on the command line.
+    #[test]
+    fn test_status_from_str() {
+        assert_eq!("todo".parse::<Status>().unwrap(), Status::Todo);
+        assert_eq!("In-Progress".parse::<Status>().unwrap(), Status::InProgress);
+        assert_eq!("in progress".parse::<Status>().unwrap(), Status::InProgress);
+        assert_eq!(" done ".parse::<Status>().unwrap(), Status::Done);
+        assert_eq!(
+            "started".parse::<Status>().unwrap_err().to_string(),
+            "Invalid input - Unknown status 'started'; expected one of: todo, in_progress, blocked, done"
+        );
+        for &status in Status::ALL {
+            assert_eq!(status.name().parse::<Status>().unwrap(), status);
+        }
+    }
+
+    /// Tests that `status` is read from task files, including ones written before it existed or
+    /// changed by versions of `tasg` that only know `completed`.
+    #[test]
+    fn test_deserialize_status() {
+        let status = |json: &str| serde_json::from_str::<Task>(json).unwrap().status;
+        assert_eq!(status(r#"{"id": 1}"#), Status::Todo);
+        assert_eq!(status(r#"{"id": 1, "completed": true}"#), Status::Done);
+        assert_eq!(status(r#"{"id": 1, "completed": false}"#), Status::Todo);
+        assert_eq!(status(r#"{"id": 1, "status": "blocked"}"#), Status::Blocked);
+        assert_eq!(
+            status(r#"{"id": 1, "completed": false, "status": "in_progress"}"#),
+            Status::InProgress
+        );
+        assert_eq!(
+            status(r#"{"id": 1, "completed": true, "status": "in_progress"}"#),
+            Status::Done
+        );
+        assert_eq!(status(r#"{"id": 1, "completed": false, "status": "done"}"#), Status::Todo);
+        assert!(serde_json::from_str::<Task>(r#"{"id": 1, "status": "started"}"#).is_err());
+    }
+
+    /// Tests that tasks are written with both `status` and `completed`, and read back unchanged.
+    #[test]
+    fn test_serialize_status() {
+        let mut task = Task::new(1, String::from("Test task"));
+        task.status = Status::InProgress;
+        let value = serde_json::to_value(&task).unwrap();
+        assert_eq!(value["status"], "in_progress");
+        assert_eq!(value["completed"], false);
+        assert!(value.get("extra").is_none());
+        assert_eq!(serde_json::from_value::<Task>(value).unwrap(), task);
+
+        task.set_status(Status::Done, task.created_at);
+        let value = serde_json::to_value(&task).unwrap();
+        assert_eq!((&value["status"], &value["completed"]), (&"done".into(), &true.into()));
+    }
+
+    /// Tests the inclusive date range check, including tasks without `completed_at`.
+    #[test]
+    fn test_completed_between() {
+        let date = |s: &str| s.parse::<NaiveDate>().unwrap();
+        let mut task = Task::new(1, String::from("Test task"));
+        task.set_completed(true, at("2024-07-15T12:00:00+00:00"));
+        let day = task.completed_at.unwrap().date_naive();
+
+        assert!(task.completed_between(day, day));
+        assert!(task.completed_between(date("2024-07-01"), date("2024-07-31")));
+        assert!(!task.completed_between(date("2024-08-01"), date("2024-08-31")));
+
+        task.completed_at = None;
+        assert!(!task.completed_between(date("2024-07-01"), date("2024-07-31")));
+    }
+
+    /// Tests that valid timestamps are left untouched.
+    #[test]
+    fn test_clamp_valid_timestamps() {
+        let now = at("2024-07-01T12:00:00+00:00");
+        let mut task = task_at("2024-06-01T12:00:00+00:00", "2024-06-02T12:00:00+00:00");
+        let original = task.clone();
+
+        assert!(task.clamp_timestamps(now).is_empty());
+        assert_eq!(task, original);
+    }
+
+    /// Tests that an `updated_at` before `created_at` is raised to `created_at`.
+    #[test]
+    fn test_clamp_updated_before_created() {
+        let now = at("2024-07-01T12:00:00+00:00");
+        let mut task = task_at("2024-06-02T12:00:00+00:00", "2024-06-01T12:00:00+00:00");
+
+        assert_eq!(task.clamp_timestamps(now), vec![TimestampIssue::UpdatedBeforeCreated]);
+        assert_eq!(task.updated_at, task.created_at);
+    }
+
+    /// Tests that future timestamps are clamped to `now`.
+    #[test]
+    fn test_clamp_future_timestamps() {
+        let now = at("2024-07-01T12:00:00+00:00");
+        let mut task = task_at("2024-08-01T12:00:00+00:00", "2024-08-02T12:00:00+00:00");
+
+        assert_eq!(
+            task.clamp_timestamps(now),
+            vec![TimestampIssue::CreatedInFuture, TimestampIssue::UpdatedInFuture]
+        );
+        assert_eq!(task.created_at, now);
+        assert_eq!(task.updated_at, now);
+    }
+
+    /// Tests that only a future `updated_at` is clamped when `created_at` is valid.
+    #[test]
+    fn test_clamp_future_updated_only() {
+        let now = at("2024-07-01T12:00:00+00:00");
+        let mut task = task_at("2024-06-01T12:00:00+00:00", "2024-08-01T12:00:00+00:00");
+
+        assert_eq!(task.clamp_timestamps(now), vec![TimestampIssue::UpdatedInFuture]);
+        assert_eq!(task.created_at, at("2024-06-01T12:00:00+00:00"));
+        assert_eq!(task.updated_at, now);
+    }
+
+    /// Tests timestamps either side of a daylight saving time change.
+    ///
+    /// When clocks go back, an update can carry an earlier wall-clock time than its creation
+    /// while still happening later. Timestamps must be compared as instants, not wall-clock times.
+    #[test]
+    fn test_clamp_across_dst_boundary() {
+        let now = at("2024-11-01T00:00:00+00:00");
+
+        // 02:30 CEST (00:30 UTC) followed by 02:10 CET (01:10 UTC): valid despite the wall clock.
+        let mut task = task_at("2024-10-27T02:30:00+02:00", "2024-10-27T02:10:00+01:00");
+        let original = task.clone();
+        assert!(task.clamp_timestamps(now).is_empty());
+        assert_eq!(task, original);
+
+        // 02:10 CET (01:10 UTC) followed by 02:30 CEST (00:30 UTC): invalid despite the wall clock.
+        let mut task = task_at("2024-10-27T02:10:00+01:00", "2024-10-27T02:30:00+02:00");
+        assert_eq!(task.clamp_timestamps(now), vec![TimestampIssue::UpdatedBeforeCreated]);
+        assert_eq!(task.updated_at, at("2024-10-27T01:10:00+00:00"));
+    }
+
+    /// Tests setting each settable field from a string.
+    #[test]
+    fn test_set_field() {
+        let mut task = Task::new(2, String::from("Test task"));
+        task.set_field("description", "Renamed task").unwrap();
+        task.set_field("completed", "yes").unwrap();
+        task.set_field("depends_on", "1").unwrap();
+        task.set_field("notes", "First line\nSecond line \n").unwrap();
+        assert_eq!(task.description, "Renamed task");
+        assert!(task.is_completed());
+        assert_eq!(task.depends_on, Some(1));
+        assert_eq!(task.notes.as_deref(), Some("First line\nSecond line"));
+
+        task.set_field("completed", "False").unwrap();
+        task.set_field("depends_on", "none").unwrap();
+        task.set_field("notes", "").unwrap();
+        assert!(!task.is_completed());
+        assert_eq!(task.depends_on, None);
+        assert_eq!(task.notes, None);
+
+        task.set_field("status", "in-progress").unwrap();
+        assert_eq!(task.status, Status::InProgress);
+    }
+
+    /// Tests that unknown fields and unparsable values are rejected without changing the task.
+    #[test]
+    fn test_set_field_invalid() {
+        let mut task = Task::new(2, String::from("Test task"));
+        let original = task.clone();
+        for (field, value) in [
+            ("priority", "high"),
+            ("id", "3"),
+            ("completed", "maybe"),
+            ("status", "started"),
+            ("depends_on", "two"),
+            ("depends_on", "2"),
+            ("description", " "),
+        ] {
+            let result = task.set_field(field, value);
+            assert!(matches!(result, Err(TaskError::InvalidInput(_))), "{} = {}", field, value);
+        }
+        assert_eq!(task, original);
+    }
+
+    /// Tests getting fields in their raw string form.
+    #[test]
+    fn test_get_field() {
+        let mut task = task_at("2024-06-01T12:00:00+00:00", "2024-06-02T12:00:00+00:00");
+        task.depends_on = Some(4);
+        assert_eq!(task.get_field("id").unwrap(), "1");
+        assert_eq!(task.get_field("description").unwrap(), "Test task");
+        assert_eq!(task.get_field("completed").unwrap(), "false");
+        assert_eq!(task.get_field("status").unwrap(), "todo");
+        assert_eq!(task.get_field("depends_on").unwrap(), "4");
+        assert_eq!(task.get_field("idempotency_key").unwrap(), "");
+        assert_eq!(task.get_field("notes").unwrap(), "");
+        let created_at = task.get_field("created_at").unwrap();
+        assert_eq!(DateTime::parse_from_rfc3339(&created_at).unwrap(), task.created_at);
+        assert!(matches!(task.get_field("priority"), Err(TaskError::InvalidInput(_))));
     }
 }
@@ -0,0 +1,238 @@
+//! Reading and writing task timestamps.
+//!
+//! Timestamps are written to the task file as RFC 3339 strings by default. A store can instead
+//! write them as Unix epoch seconds, which makes files smaller and easier to read from other
+//! languages. Either form is accepted when reading, so a file may mix the two, e.g. after
+//! switching formats. The `with` modules here are used by the timestamp fields of `Task`.
+
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_json::Value;
+
+use crate::error::TaskError;
+
+/// The fields of a task that hold timestamps.
+pub const FIELDS: &[&str] =
+    &["created_at", "updated_at", "description_updated_at", "completed_at", "deleted_at"];
+
+/// How timestamps are written to the task file.
+///
+/// # Variants
+///
+/// - `Rfc3339` - As RFC 3339 strings in local time, e.g. `"2024-07-01T12:00:00+01:00"`.
+/// - `Epoch` - As whole seconds since the Unix epoch, e.g. `1719831600`; sub-second precision is lost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// As RFC 3339 strings in local time.
+    #[default]
+    Rfc3339,
+
+    /// As whole seconds since the Unix epoch.
+    Epoch,
+}
+
+impl TimestampFormat {
+    /// Reads the format from the `TASG_TIMESTAMP_FORMAT` environment variable, which may be
+    /// `rfc3339` or `epoch`, falling back to RFC 3339 if it is not set.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, TaskError>` - The format, or `TaskError::InvalidInput` if the variable has any other value.
+    pub fn from_env() -> Result<Self, TaskError> {
+        match std::env::var("TASG_TIMESTAMP_FORMAT") {
+            Err(_) => Ok(Self::default()),
+            Ok(value) => match value.trim().to_lowercase().as_str() {
+                "rfc3339" => Ok(TimestampFormat::Rfc3339),
+                "epoch" => Ok(TimestampFormat::Epoch),
+                _ => Err(TaskError::InvalidInput(format!(
+                    "TASG_TIMESTAMP_FORMAT must be rfc3339 or epoch, not '{}'",
+                    value
+                ))),
+            },
+        }
+    }
+
+    /// Rewrites the timestamps of serialized tasks in this format.
+    ///
+    /// Tasks serialize their timestamps as RFC 3339, so only `Epoch` changes anything.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - The serialized tasks, as a JSON array of objects.
+    pub fn apply(self, tasks: &mut Value) {
+        if self != TimestampFormat::Epoch {
+            return;
+        }
+        let objects = tasks.as_array_mut().into_iter().flatten().filter_map(Value::as_object_mut);
+        for object in objects {
+            for field in FIELDS {
+                if let Some(value) = object.get_mut(*field) {
+                    if let Some(at) = value.as_str().and_then(parse_rfc3339) {
+                        *value = at.timestamp().into();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parses an RFC 3339 timestamp into local time, as `chrono` does when deserializing.
+fn parse_rfc3339(text: &str) -> Option<DateTime<Local>> {
+    text.parse().ok()
+}
+
+/// A timestamp as written in the task file, in either format.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Raw {
+    /// Seconds since the Unix epoch.
+    Epoch(i64),
+
+    /// An RFC 3339 string.
+    Text(String),
+}
+
+impl Raw {
+    /// Converts the timestamp to local time.
+    fn into_local<E: serde::de::Error>(self) -> Result<DateTime<Local>, E> {
+        match self {
+            Raw::Epoch(seconds) => Local
+                .timestamp_opt(seconds, 0)
+                .single()
+                .ok_or_else(|| E::custom(format!("timestamp {} is out of range", seconds))),
+            Raw::Text(text) => parse_rfc3339(&text)
+                .ok_or_else(|| E::custom(format!("invalid RFC 3339 timestamp '{}'", text))),
+        }
+    }
+}
+
+/// Writes a timestamp as an RFC 3339 string.
+///
+/// # Arguments
+///
+/// * `at` - The timestamp.
+/// * `serializer` - The serializer to write to.
+///
+/// # Returns
+///
+/// * `Result<S::Ok, S::Error>` - The serializer's result.
+pub fn serialize<S: Serializer>(at: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error> {
+    serde::Serialize::serialize(at, serializer)
+}
+
+/// Reads a timestamp written as an RFC 3339 string or as Unix epoch seconds.
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer to read from.
+///
+/// # Returns
+///
+/// * `Result<DateTime<Local>, D::Error>` - The timestamp in local time, or an error if it is neither form or is out of range.
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Local>, D::Error> {
+    Raw::deserialize(deserializer)?.into_local()
+}
+
+/// The same as the parent module, for optional timestamps.
+pub mod option {
+    use super::*;
+
+    /// Writes an optional timestamp as an RFC 3339 string, or `null`.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` - The timestamp, if any.
+    /// * `serializer` - The serializer to write to.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<S::Ok, S::Error>` - The serializer's result.
+    pub fn serialize<S: Serializer>(
+        at: &Option<DateTime<Local>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(at, serializer)
+    }
+
+    /// Reads an optional timestamp written as an RFC 3339 string, Unix epoch seconds, or `null`.
+    ///
+    /// # Arguments
+    ///
+    /// * `deserializer` - The deserializer to read from.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<DateTime<Local>>, D::Error>` - The timestamp in local time, if any, or an error if it is neither form or is out of range.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Local>>, D::Error> {
+        Option::<Raw>::deserialize(deserializer)?.map(Raw::into_local).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Task;
+
+    /// Builds a task with whole-second timestamps, which survive the epoch format unchanged.
+    fn task() -> Task {
+        let mut task = Task::new(1, String::from("Test task"));
+        task.created_at = parse_rfc3339("2024-07-01T12:00:00+00:00").unwrap();
+        task.updated_at = parse_rfc3339("2024-07-02T12:00:00+00:00").unwrap();
+        task.completed_at = Some(task.updated_at);
+        task
+    }
+
+    /// Tests that tasks written with epoch timestamps read back unchanged.
+    #[test]
+    fn test_epoch_round_trip() {
+        let tasks = vec![task()];
+        let mut value = serde_json::to_value(&tasks).unwrap();
+        TimestampFormat::Epoch.apply(&mut value);
+
+        assert_eq!(value[0]["created_at"], 1719835200);
+        assert_eq!(value[0]["completed_at"], 1719921600);
+        assert!(value[0].get("deleted_at").is_none());
+        let parsed: Vec<Task> = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, tasks);
+    }
+
+    /// Tests that the RFC 3339 format leaves serialized tasks as they are.
+    #[test]
+    fn test_rfc3339_unchanged() {
+        let mut value = serde_json::to_value(vec![task()]).unwrap();
+        let original = value.clone();
+        TimestampFormat::Rfc3339.apply(&mut value);
+        assert_eq!(value, original);
+    }
+
+    /// Tests reading a file that mixes both formats.
+    #[test]
+    fn test_mixed_formats() {
+        let data = r#"[
+            {"id": 1, "created_at": 1719835200, "updated_at": "2024-07-02T12:00:00+00:00", "completed_at": 1719921600},
+            {"id": 2, "created_at": "2024-07-01T12:00:00+00:00", "updated_at": 1719921600, "deleted_at": null}
+        ]"#;
+        let tasks: Vec<Task> = serde_json::from_str(data).unwrap();
+
+        assert_eq!(tasks[0].created_at, tasks[1].created_at);
+        assert_eq!(tasks[0].updated_at, tasks[1].updated_at);
+        assert_eq!(tasks[0].completed_at, Some(tasks[1].updated_at));
+        assert_eq!(tasks[1].deleted_at, None);
+    }
+
+    /// Tests that invalid timestamps are rejected.
+    #[test]
+    fn test_invalid_timestamps() {
+        for data in [
+            r#"[{"id": 1, "created_at": "yesterday"}]"#,
+            r#"[{"id": 1, "created_at": true}]"#,
+            r#"[{"id": 1, "created_at": 9223372036854775807}]"#,
+        ] {
+            assert!(serde_json::from_str::<Vec<Task>>(data).is_err(), "{}", data);
+        }
+    }
+}
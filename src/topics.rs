@@ -0,0 +1,160 @@
+//! Help topics.
+//!
+//! Several options take small languages of their own, such as durations or task file templates.
+//! `tasg help <topic>` explains one of them, and `tasg help <command>` shows a command's help as
+//! `--help` would. The text of each topic is built from the same tables the parsers use, so it
+//! always lists exactly what is accepted.
+
+use std::fmt::Write;
+
+use clap::CommandFactory;
+
+use crate::cli::Cli;
+use crate::duration;
+use crate::error::TaskError;
+use crate::instruction;
+use crate::path_template;
+use crate::render::Column;
+use crate::task::Task;
+
+/// The help topics, with a one-line summary of each.
+pub const TOPICS: &[(&str, &str)] = &[
+    ("durations", "Lengths of time, e.g. for `list --highlight-changed`"),
+    ("fields", "Field names for `list --fields`, `get`, and `set`"),
+    ("templates", "Date tokens in the tasks file path, e.g. `%Y-%m`"),
+    ("instructions", "Verbs understood by `tasg do`"),
+];
+
+/// The text of a help topic.
+///
+/// # Arguments
+///
+/// * `name` - The name of the topic, one of `TOPICS`.
+///
+/// # Returns
+///
+/// * `Option<String>` - The text, with a trailing newline, or `None` if there is no such topic.
+pub fn topic(name: &str) -> Option<String> {
+    let mut text = String::new();
+    match name {
+        "durations" => {
+            text.push_str("A duration is a number followed by a unit, e.g. `30m` or `2w`. Several may be\ncombined, e.g. `1d12h`.\n\nUnits:\n");
+            for (letter, name, _) in duration::UNITS {
+                writeln!(text, "  {}  {}", letter, name).unwrap();
+            }
+        }
+        "fields" => {
+            text.push_str("Fields for `list --fields`, in their default JSON order:\n");
+            for column in Column::FIELDS {
+                writeln!(text, "  {:<12} {}", column.name(), column.header()).unwrap();
+            }
+            writeln!(text, "\nFields for `get`:\n  {}", Task::GETTABLE_FIELDS.join(", ")).unwrap();
+            writeln!(text, "\nFields for `set`:\n  {}", Task::SETTABLE_FIELDS.join(", ")).unwrap();
+        }
+        "templates" => {
+            text.push_str("The tasks file path may contain `strftime` tokens, e.g. `~/tasks/%Y-%m.json`, to\nstart a new file every period. The smallest period in the path decides which file\n`carryover` copies open tasks from. Tokens by period:\n");
+            for (period, tokens) in path_template::TOKENS {
+                let tokens: Vec<String> = tokens.chars().map(|c| format!("%{}", c)).collect();
+                writeln!(text, "  {:<6} {}", period.name(), tokens.join(" ")).unwrap();
+            }
+        }
+        "instructions" => {
+            text.push_str("`tasg do` takes a verb and its arguments as one string, e.g.\n`tasg do \"complete 12\"`. Verbs:\n");
+            writeln!(text, "  {}", instruction::VERBS.join(", ")).unwrap();
+        }
+        _ => return None,
+    }
+    Some(text)
+}
+
+/// The help for a topic or command, or the general help with a list of topics.
+///
+/// # Arguments
+///
+/// * `name` - The name of a topic or command, or `None` for the general help.
+///
+/// # Returns
+///
+/// * `Result<String, TaskError>` - The help text, or `TaskError::InvalidInput` if `name` is neither a topic nor a command.
+pub fn help(name: Option<&str>) -> Result<String, TaskError> {
+    let mut command = Cli::command();
+    command.build();
+    let Some(name) = name else {
+        let mut text = command.render_long_help().to_string();
+        text.push_str("\nHelp topics (tasg help <topic>):\n");
+        for (name, summary) in TOPICS {
+            writeln!(text, "  {:<12} {}", name, summary).unwrap();
+        }
+        return Ok(text);
+    };
+    if let Some(text) = topic(name) {
+        return Ok(text);
+    }
+    match command.find_subcommand_mut(name) {
+        Some(subcommand) => Ok(subcommand.render_long_help().to_string()),
+        None => {
+            let names: Vec<&str> = TOPICS.iter().map(|(name, _)| *name).collect();
+            Err(TaskError::InvalidInput(format!(
+                "Unknown help topic or command '{}'; topics are: {}",
+                name,
+                names.join(", ")
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that every topic has text.
+    #[test]
+    fn test_topics() {
+        for (name, _) in TOPICS {
+            assert!(topic(name).is_some(), "{}", name);
+        }
+        assert!(topic("filters").is_none());
+    }
+
+    /// Tests that each topic mentions every entry of the table its parser uses.
+    #[test]
+    fn test_topics_cover_registries() {
+        let durations = topic("durations").unwrap();
+        for (letter, name, _) in duration::UNITS {
+            assert!(durations.contains(&format!("{}  {}", letter, name)), "{}", letter);
+        }
+        let fields = topic("fields").unwrap();
+        for name in Column::FIELDS
+            .iter()
+            .map(|c| c.name())
+            .chain(Task::GETTABLE_FIELDS.iter().copied())
+            .chain(Task::SETTABLE_FIELDS.iter().copied())
+        {
+            assert!(fields.contains(name), "{}", name);
+        }
+        let templates = topic("templates").unwrap();
+        for (period, tokens) in path_template::TOKENS {
+            assert!(templates.contains(period.name()));
+            for token in tokens.chars() {
+                assert!(templates.contains(&format!("%{}", token)), "%{}", token);
+            }
+        }
+        let instructions = topic("instructions").unwrap();
+        for verb in instruction::VERBS {
+            assert!(instructions.contains(verb), "{}", verb);
+        }
+    }
+
+    /// Tests the general help, a command's help, and an unknown name.
+    #[test]
+    fn test_help() {
+        let text = help(None).unwrap();
+        assert!(text.contains("Usage: tasg"));
+        assert!(TOPICS.iter().all(|(name, _)| text.contains(name)));
+        assert!(help(Some("list")).unwrap().contains("Usage: tasg list"));
+        assert_eq!(
+            help(Some("filters")).unwrap_err().to_string(),
+            "Invalid input - Unknown help topic or command 'filters'; topics are: durations, fields, templates, instructions"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! The guided tutorial.
+//!
+//! This module contains the data-driven lesson behind `tasg tutorial`. Each step names the command
+//! the user is expected to run, a validation predicate that inspects the (throwaway) task list
+//! before and after the command, and a hint shown when the step has not been completed yet.
+//! The interactive loop itself lives in the binary; everything here is pure and can be tested
+//! without a terminal.
+
+use crate::task::Task;
+
+/// A single step of the tutorial.
+#[derive(Debug, Clone, Copy)]
+pub struct Step {
+    /// A short title for the step.
+    pub title: &'static str,
+
+    /// An explanation of what the step teaches.
+    pub explanation: &'static str,
+
+    /// An example of the command to run.
+    pub example: &'static str,
+
+    /// The subcommand the user is expected to run, e.g. `add`.
+    pub command: &'static str,
+
+    /// The hint shown when the step has not been completed.
+    pub hint: &'static str,
+
+    /// Checks whether the step was completed, given the tasks before and after the command ran.
+    pub check: fn(before: &[Task], after: &[Task]) -> bool,
+}
+
+/// The outcome of evaluating a command against a tutorial step.
+///
+/// # Variants
+///
+/// - `Passed` - The expected command was run and the step's check holds.
+/// - `WrongCommand` - A different command was run.
+/// - `NotYet` - The expected command was run, but the step's check does not hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The expected command was run and the step's check holds.
+    Passed,
+
+    /// A different command was run.
+    WrongCommand,
+
+    /// The expected command was run, but the step's check does not hold.
+    NotYet,
+}
+
+impl Step {
+    /// Evaluates a command the user ran against this step.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The command-line arguments the user entered, without the leading `tasg`.
+    /// * `before` - The tasks before the command ran.
+    /// * `after` - The tasks after the command ran.
+    ///
+    /// # Returns
+    ///
+    /// * `Outcome` - Whether the step was completed.
+    pub fn evaluate(&self, args: &[String], before: &[Task], after: &[Task]) -> Outcome {
+        if args.first().map(String::as_str) != Some(self.command) {
+            Outcome::WrongCommand
+        } else if (self.check)(before, after) {
+            Outcome::Passed
+        } else {
+            Outcome::NotYet
+        }
+    }
+}
+
+/// The commands that can be run in the tutorial.
+///
+/// Anything else is refused, so that commands which write outside the throwaway task list, such
+/// as `backup` or `completions install`, cannot touch the user's real files.
+pub const COMMANDS: &[&str] = &["add", "list", "edit", "complete", "delete"];
+
+/// The steps of the tutorial, in order.
+pub const STEPS: &[Step] = &[
+    Step {
+        title: "Add a task",
+        explanation: "Tasks are created with `add` followed by a description in quotes.",
+        example: "tasg add \"Buy milk\"",
+        command: "add",
+        hint: "Run `add` with a non-empty description, e.g. tasg add \"Buy milk\"",
+        check: |before, after| after.len() > before.len(),
+    },
+    Step {
+        title: "List your tasks",
+        explanation: "`list` shows your incomplete tasks along with their IDs.",
+        example: "tasg list",
+        command: "list",
+        hint: "Run tasg list",
+        check: |_, _| true,
+    },
+    Step {
+        title: "Edit a task",
+        explanation: "`edit` changes the description of a task, identified by its ID.",
+        example: "tasg edit 1 --description \"Buy oat milk\"",
+        command: "edit",
+        hint: "Pass an existing ID and a new description, e.g. tasg edit 1 --description \"Buy oat milk\"",
+        check: |before, after| {
+            after.iter().any(|a| {
+                before.iter().any(|b| b.same_id(a) && b.description != a.description)
+            })
+        },
+    },
+    Step {
+        title: "Complete a task",
+        explanation: "`complete` marks a task as done. Completed tasks are hidden from `list`.",
+        example: "tasg complete 1",
+        command: "complete",
+        hint: "Pass the ID of an incomplete task, e.g. tasg complete 1",
+        check: |before, after| {
+            after.iter().filter(|t| t.is_completed()).count()
+                > before.iter().filter(|t| t.is_completed()).count()
+        },
+    },
+    Step {
+        title: "Delete a task",
+        explanation: "`delete` removes a task you no longer need.",
+        example: "tasg delete 1",
+        command: "delete",
+        hint: "Pass the ID of an existing task, e.g. tasg delete 1",
+        check: |before, after| after.len() < before.len(),
+    },
+];
+
+/// Renders the whole lesson as text, for `tasg tutorial --non-interactive`.
+///
+/// # Returns
+///
+/// * `String` - Every step's title, explanation, and example command.
+pub fn lesson() -> String {
+    let mut text = String::from("Welcome to the tasg tutorial!\n");
+    for (index, step) in STEPS.iter().enumerate() {
+        text.push_str(&format!(
+            "\nStep {}/{}: {}\n  {}\n  $ {}\n",
+            index + 1,
+            STEPS.len(),
+            step.title,
+            step.explanation,
+            step.example
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Status;
+
+    /// Builds argument vectors from string slices.
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    /// Tests that every step's command can be run in the tutorial.
+    #[test]
+    fn test_steps_use_allowed_commands() {
+        for step in STEPS {
+            assert!(COMMANDS.contains(&step.command), "{}", step.command);
+        }
+    }
+
+    /// Tests that every step can be completed by running its expected command.
+    #[test]
+    fn test_steps_pass_in_sequence() {
+        let mut before = Vec::new();
+        let mut after = vec![Task::new(1, String::from("Buy milk"))];
+        assert_eq!(STEPS[0].evaluate(&args(&["add"]), &before, &after), Outcome::Passed);
+
+        before = after.clone();
+        assert_eq!(STEPS[1].evaluate(&args(&["list"]), &before, &after), Outcome::Passed);
+
+        after[0].description = String::from("Buy oat milk");
+        assert_eq!(STEPS[2].evaluate(&args(&["edit", "1"]), &before, &after), Outcome::Passed);
+
+        before = after.clone();
+        after[0].status = Status::Done;
+        assert_eq!(STEPS[3].evaluate(&args(&["complete", "1"]), &before, &after), Outcome::Passed);
+
+        before = after.clone();
+        after.clear();
+        assert_eq!(STEPS[4].evaluate(&args(&["delete", "1"]), &before, &after), Outcome::Passed);
+    }
+
+    /// Tests that running a different command does not advance a step.
+    #[test]
+    fn test_step_wrong_command() {
+        let tasks = vec![Task::new(1, String::from("Buy milk"))];
+        assert_eq!(STEPS[0].evaluate(&args(&["list"]), &tasks, &tasks), Outcome::WrongCommand);
+        assert_eq!(STEPS[0].evaluate(&[], &tasks, &tasks), Outcome::WrongCommand);
+    }
+
+    /// Tests that running the expected command without effect does not advance a step.
+    #[test]
+    fn test_step_not_yet() {
+        let tasks = vec![Task::new(1, String::from("Buy milk"))];
+        assert_eq!(STEPS[2].evaluate(&args(&["edit", "1"]), &tasks, &tasks), Outcome::NotYet);
+        assert_eq!(STEPS[3].evaluate(&args(&["complete", "2"]), &tasks, &tasks), Outcome::NotYet);
+    }
+
+    /// Tests that the lesson text covers every step.
+    #[test]
+    fn test_lesson_covers_all_steps() {
+        let text = lesson();
+        for step in STEPS {
+            assert!(text.contains(step.title));
+            assert!(text.contains(step.example));
+        }
+    }
+}
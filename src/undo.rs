@@ -0,0 +1,297 @@
+//! Undoing changes to the tasks.
+//!
+//! Whenever a command changes the tasks, the tasks as they were before it are pushed onto a
+//! bounded history kept in a sidecar file next to the tasks file, e.g. `tasks.json.undo`.
+//! `tasg undo` pops entries off the history and writes them back, keeping what it replaced so
+//! that `tasg redo` can re-apply it until the next change. Every command counts as one change,
+//! however many tasks it touches.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::TaskError;
+use crate::store::{write_atomically, Store};
+use crate::task::Task;
+
+/// The number of changes that can be undone, unless `TASG_UNDO_LIMIT` says otherwise.
+pub const DEFAULT_LIMIT: usize = 10;
+
+/// The changes that can be undone and redone.
+///
+/// # Fields
+///
+/// - `undo` - The tasks as they were before each recorded change, oldest first.
+/// - `redo` - The tasks as they were before each undone change was undone, most recently undone last.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct History {
+    /// The tasks as they were before each recorded change, oldest first.
+    undo: Vec<Vec<Task>>,
+
+    /// The tasks as they were before each undone change was undone, most recently undone last.
+    redo: Vec<Vec<Task>>,
+}
+
+impl History {
+    /// The path of the history file for a tasks file.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks_file` - The path to the tasks file.
+    ///
+    /// # Returns
+    ///
+    /// * `PathBuf` - The tasks file's path with `.undo` appended.
+    pub fn path(tasks_file: &str) -> PathBuf {
+        PathBuf::from(format!("{}.undo", tasks_file))
+    }
+
+    /// Reads the number of changes to keep from the `TASG_UNDO_LIMIT` environment variable,
+    /// falling back to `DEFAULT_LIMIT` if it is not set.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, TaskError>` - The limit, or `TaskError::InvalidInput` if `TASG_UNDO_LIMIT` is not a positive number.
+    pub fn limit_from_env() -> Result<usize, TaskError> {
+        match std::env::var("TASG_UNDO_LIMIT") {
+            Err(_) => Ok(DEFAULT_LIMIT),
+            Ok(limit) => limit.trim().parse().ok().filter(|&limit| limit > 0).ok_or_else(|| {
+                TaskError::InvalidInput(format!(
+                    "TASG_UNDO_LIMIT must be a positive number, not '{}'",
+                    limit
+                ))
+            }),
+        }
+    }
+
+    /// Loads the history file at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the history file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, TaskError>` - The history, an empty history if the file does not exist, or `TaskError::InvalidInput` if it is corrupt.
+    pub fn load(path: &Path) -> Result<Self, TaskError> {
+        if !path.exists() {
+            return Ok(History::default());
+        }
+        serde_json::from_str(&std::fs::read_to_string(path)?).map_err(|e| {
+            TaskError::InvalidInput(format!(
+                "Undo history {} is corrupt ({}); delete it to start a new history",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Writes the history to the given path, replacing the file atomically.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the history file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), TaskError>` - `Ok(())` if the history was written, or a `TaskError` if an error occurs.
+    pub fn save(&self, path: &Path) -> Result<(), TaskError> {
+        write_atomically(path, &serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Records a change, forgetting the oldest ones beyond the limit.
+    ///
+    /// Changes that were undone can no longer be redone.
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - The tasks as they were before the change.
+    /// * `limit` - The number of changes to keep.
+    pub fn record(&mut self, before: Vec<Task>, limit: usize) {
+        self.undo.push(before);
+        let excess = self.undo.len().saturating_sub(limit);
+        self.undo.drain(..excess);
+        self.redo.clear();
+    }
+
+    /// Undoes the last changes, returning the tasks as they were before them.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - The number of changes to undo.
+    /// * `current` - The tasks as they are now, kept so that the changes can be redone.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Task>, TaskError>` - The tasks to restore, or `TaskError::InvalidInput` if `steps` is 0 or fewer than `steps` changes were recorded, in which case the history is unchanged.
+    pub fn undo(&mut self, steps: usize, current: Vec<Task>) -> Result<Vec<Task>, TaskError> {
+        step(&mut self.undo, &mut self.redo, steps, current, "undo", "undone")
+    }
+
+    /// Redoes the last undone changes, returning the tasks as they were before they were undone.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - The number of changes to redo.
+    /// * `current` - The tasks as they are now, kept so that the changes can be undone again.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Task>, TaskError>` - The tasks to restore, or `TaskError::InvalidInput` if `steps` is 0 or fewer than `steps` changes were undone, in which case the history is unchanged.
+    pub fn redo(&mut self, steps: usize, current: Vec<Task>) -> Result<Vec<Task>, TaskError> {
+        step(&mut self.redo, &mut self.undo, steps, current, "redo", "redone")
+    }
+}
+
+/// Moves `steps` entries from the top of one stack to the other, returning the deepest one.
+///
+/// `current` and every entry above the returned one are pushed onto `to` in reverse order, so
+/// that stepping back the same number of times returns to `current`.
+fn step(
+    from: &mut Vec<Vec<Task>>,
+    to: &mut Vec<Vec<Task>>,
+    steps: usize,
+    current: Vec<Task>,
+    verb: &str,
+    participle: &str,
+) -> Result<Vec<Task>, TaskError> {
+    if steps == 0 {
+        return Err(TaskError::InvalidInput("The number of steps must be at least 1".into()));
+    }
+    match from.len() {
+        0 => Err(TaskError::InvalidInput(format!("Nothing to {}", verb))),
+        n if n < steps => Err(TaskError::InvalidInput(format!(
+            "Only {} change(s) can be {}, not {}",
+            n, participle, steps
+        ))),
+        n => {
+            let mut taken = from.split_off(n - steps);
+            let target = taken.remove(0);
+            to.push(current);
+            to.extend(taken.into_iter().rev());
+            Ok(target)
+        }
+    }
+}
+
+/// Every task in a store, including completed tasks and tasks in the trash, ordered by ID.
+///
+/// # Arguments
+///
+/// * `store` - The store to read.
+///
+/// # Returns
+///
+/// * `Result<Vec<Task>, TaskError>` - The tasks, or a `TaskError` if the store cannot be read.
+pub fn snapshot(store: &dyn Store) -> Result<Vec<Task>, TaskError> {
+    let mut tasks = store.list(true)?;
+    tasks.extend(store.list_trash()?);
+    tasks.sort_by_key(|t| t.id);
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a list of tasks with the given IDs, all created at the same time.
+    fn tasks(ids: &[u32]) -> Vec<Task> {
+        let at = "2024-07-01T12:00:00+00:00".parse().unwrap();
+        ids.iter()
+            .map(|&id| Task {
+                created_at: at,
+                updated_at: at,
+                ..Task::new(id, format!("Task {}", id))
+            })
+            .collect()
+    }
+
+    /// Tests undoing one and several changes.
+    #[test]
+    fn test_undo_steps() {
+        let mut history = History::default();
+        for n in 0..4 {
+            history.record(tasks(&(1..=n).collect::<Vec<_>>()), DEFAULT_LIMIT);
+        }
+
+        assert_eq!(history.undo(1, tasks(&[9])).unwrap(), tasks(&[1, 2, 3]));
+        assert_eq!(history.undo(2, tasks(&[9])).unwrap(), tasks(&[1]));
+        assert_eq!(history.undo(1, tasks(&[9])).unwrap(), tasks(&[]));
+        assert_eq!(
+            history.undo(1, tasks(&[9])).unwrap_err().to_string(),
+            "Invalid input - Nothing to undo"
+        );
+    }
+
+    /// Tests that undoing more changes than were recorded fails without forgetting any.
+    #[test]
+    fn test_undo_too_many_steps() {
+        let mut history = History::default();
+        history.record(tasks(&[]), DEFAULT_LIMIT);
+        history.record(tasks(&[1]), DEFAULT_LIMIT);
+
+        let err = history.undo(3, tasks(&[9])).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid input - Only 2 change(s) can be undone, not 3");
+        assert_eq!(history.undo(2, tasks(&[9])).unwrap(), tasks(&[]));
+    }
+
+    /// Tests that undone changes can be redone in order, and undone again.
+    #[test]
+    fn test_redo() {
+        let mut history = History::default();
+        for n in 0..3 {
+            history.record(tasks(&(1..=n).collect::<Vec<_>>()), DEFAULT_LIMIT);
+        }
+
+        assert_eq!(history.undo(2, tasks(&[1, 2, 3])).unwrap(), tasks(&[1]));
+        assert_eq!(history.redo(1, tasks(&[1])).unwrap(), tasks(&[1, 2]));
+        assert_eq!(history.redo(1, tasks(&[1, 2])).unwrap(), tasks(&[1, 2, 3]));
+        assert_eq!(
+            history.redo(1, tasks(&[1, 2, 3])).unwrap_err().to_string(),
+            "Invalid input - Nothing to redo"
+        );
+        assert_eq!(history.undo(3, tasks(&[1, 2, 3])).unwrap(), tasks(&[]));
+        assert_eq!(history.redo(3, tasks(&[])).unwrap(), tasks(&[1, 2, 3]));
+    }
+
+    /// Tests that a new change forgets the changes that could be redone.
+    #[test]
+    fn test_record_clears_redo() {
+        let mut history = History::default();
+        history.record(tasks(&[]), DEFAULT_LIMIT);
+        history.undo(1, tasks(&[1])).unwrap();
+        history.record(tasks(&[]), DEFAULT_LIMIT);
+        assert!(history.redo(1, tasks(&[2])).is_err());
+    }
+
+    /// Tests that the oldest changes are forgotten beyond the limit.
+    #[test]
+    fn test_record_limit() {
+        let mut history = History::default();
+        for n in 0..5 {
+            history.record(tasks(&(1..=n).collect::<Vec<_>>()), 3);
+        }
+        assert!(history.undo(4, tasks(&[9])).is_err());
+        assert_eq!(history.undo(3, tasks(&[9])).unwrap(), tasks(&[1, 2]));
+    }
+
+    /// Tests that the history survives a round trip, and that a corrupt file is rejected.
+    #[test]
+    fn test_load_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = History::path(dir.path().join("tasks.json").to_str().unwrap());
+        assert!(path.ends_with("tasks.json.undo"));
+        assert_eq!(History::load(&path).unwrap(), History::default());
+
+        let mut history = History::default();
+        history.record(tasks(&[1, 2]), DEFAULT_LIMIT);
+        history.save(&path).unwrap();
+        assert_eq!(History::load(&path).unwrap(), history);
+
+        std::fs::write(&path, "[{").unwrap();
+        let err = History::load(&path).unwrap_err();
+        assert!(err.to_string().contains("is corrupt"));
+    }
+}
@@ -5,6 +5,8 @@ use tempfile::TempDir;
 fn prepare_cmd(temp_dir: &TempDir) -> Command {
     let mut cmd = Command::cargo_bin("tasg").unwrap();
     cmd.env("TASG_FILE", temp_dir.path().join("tasks.json").to_str().unwrap());
+    cmd.env("TASG_CONFIG", temp_dir.path().join("config.toml").to_str().unwrap());
+    cmd.env("TASG_STATE", temp_dir.path().join("state.json").to_str().unwrap());
     cmd
 }
 
@@ -194,7 +196,7 @@ fn test_nuke_tasks_with_various_confirmation_inputs() {
         "Operation cancelled.",
     ];
 
-    for (input, expected_output) in inputs.into_iter().zip(expected_outputs.into_iter()) {
+    for (input, expected_output) in inputs.into_iter().zip(expected_outputs) {
         let mut cmd = prepare_cmd(&temp_dir);
         let assert = cmd.arg("nuke").write_stdin(input).assert();
 
@@ -256,9 +258,1979 @@ fn test_edit_task_no_description() {
     assert.success().stdout(predicate::str::contains(test_task_description));
 }
 
+#[test]
+fn test_edit_completed_task() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).args(["complete", "1"]).assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["edit", "1", "--description", "Rewritten"]).assert();
+    assert
+        .failure()
+        .stderr(predicate::str::contains("Task 1 is completed"))
+        .stderr(predicate::str::contains("`tasg set 1 completed false`"));
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["do", "edit 1 Rewritten"]).assert().failure();
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["list", "--all"]).assert().success().stdout(predicate::str::contains("Test task"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["edit", "1", "--description", "Rewritten", "--completed-ok"]).assert();
+    assert.success();
+
+    std::fs::write(temp_dir.path().join("config.toml"), "edit_completed = true\n").unwrap();
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["edit", "1", "--description", "Rewritten again"]).assert().success();
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--all"]).assert();
+    assert.success().stdout(predicate::str::contains("Rewritten again"));
+}
+
+#[test]
+fn test_set_completed_task() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).args(["complete", "1"]).assert().success();
+
+    for field in ["description", "notes"] {
+        let mut cmd = prepare_cmd(&temp_dir);
+        let assert = cmd.args(["set", "1", field, "Rewritten"]).assert();
+        assert.failure().stderr(predicate::str::contains("Task 1 is completed"));
+    }
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["get", "1", "description"]).assert().success().stdout("Test task\n");
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["set", "1", "notes", "Rewritten", "--completed-ok"]).assert().success();
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["set", "1", "completed", "false"]).assert().success();
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["set", "1", "description", "Rewritten"]).assert().success();
+}
+
+#[test]
+fn test_edit_interactive() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Buy milk").assert().success();
+    prepare_cmd(&temp_dir).arg("add").arg("Walk the dog").assert().success();
+    prepare_cmd(&temp_dir).args(["set", "2", "notes", "Before dinner"]).assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["edit", "2", "--interactive"]).write_stdin("\n\n\n1\n").assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("description [Walk the dog]: "))
+        .stdout(predicate::str::contains("notes [Before dinner]: "))
+        .stdout(predicate::str::contains("Updated depends_on of task 2"));
+    let task = |field: &str| {
+        let output = prepare_cmd(&temp_dir).args(["get", "2", field]).output().unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(task("description"), "Walk the dog\n");
+    assert_eq!(task("notes"), "Before dinner\n");
+    assert_eq!(task("status"), "todo\n");
+    assert_eq!(task("depends_on"), "1\n");
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["edit", "2", "-i"]).write_stdin("Walk the cat\n").assert();
+    assert.success().stdout(predicate::str::contains("Edit aborted; task 2 was not changed"));
+    assert_eq!(task("description"), "Walk the dog\n");
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["edit", "2", "-i"]).write_stdin("Walk the cat\n\nmaybe\n\n").assert();
+    assert.failure().stderr(predicate::str::contains("Invalid value 'maybe' for status"));
+    assert_eq!(task("description"), "Walk the dog\n");
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["edit", "2", "-i"]).write_stdin("Walk the cat\n\ndone\n9\n").assert();
+    assert
+        .failure()
+        .stderr(predicate::str::contains("Task 2 cannot depend on task 9, which does not exist"));
+    assert_eq!(task("description"), "Walk the dog\n");
+    assert_eq!(task("status"), "todo\n");
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["edit", "2", "-i"]).write_stdin("Walk the cat\n\ndone\n\n").assert();
+    assert.success().stdout(predicate::str::contains("Updated description, status of task 2"));
+    assert_eq!(task("description"), "Walk the cat\n");
+    assert_eq!(task("status"), "done\n");
+}
+
 #[test]
 fn test_edit_non_existent_task() {
     let (mut cmd, _temp_dir) = setup();
     let assert = cmd.arg("edit").arg("9999").assert();
     assert.failure().stderr(predicate::str::contains("Task with ID 9999 not found"));
 }
+
+#[test]
+fn test_diff_against_other_file() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Unchanged task").assert().success();
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.arg("add").arg("Removed task").assert().success();
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.arg("add").arg("Modified task").assert().success();
+
+    // Build the other file from the current tasks: drop task 2, complete task 3, add task 4
+    let data = std::fs::read_to_string(temp_dir.path().join("tasks.json")).unwrap();
+    let mut tasks: Vec<tasg::task::Task> = serde_json::from_str(&data).unwrap();
+    tasks.retain(|t| t.id != 2);
+    tasks[1].status = tasg::task::Status::Done;
+    tasks.push(tasg::task::Task::new(4, String::from("Added task")));
+    let other = temp_dir.path().join("other.json");
+    std::fs::write(&other, serde_json::to_string(&tasks).unwrap()).unwrap();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("diff").arg(other.to_str().unwrap()).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("- [2] Removed task"))
+        .stdout(predicate::str::contains("~ [3] Modified task (status: Todo -> Done)"))
+        .stdout(predicate::str::contains("+ [4] Added task"))
+        .stdout(predicate::str::contains("Unchanged task").not());
+}
+
+#[test]
+fn test_diff_stat_and_json() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Modified task").assert().success();
+
+    let data = std::fs::read_to_string(temp_dir.path().join("tasks.json")).unwrap();
+    let mut tasks: Vec<tasg::task::Task> = serde_json::from_str(&data).unwrap();
+    tasks[0].description = String::from("Renamed task");
+    tasks.push(tasg::task::Task::new(2, String::from("Added task")));
+    let other = temp_dir.path().join("other.json");
+    std::fs::write(&other, serde_json::to_string(&tasks).unwrap()).unwrap();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["diff", "--stat", other.to_str().unwrap()]).assert();
+    assert.success().stdout("1 added, 0 removed, 1 changed\n");
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let output = cmd.args(["diff", "--json", other.to_str().unwrap()]).assert().success();
+    let value: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(value["added"][0]["description"], "Added task");
+    assert_eq!(value["changed"][0]["changes"][0]["field"], "description");
+    assert_eq!(value["changed"][0]["changes"][0]["after"], "Renamed task");
+}
+
+#[test]
+fn test_diff_missing_file() {
+    let (mut cmd, temp_dir) = setup();
+    let missing = temp_dir.path().join("missing.json");
+    let assert = cmd.arg("diff").arg(missing.to_str().unwrap()).assert();
+    assert.failure().stderr(predicate::str::contains("Error: I/O error"));
+}
+
+#[test]
+fn test_doctor_check_timestamps() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    // Hand-edit the file so the task was created in the future
+    let path = temp_dir.path().join("tasks.json");
+    let mut tasks: Vec<tasg::task::Task> =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    tasks[0].created_at += chrono::Duration::days(1);
+    tasks[0].updated_at = tasks[0].created_at;
+    std::fs::write(&path, serde_json::to_string(&tasks).unwrap()).unwrap();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("doctor").arg("--check-timestamps").assert();
+    assert.success().stdout(predicate::str::contains("Task 1: created_at is in the future"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("doctor").arg("--check-timestamps").arg("--fix").assert();
+    assert.success().stdout(predicate::str::contains("Fixed 2 problem(s)"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("doctor").assert();
+    assert.success().stdout(predicate::str::contains("No problems found"));
+}
+
+#[test]
+fn test_future_timestamp_warned_once() {
+    let (mut cmd, temp_dir) = setup();
+    let tomorrow = (chrono::Local::now() + chrono::Duration::days(1)).to_rfc3339();
+    let tasks = serde_json::json!([
+        {"id": 1, "description": "First task", "created_at": tomorrow, "updated_at": tomorrow},
+        {"id": 2, "description": "Second task", "created_at": tomorrow, "updated_at": tomorrow},
+    ]);
+    std::fs::write(temp_dir.path().join("tasks.json"), tasks.to_string()).unwrap();
+
+    let output = cmd.args(["complete", "1", "2"]).output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr.matches("Warning:").count(),
+        1,
+        "expected a single warning, got {:?}",
+        stderr
+    );
+    assert!(stderr.contains("2 task(s) have a timestamp in the future"));
+}
+
+#[test]
+fn test_doctor_check_dependencies() {
+    let (mut cmd, temp_dir) = setup();
+    std::fs::write(
+        temp_dir.path().join("tasks.json"),
+        r#"[
+            {"id": 1, "description": "First task", "depends_on": 2},
+            {"id": 2, "description": "Second task", "depends_on": 1},
+            {"id": 3, "description": "Third task", "depends_on": 9},
+            {"id": 4, "description": "Fourth task", "updated_at": "2999-01-01T00:00:00+00:00"}
+        ]"#,
+    )
+    .unwrap();
+
+    let assert = cmd.args(["doctor", "--check-dependencies"]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains(
+            "Task 1: depends on task 2, which already depends on task 1",
+        ))
+        .stdout(predicate::str::contains("Task 3: depends on task 9, which does not exist"))
+        .stdout(predicate::str::contains("Task 4").not())
+        .stdout(predicate::str::contains("Found 3 problem(s)"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["doctor", "--check-timestamps"]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Task 4: updated_at is in the future"))
+        .stdout(predicate::str::contains("Found 1 problem(s)"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["doctor", "--check-dependencies", "--fix"]).assert();
+    assert.success().stdout(predicate::str::contains("Fixed 2 problem(s)"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["doctor", "--check-dependencies"]).assert();
+    assert.success().stdout(predicate::str::contains("No problems found"));
+}
+
+#[test]
+fn test_tutorial_non_interactive() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.arg("tutorial").arg("--non-interactive").assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Step 1/5: Add a task"))
+        .stdout(predicate::str::contains("$ tasg delete 1"));
+}
+
+#[test]
+fn test_tutorial_interactive_uses_throwaway_store() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Real task").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let script = "tasg add \"Buy milk\"\nlist\nedit 1\nedit 1 --description \"Buy oat milk\"\n\
+                  complete 1\ndelete 1\n";
+    let assert = cmd.arg("tutorial").write_stdin(script).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Hint: Pass an existing ID and a new description"))
+        .stdout(predicate::str::contains("You have completed the tutorial"));
+
+    // The real store is untouched
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("list").assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Real task"))
+        .stdout(predicate::str::contains("Buy milk").not());
+}
+
+#[test]
+fn test_tutorial_refuses_other_commands() {
+    let (mut cmd, temp_dir) = setup();
+    let backup_dir = temp_dir.path().join("backups");
+    cmd.env("TASG_BACKUP_DIR", &backup_dir).env("TASG_BACKUP_KEEP", "1");
+    let script = "backup\ncompletions install bash\n--dry-run add \"Buy milk\"\n";
+    let assert = cmd.arg("tutorial").write_stdin(script).assert();
+    assert.success().stdout(
+        predicate::str::contains(
+            "Only these commands can be run in the tutorial: add, list, edit, complete, delete",
+        )
+        .count(3),
+    );
+    assert!(!backup_dir.exists());
+}
+
+#[test]
+fn test_tutorial_exits_on_eof() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.arg("tutorial").write_stdin("add \"Buy milk\"\n").assert();
+    assert.success().stdout(predicate::str::contains("Tutorial exited."));
+}
+
+#[test]
+fn test_complete_all_except() {
+    let (_, temp_dir) = setup();
+    for description in ["Task 1", "Task 2", "Task 3", "Task 4"] {
+        prepare_cmd(&temp_dir).arg("add").arg(description).assert().success();
+    }
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["complete", "--all", "--except", "2,4", "9"]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Completed 2 task(s)"))
+        .stderr(predicate::str::contains("Warning: task with ID 9 not found"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("list").assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Task 2"))
+        .stdout(predicate::str::contains("Task 4"))
+        .stdout(predicate::str::contains("Task 1").not())
+        .stdout(predicate::str::contains("Task 3").not());
+}
+
+#[test]
+fn test_status() {
+    let (_, temp_dir) = setup();
+    for description in ["Task 1", "Task 2", "Task 3"] {
+        prepare_cmd(&temp_dir).arg("add").arg(description).assert().success();
+    }
+    prepare_cmd(&temp_dir).args(["status", "1", "in-progress"]).assert().success();
+    prepare_cmd(&temp_dir).args(["status", "2", "blocked"]).assert().success();
+    prepare_cmd(&temp_dir).args(["status", "3", "done"]).assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--fields", "id,status"]).assert();
+    assert.success().stdout("ID  Status\n1   In progress\n2   Blocked\n");
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--all", "--json", "--fields", "id,status,completed"]).assert();
+    let output = String::from_utf8(assert.success().get_output().stdout.clone()).unwrap();
+    let tasks: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(tasks[2], serde_json::json!({"id": 3, "status": "done", "completed": true}));
+    prepare_cmd(&temp_dir)
+        .args(["get", "3", "completed_at"])
+        .assert()
+        .stdout(predicate::str::is_empty().not());
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["status", "1", "started"]).assert();
+    assert.failure().stderr(predicate::str::contains("Unknown status 'started'"));
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["status", "9", "done"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Task with ID 9 not found"));
+}
+
+#[test]
+fn test_status_from_older_task_file() {
+    let (_, temp_dir) = setup();
+    let path = temp_dir.path().join("tasks.json");
+    std::fs::write(
+        &path,
+        r#"[{"id": 1, "description": "Open task", "completed": false}, {"id": 2, "description": "Done task", "completed": true}]"#,
+    )
+    .unwrap();
+    prepare_cmd(&temp_dir).args(["get", "2", "status"]).assert().success().stdout("done\n");
+    prepare_cmd(&temp_dir).args(["status", "1", "blocked"]).assert().success();
+
+    let tasks: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!((&tasks[0]["status"], &tasks[0]["completed"]), (&"blocked".into(), &false.into()));
+    assert_eq!((&tasks[1]["status"], &tasks[1]["completed"]), (&"done".into(), &true.into()));
+}
+
+#[test]
+fn test_complete_except_requires_all() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.args(["complete", "1", "--except", "2"]).assert();
+    assert.failure().stderr(predicate::str::contains(
+        "Error: Invalid input - --except can only be used with --all",
+    ));
+}
+
+#[test]
+fn test_read_only_rejects_mutations() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    let path = temp_dir.path().join("tasks.json");
+    let before = std::fs::read_to_string(&path).unwrap();
+
+    let mutations: Vec<Vec<&str>> = vec![
+        vec!["add", "New task"],
+        vec!["complete", "1"],
+        vec!["complete", "--all"],
+        vec!["delete", "1"],
+        vec!["edit", "1", "--description", "Edited"],
+    ];
+    for args in mutations {
+        let mut cmd = prepare_cmd(&temp_dir);
+        let assert = cmd.arg("--read-only").args(&args).assert();
+        assert.failure().stderr(predicate::str::contains("Error: Read-only mode"));
+    }
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.env("TASG_READONLY", "1").arg("nuke").write_stdin("y\n").assert();
+    assert.failure().stderr(predicate::str::contains("Error: Read-only mode"));
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+}
+
+#[test]
+fn test_read_only_allows_reads() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    let path = temp_dir.path().join("tasks.json");
+
+    let reads: Vec<Vec<&str>> = vec![
+        vec!["list"],
+        vec!["list", "--all"],
+        vec!["diff", path.to_str().unwrap()],
+        vec!["doctor"],
+        vec!["tutorial", "--non-interactive"],
+    ];
+    for args in reads {
+        let mut cmd = prepare_cmd(&temp_dir);
+        cmd.env("TASG_READONLY", "1").args(&args).assert().success();
+    }
+}
+
+#[test]
+fn test_read_only_does_not_create_file() {
+    let (mut cmd, temp_dir) = setup();
+    let assert = cmd.arg("--read-only").arg("list").assert();
+    assert.success().stdout(predicate::str::contains("No tasks found"));
+    assert!(!temp_dir.path().join("tasks.json").exists());
+}
+
+#[test]
+fn test_dry_run_leaves_file_unchanged() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    let path = temp_dir.path().join("tasks.json");
+    let before = std::fs::read_to_string(&path).unwrap();
+    let history = temp_dir.path().join("tasks.json.undo");
+    let history_before = std::fs::read_to_string(&history).unwrap();
+
+    let cases = [
+        (vec!["--dry-run", "add", "Another task"], "+ [2] Another task"),
+        (
+            vec!["complete", "1", "--dry-run"],
+            "~ [1] Test task (status: Todo -> Done, completed_at: none -> ",
+        ),
+        (vec!["--dry-run", "delete", "1"], "- [1] Test task"),
+        (vec!["--dry-run", "list"], "Dry run: nothing would change"),
+        (vec!["--dry-run", "undo"], "- [1] Test task"),
+    ];
+    for (args, change) in cases {
+        let mut cmd = prepare_cmd(&temp_dir);
+        let assert = cmd.args(&args).assert();
+        assert.success().stderr(predicate::str::contains(change));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before, "{:?}", args);
+        assert_eq!(std::fs::read_to_string(&history).unwrap(), history_before, "{:?}", args);
+    }
+}
+
+#[test]
+fn test_dry_run_does_not_create_file() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.args(["--dry-run", "add", "Test task"]).assert().success();
+    assert!(!temp_dir.path().join("tasks.json").exists());
+}
+
+#[test]
+fn test_wait_times_out() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["wait", "1", "--timeout", "1"]).assert();
+    assert
+        .code(124)
+        .stderr(predicate::str::contains("Error: Timed out waiting for task with ID 1"));
+}
+
+#[test]
+fn test_wait_completed_task() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("complete").arg("1").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["wait", "1", "--timeout", "1"]).assert();
+    assert.success().stdout(predicate::str::contains("Task 1 completed"));
+}
+
+#[test]
+fn test_wait_until_completed_by_another_process() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    let mut waiter = std::process::Command::new(assert_cmd::cargo::cargo_bin("tasg"))
+        .env("TASG_FILE", temp_dir.path().join("tasks.json"))
+        .args(["wait", "1", "--timeout", "30"])
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    prepare_cmd(&temp_dir).arg("complete").arg("1").assert().success();
+
+    assert!(waiter.wait().unwrap().success());
+}
+
+#[test]
+fn test_wait_non_existent_task() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.args(["wait", "9999"]).assert();
+    assert.code(1).stderr(predicate::str::contains("Task with ID 9999 not found"));
+}
+
+#[test]
+fn test_touch_task() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    let path = temp_dir.path().join("tasks.json");
+    let read = || -> Vec<tasg::task::Task> {
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap()
+    };
+    let before = read();
+
+    prepare_cmd(&temp_dir).arg("touch").arg("1").assert().success();
+
+    let after = read();
+    assert!(after[0].updated_at > before[0].updated_at);
+    assert_eq!(after[0].description, before[0].description);
+    assert_eq!(after[0].created_at, before[0].created_at);
+    assert_eq!(after[0].status, before[0].status);
+}
+
+#[test]
+fn test_touch_non_existent_task() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.arg("touch").arg("9999").assert();
+    assert.failure().stderr(predicate::str::contains("Task with ID 9999 not found"));
+}
+
+#[test]
+fn test_list_utf8_bom_file() {
+    let (mut cmd, temp_dir) = setup();
+    std::fs::copy("tests/fixtures/encoding/utf8_bom.json", temp_dir.path().join("tasks.json"))
+        .unwrap();
+    let assert = cmd.arg("list").assert();
+    assert.success().stdout(predicate::str::contains("Café task"));
+}
+
+#[test]
+fn test_list_unsupported_encoding() {
+    let (mut cmd, temp_dir) = setup();
+    std::fs::copy("tests/fixtures/encoding/latin1.json", temp_dir.path().join("tasks.json"))
+        .unwrap();
+    let assert = cmd.arg("list").assert();
+    assert.failure().stderr(predicate::str::contains("Error: Encoding error"));
+}
+
+#[test]
+fn test_list_with_width() {
+    let (mut cmd, temp_dir) = setup();
+    let long_description = "A very long task description that will not fit in a narrow list";
+    cmd.arg("add").arg(long_description).assert().success();
+
+    let cases = [
+        (vec!["list", "--width", "50"], 50, "A very long task descrip…"),
+        (vec!["list", "-a", "--width", "72"], 72, "A very long task descrip…"),
+    ];
+    for (args, width, truncated) in cases {
+        let mut cmd = prepare_cmd(&temp_dir);
+        let output = cmd.args(&args).assert().success().get_output().stdout.clone();
+        let output = String::from_utf8(output).unwrap();
+        for line in output.lines() {
+            assert!(line.chars().count() <= width, "line {:?} is too wide", line);
+        }
+        assert!(output.contains(truncated));
+        assert!(!output.contains(long_description));
+    }
+}
+
+#[test]
+fn test_list_truncate() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("A very long task description").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--truncate", "12"]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("1   A very long… "))
+        .stdout(predicate::str::contains("A very long t").not());
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--compact", "--truncate", "6"]).assert();
+    assert.success().stdout("#1 [ ] A ver…\n");
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["list", "--truncate", "0"]).assert().failure();
+}
+
+#[test]
+fn test_list_with_width_too_narrow() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.args(["list", "--width", "20"]).assert();
+    assert
+        .failure()
+        .stderr(predicate::str::contains("Error: Invalid input - Width must be at least 35"));
+}
+
+#[test]
+fn test_list_wide_ids() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Buy milk").assert().success();
+
+    let path = temp_dir.path().join("tasks.json");
+    let mut tasks: Vec<tasg::task::Task> =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    tasks[0].id = 123456;
+    std::fs::write(&path, serde_json::to_string(&tasks).unwrap()).unwrap();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--width", "40"]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::starts_with("ID     Description"))
+        .stdout(predicate::str::contains("123456 Buy milk"));
+}
+
+#[test]
+fn test_add_when_id_space_exhausted() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Buy milk").assert().success();
+
+    let path = temp_dir.path().join("tasks.json");
+    let mut tasks: Vec<tasg::task::Task> =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    tasks[0].id = u32::MAX;
+    std::fs::write(&path, serde_json::to_string(&tasks).unwrap()).unwrap();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("add").arg("Walk the dog").assert();
+    assert.failure().stderr(predicate::str::contains(
+        "Error: ID space exhausted - task 4294967295 already exists",
+    ));
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("Walk the dog"));
+}
+
+#[test]
+fn test_delete_moves_task_to_trash() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("delete").arg("1").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("list").arg("--all").assert();
+    assert.success().stdout(predicate::str::contains("No tasks found"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("trash").assert();
+    assert.success().stdout(predicate::str::contains("Test task"));
+
+    // A new task must not reuse the ID of the task in the trash
+    prepare_cmd(&temp_dir).arg("add").arg("Another task").assert().success();
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("list").assert();
+    assert.success().stdout(predicate::str::is_match(r"(?m)^2\s+Another task").unwrap());
+}
+
+#[test]
+fn test_restore_task_from_trash() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("delete").arg("1").assert().success();
+    prepare_cmd(&temp_dir).arg("restore").arg("1").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("list").assert();
+    assert.success().stdout(predicate::str::contains("Test task"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("trash").assert();
+    assert.success().stdout(predicate::str::contains("The trash is empty"));
+}
+
+#[test]
+fn test_restore_task_not_in_trash() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("restore").arg("1").assert();
+    assert.failure().stderr(predicate::str::contains("Task with ID 1 not found"));
+}
+
+#[test]
+fn test_hard_delete_bypasses_trash() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("delete").arg("1").arg("--hard").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("trash").assert();
+    assert.success().stdout(predicate::str::contains("The trash is empty"));
+}
+
+#[test]
+fn test_empty_trash() {
+    let (_, temp_dir) = setup();
+    for description in ["Task 1", "Task 2", "Task 3"] {
+        prepare_cmd(&temp_dir).arg("add").arg(description).assert().success();
+    }
+    prepare_cmd(&temp_dir).arg("delete").arg("1").assert().success();
+    prepare_cmd(&temp_dir).arg("delete").arg("3").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("empty-trash").write_stdin("y\n").assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("permanently delete 2 task(s)"))
+        .stdout(predicate::str::contains("Permanently deleted 2 task(s) from the trash."));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("list").assert();
+    assert.success().stdout(predicate::str::contains("Task 2"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("trash").assert();
+    assert.success().stdout(predicate::str::contains("The trash is empty"));
+}
+
+#[test]
+fn test_empty_trash_cancel() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("delete").arg("1").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("empty-trash").write_stdin("n\n").assert();
+    assert.success().stdout(predicate::str::contains("Operation cancelled."));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("trash").assert();
+    assert.success().stdout(predicate::str::contains("Test task"));
+}
+
+#[test]
+fn test_empty_trash_older_than() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("delete").arg("1").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["empty-trash", "--yes", "--older-than", "30"]).assert();
+    assert.success().stdout(predicate::str::contains("Nothing to delete from the trash"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["empty-trash", "--yes", "--older-than", "4000000000"]).assert();
+    assert
+        .failure()
+        .stderr(predicate::str::contains("The duration given to --older-than is too long"));
+}
+
+#[test]
+fn test_show() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("complete").arg("1").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("show").arg("1").assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Description:  Test task"))
+        .stdout(predicate::str::contains("Completed:    Yes"))
+        .stdout(predicate::str::contains("Completed At: "));
+}
+
+#[test]
+fn test_output_plain() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Buy milk").assert().success();
+    prepare_cmd(&temp_dir).arg("add").arg("Call the bank").assert().success();
+    prepare_cmd(&temp_dir).args(["status", "2", "in-progress"]).assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["--output", "plain", "list"]).assert().success().stdout(concat!(
+        "Task 1. Buy milk. Created just now. Not completed.\n",
+        "Task 2. Call the bank. Created just now. In progress.\n",
+    ));
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["show", "2", "--output", "plain"]).assert().success().stdout(concat!(
+        "Task: 2\n",
+        "Description: Call the bank\n",
+        "Created: just now\n",
+        "Updated: just now\n",
+        "Status: In progress\n",
+    ));
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["count", "--output", "plain"]).assert();
+    assert.success().stdout("2 tasks. 0 completed. 2 pending.\n");
+
+    std::fs::write(temp_dir.path().join("config.toml"), "output = \"plain\"\n").unwrap();
+    prepare_cmd(&temp_dir).args(["delete", "1"]).assert().success();
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("trash").assert();
+    assert.success().stdout("Task 1. Buy milk. Moved to the trash just now. Not completed.\n");
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["--output", "standard", "count"]).assert();
+    assert.success().stdout("1 task(s): 0 completed, 1 pending\n");
+}
+
+#[test]
+fn test_show_not_found() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.arg("show").arg("1").assert();
+    assert.failure().stderr(predicate::str::contains("Task with ID 1 not found"));
+}
+
+#[test]
+fn test_list_with_fields() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let output = cmd.args(["list", "--fields", "description,id"]).assert().success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], format!("{:<50} ID", "Description"));
+    assert_eq!(lines[1], format!("{:<50} 1", "Test task"));
+}
+
+#[test]
+fn test_list_json_with_fields() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let output = cmd.args(["list", "--json", "--fields", "id,completed"]).assert().success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let tasks: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_str(&stdout).unwrap();
+    assert_eq!(tasks.len(), 1);
+    let keys: Vec<&String> = tasks[0].keys().collect();
+    assert_eq!(keys, ["completed", "id"]);
+}
+
+#[test]
+fn test_list_with_unknown_field() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.args(["list", "--fields", "id,due"]).assert();
+    assert.failure().stderr(predicate::str::contains("Unknown field 'due'"));
+}
+
+#[test]
+fn test_add_with_idempotency_key_is_noop_when_repeated() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.args(["add", "Deploy v2", "--idempotency-key", "deploy-v2"]).assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["add", "Deploy v2", "--idempotency-key", "deploy-v2"]).assert();
+    assert.success().stdout("1\n");
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let output = cmd.args(["list", "--json"]).assert().success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let tasks: Vec<serde_json::Value> = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(tasks.len(), 1);
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("show").arg("1").assert();
+    assert.success().stdout(predicate::str::contains("Idempotency Key: deploy-v2"));
+}
+
+#[test]
+fn test_add_with_idempotency_key_conflicting_description() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.args(["add", "Deploy v2", "--idempotency-key", "deploy-v2"]).assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["add", "Deploy v3", "--idempotency-key", "deploy-v2"]).assert();
+    assert.failure().stderr(predicate::str::contains(
+        "Idempotency key 'deploy-v2' is already used by task 1 with a different description",
+    ));
+}
+
+#[test]
+fn test_add_depends_on_last() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.args(["add", "Step 1"]).assert().success();
+    prepare_cmd(&temp_dir).args(["add", "Step 2", "--depends-on-last"]).assert().success();
+    prepare_cmd(&temp_dir).args(["add", "Step 3", "--depends-on-last"]).assert().success();
+
+    let data = std::fs::read_to_string(temp_dir.path().join("tasks.json")).unwrap();
+    let tasks: Vec<tasg::task::Task> = serde_json::from_str(&data).unwrap();
+    let links: Vec<Option<u32>> = tasks.iter().map(|t| t.depends_on).collect();
+    assert_eq!(links, vec![None, Some(1), Some(2)]);
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("show").arg("3").assert();
+    assert.success().stdout(predicate::str::contains("Depends On:  2"));
+}
+
+#[test]
+fn test_add_depends_on_last_without_previous_task() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.args(["add", "Step 1", "--depends-on-last"]).assert();
+    assert.failure().stderr(predicate::str::contains("There is no previous task to depend on"));
+}
+
+#[test]
+fn test_set_fields_by_name() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Step 1").assert().success();
+    prepare_cmd(&temp_dir).arg("add").arg("Step 2").assert().success();
+
+    for (field, value) in
+        [("description", "Second step"), ("completed", "yes"), ("depends_on", "1")]
+    {
+        prepare_cmd(&temp_dir).args(["set", "2", field, value]).assert().success();
+    }
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("show").arg("2").assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Description:  Second step"))
+        .stdout(predicate::str::contains("Completed:    Yes"))
+        .stdout(predicate::str::contains("Depends On:   1"));
+}
+
+#[test]
+fn test_set_unknown_field() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["set", "1", "priority", "high"]).assert();
+    assert.failure().stderr(predicate::str::contains(
+        "Unknown field 'priority'; expected one of: description, notes, status, completed, depends_on",
+    ));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["set", "1", "completed", "maybe"]).assert();
+    assert.failure().stderr(predicate::str::contains("Invalid value 'maybe' for completed"));
+}
+
+#[test]
+fn test_set_depends_on_invalid() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Step 1").assert().success();
+    prepare_cmd(&temp_dir).arg("add").arg("Step 2").assert().success();
+    prepare_cmd(&temp_dir).args(["set", "2", "depends_on", "1"]).assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["set", "1", "depends_on", "2"]).assert();
+    assert.failure().stderr(predicate::str::contains(
+        "Task 1 cannot depend on task 2, which already depends on task 1",
+    ));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["set", "1", "depends_on", "7"]).assert();
+    assert
+        .failure()
+        .stderr(predicate::str::contains("Task 1 cannot depend on task 7, which does not exist"));
+}
+
+#[test]
+fn test_get_fields() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Step 1").assert().success();
+    prepare_cmd(&temp_dir).args(["add", "Step 2", "--depends-on-last"]).assert().success();
+
+    for (field, expected) in [
+        ("id", "2\n"),
+        ("description", "Step 2\n"),
+        ("completed", "false\n"),
+        ("depends_on", "1\n"),
+    ] {
+        let mut cmd = prepare_cmd(&temp_dir);
+        cmd.args(["get", "2", field]).assert().success().stdout(expected);
+    }
+}
+
+#[test]
+fn test_get_errors() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["get", "1", "priority"]).assert();
+    assert.failure().stderr(predicate::str::contains("Unknown field 'priority'"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["get", "2", "description"]).assert();
+    assert.failure().stderr(predicate::str::contains("Task with ID 2 not found"));
+}
+
+#[test]
+fn test_carryover_from_previous_period() {
+    let temp_dir = TempDir::new().unwrap();
+    let template = temp_dir.path().join("%Y-%m-%d.json");
+    let template = template.to_str().unwrap();
+    let today = chrono::Local::now().naive_local();
+    let yesterday = today - chrono::Duration::days(1);
+
+    // Last period: an open task depending on another open task, and a completed task
+    let mut first = tasg::task::Task::new(1, String::from("Open task"));
+    let mut done = tasg::task::Task::new(2, String::from("Done task"));
+    done.status = tasg::task::Status::Done;
+    let mut second = tasg::task::Task::new(3, String::from("Dependent task"));
+    second.depends_on = Some(1);
+    first.created_at -= chrono::Duration::days(1);
+    let previous = yesterday.format(template).to_string();
+    std::fs::write(&previous, serde_json::to_string(&[first, done, second]).unwrap()).unwrap();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("tasg").unwrap();
+        cmd.env("TASG_FILE", template).args(args).assert().success()
+    };
+    run(&["add", "New task"]);
+    run(&["carryover"])
+        .stdout(predicate::str::contains("Carried over task 1 as task 2: Open task"))
+        .stdout(predicate::str::contains("Carried over task 3 as task 3: Dependent task"))
+        .stdout(predicate::str::contains("Carried over 2 task(s)"));
+    run(&["carryover"]).stdout(predicate::str::contains("Carried over 0 task(s)"));
+
+    let current = today.format(template).to_string();
+    let tasks: Vec<tasg::task::Task> =
+        serde_json::from_str(&std::fs::read_to_string(current).unwrap()).unwrap();
+    let summary: Vec<(u32, &str, Option<u32>)> =
+        tasks.iter().map(|t| (t.id, t.description.as_str(), t.depends_on)).collect();
+    assert_eq!(
+        summary,
+        vec![(1, "New task", None), (2, "Open task", None), (3, "Dependent task", Some(2))]
+    );
+}
+
+#[test]
+fn test_carryover_idempotency_key_collision() {
+    let temp_dir = TempDir::new().unwrap();
+    let template = temp_dir.path().join("%Y-%m-%d.json");
+    let template = template.to_str().unwrap();
+    let today = chrono::Local::now().naive_local();
+    let yesterday = today - chrono::Duration::days(1);
+
+    let first = tasg::task::Task::new(1, String::from("Open task"));
+    let mut keyed = tasg::task::Task::new(2, String::from("Keyed task"));
+    keyed.idempotency_key = Some(String::from("weekly-review"));
+    let previous = yesterday.format(template).to_string();
+    std::fs::write(&previous, serde_json::to_string(&[first, keyed]).unwrap()).unwrap();
+
+    let cmd = || {
+        let mut cmd = Command::cargo_bin("tasg").unwrap();
+        cmd.env("TASG_FILE", template);
+        cmd
+    };
+    cmd().args(["add", "Review", "--idempotency-key", "weekly-review"]).assert().success();
+    cmd().arg("carryover").assert().failure().stderr(predicate::str::contains(
+        "Idempotency key 'weekly-review' of task 2 is already used by task 1; nothing was carried over",
+    ));
+
+    let current = today.format(template).to_string();
+    let tasks: Vec<tasg::task::Task> =
+        serde_json::from_str(&std::fs::read_to_string(current).unwrap()).unwrap();
+    assert_eq!(tasks.len(), 1);
+}
+
+#[test]
+fn test_carryover_without_date_in_path() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.arg("carryover").assert();
+    assert.failure().stderr(predicate::str::contains("does not contain a date"));
+}
+
+#[test]
+fn test_backup_keeps_configured_number() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    let backup_dir = temp_dir.path().join("backups");
+
+    for _ in 0..4 {
+        let mut cmd = prepare_cmd(&temp_dir);
+        cmd.env("TASG_BACKUP_KEEP", "2").env("TASG_BACKUP_DIR", &backup_dir);
+        cmd.arg("backup")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Backed up the tasks to"));
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    }
+    assert_eq!(std::fs::read_dir(&backup_dir).unwrap().count(), 2);
+}
+
+#[test]
+fn test_read_only_backup() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    let backup_dir = temp_dir.path().join("backups");
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.env("TASG_BACKUP_DIR", &backup_dir);
+    let assert = cmd.arg("--read-only").arg("backup").assert();
+    assert.failure().stderr(predicate::str::contains("Error: Read-only mode"));
+    assert!(!backup_dir.exists());
+}
+
+#[test]
+fn test_backup_invalid_keep() {
+    let (mut cmd, _temp_dir) = setup();
+    let assert = cmd.env("TASG_BACKUP_KEEP", "none").arg("backup").assert();
+    assert.failure().stderr(predicate::str::contains("TASG_BACKUP_KEEP must be a positive number"));
+}
+
+/// Writes an edited task file for `apply` and returns its path.
+fn write_edits(temp_dir: &TempDir, edits: &str) -> String {
+    let path = temp_dir.path().join("edits.json");
+    std::fs::write(&path, edits).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_apply_changed_fields() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Task 1").assert().success();
+    prepare_cmd(&temp_dir).arg("add").arg("Task 2").assert().success();
+    let edits = write_edits(
+        &temp_dir,
+        r#"[{"id": 1, "completed": true}, {"id": 5, "description": "New"}]"#,
+    );
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["apply", &edits]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains(
+            "~ [1] Task 1 (status: Todo -> Done, completed_at: none -> ",
+        ))
+        .stdout(predicate::str::contains("Applied 1 change(s)"))
+        .stderr(predicate::str::contains("skipping 1 new task(s)"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["get", "1", "completed"]).assert().success().stdout("true\n");
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["get", "2", "description"]).assert().success().stdout("Task 2\n");
+}
+
+#[test]
+fn test_apply_notes_only() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Task 1").assert().success();
+    let edits = write_edits(&temp_dir, r#"[{"id": 1, "notes": "Ask about the deadline"}]"#);
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["apply", &edits]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains(
+            r#"~ [1] Task 1 (notes: none -> "Ask about the deadline")"#,
+        ))
+        .stdout(predicate::str::contains("Applied 1 change(s)"));
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["get", "1", "notes"]).assert().success().stdout("Ask about the deadline\n");
+}
+
+#[test]
+fn test_apply_completed_task() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Task 1").assert().success();
+    prepare_cmd(&temp_dir).args(["complete", "1"]).assert().success();
+    let edits = write_edits(&temp_dir, r#"[{"id": 1, "description": "Rewritten"}]"#);
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["apply", &edits]).assert();
+    assert.failure().stderr(predicate::str::contains("Task 1 is completed"));
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["get", "1", "description"]).assert().success().stdout("Task 1\n");
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["apply", &edits, "--completed-ok"]).assert();
+    assert.success().stdout(predicate::str::contains("Applied 1 change(s)"));
+}
+
+#[test]
+fn test_dry_run_set_notes() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Task 1").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["--dry-run", "set", "1", "notes", "hello"]).assert();
+    assert.success().stderr(concat!(
+        "Dry run: these changes were not saved:\n",
+        "~ [1] Task 1 (notes: none -> \"hello\")\n",
+    ));
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["get", "1", "notes"]).assert().success().stdout("\n");
+}
+
+#[test]
+fn test_apply_allow_new_and_prune_missing() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Task 1").assert().success();
+    prepare_cmd(&temp_dir).arg("add").arg("Task 2").assert().success();
+    let edits = write_edits(&temp_dir, r#"[{"id": 2}, {"description": "New task"}]"#);
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["apply", &edits, "--allow-new", "--prune-missing"]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("- [1] Task 1"))
+        .stdout(predicate::str::contains("+ [3] New task"))
+        .stdout(predicate::str::contains("Applied 2 change(s)"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.arg("trash").assert().success().stdout(predicate::str::contains("Task 1"));
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.arg("list").assert().success().stdout(predicate::str::contains("New task"));
+}
+
+#[test]
+fn test_apply_dry_run() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Task 1").assert().success();
+    let edits = write_edits(&temp_dir, r#"[{"id": 1, "description": "Renamed"}]"#);
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["--dry-run", "apply", &edits]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains(r#"~ [1] Task 1 (description: "Task 1" -> "Renamed")"#))
+        .stderr(concat!(
+            "Dry run: these changes were not saved:\n",
+            "~ [1] Task 1 (description: \"Task 1\" -> \"Renamed\")\n",
+        ));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["get", "1", "description"]).assert().success().stdout("Task 1\n");
+}
+
+#[test]
+fn test_apply_without_changes() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Task 1").assert().success();
+    let output = prepare_cmd(&temp_dir).args(["list", "--json"]).assert().success();
+    let exported = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let edits = write_edits(&temp_dir, &exported);
+    let before = std::fs::read_to_string(temp_dir.path().join("tasks.json")).unwrap();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["apply", &edits, "--prune-missing"]).assert();
+    assert.success().stdout("No changes to apply\n");
+    assert_eq!(std::fs::read_to_string(temp_dir.path().join("tasks.json")).unwrap(), before);
+}
+
+#[test]
+fn test_pasted_ids() {
+    let (_, temp_dir) = setup();
+    for description in ["Task 1", "Task 2", "Task 3", "Task 4", "Task 5"] {
+        prepare_cmd(&temp_dir).arg("add").arg(description).assert().success();
+    }
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["complete", "#1, 3-4"]).assert().success().stdout("Completed 3 task(s)\n");
+    prepare_cmd(&temp_dir).args(["delete", "#2.", "5"]).assert().success();
+    prepare_cmd(&temp_dir).args(["show", "#3"]).assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--all"]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Task 1"))
+        .stdout(predicate::str::contains("Task 2").not())
+        .stdout(predicate::str::contains("Task 5").not())
+        .stdout(predicate::str::contains("No").not());
+}
+
+#[test]
+fn test_invalid_id_token() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["complete", "1, x2"]).assert();
+    assert.failure().stderr(predicate::str::contains("Invalid task ID 'x2'"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["show", "#one"]).assert();
+    assert.failure().stderr(predicate::str::contains("Invalid task ID '#one'"));
+}
+
+#[test]
+fn test_delete_many_is_all_or_nothing() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["delete", "1", "2"]).assert();
+    assert.failure().stderr(predicate::str::contains("Task with ID 2 not found"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.arg("list").assert().success().stdout(predicate::str::contains("Test task"));
+}
+
+#[test]
+fn test_list_all_hide_completed_column() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("complete").arg("1").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--all", "--hide-completed-column"]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Test task"))
+        .stdout(predicate::str::contains("Created At"))
+        .stdout(predicate::str::contains("Completed").not())
+        .stdout(predicate::str::contains("Yes").not());
+}
+
+#[test]
+fn test_list_highlight_changed() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--highlight-changed", "1d"]).assert();
+    assert.success().stdout(predicate::str::contains("* 1   Test task"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--highlight-changed", "soon"]).assert();
+    assert.failure().stderr(predicate::str::contains("Invalid duration 'soon'"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--highlight-changed", "1000000000d"]).assert();
+    assert
+        .failure()
+        .stderr(predicate::str::contains("The duration given to --highlight-changed is too long"));
+}
+
+#[test]
+fn test_list_completed_between() {
+    let (mut cmd, temp_dir) = setup();
+    let file_path = temp_dir.path().join("tasks.json");
+    std::fs::write(
+        &file_path,
+        r#"[
+            {"id": 1, "description": "June task", "completed": true, "completed_at": "2024-06-30T12:00:00+00:00"},
+            {"id": 2, "description": "July task", "completed": true, "completed_at": "2024-07-15T12:00:00+00:00"},
+            {"id": 3, "description": "Legacy task", "completed": true},
+            {"id": 4, "description": "Open task"}
+        ]"#,
+    )
+    .unwrap();
+
+    let assert = cmd.args(["list", "--completed-between", "2024-07-01", "2024-07-31"]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("July task"))
+        .stdout(predicate::str::contains("June task").not())
+        .stdout(predicate::str::contains("Legacy task").not())
+        .stdout(predicate::str::contains("Open task").not());
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--completed-between", "2024-08-01", "2024-08-31"]).assert();
+    assert.success().stdout(predicate::str::contains("No tasks found"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--completed-between", "2024-07-31", "2024-07-01"]).assert();
+    assert
+        .failure()
+        .stderr(predicate::str::contains("Start date 2024-07-31 is after end date 2024-07-01"));
+}
+
+#[test]
+fn test_list_changed_since_field() {
+    let (mut cmd, temp_dir) = setup();
+    let file_path = temp_dir.path().join("tasks.json");
+    std::fs::write(
+        &file_path,
+        r#"[
+            {"id": 1, "description": "Annotated task", "created_at": "2024-07-01T12:00:00+00:00", "updated_at": "2024-07-01T12:00:00+00:00"},
+            {"id": 2, "description": "Rename me", "created_at": "2024-07-01T12:00:00+00:00", "updated_at": "2024-07-01T12:00:00+00:00"},
+            {"id": 3, "description": "Finish me", "created_at": "2024-07-01T12:00:00+00:00", "updated_at": "2024-07-01T12:00:00+00:00"}
+        ]"#,
+    )
+    .unwrap();
+    cmd.args(["edit", "2", "--description", "Renamed task"]).assert().success();
+    prepare_cmd(&temp_dir).args(["complete", "3"]).assert().success();
+    prepare_cmd(&temp_dir)
+        .args(["set", "1", "notes", "Not a description change"])
+        .assert()
+        .success();
+
+    let list = |args: &[&str]| {
+        let output = prepare_cmd(&temp_dir).arg("list").args(args).output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let shown = |output: &str| {
+        ["Annotated task", "Renamed task", "Finish me"]
+            .into_iter()
+            .filter(|d| output.contains(d))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        shown(&list(&["--all", "--changed-since", "1d"])),
+        vec!["Annotated task", "Renamed task"]
+    );
+    assert_eq!(
+        shown(&list(&["--all", "--changed-since", "1d", "--field", "description"])),
+        vec!["Renamed task"]
+    );
+    assert_eq!(shown(&list(&["--changed-since", "1d", "--field", "completed"])), vec!["Finish me"]);
+    assert!(
+        list(&["--changed-since", "1d", "--field", "completed", "--json"]).contains("\"id\": 3")
+    );
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["list", "--field", "description"]).assert();
+    assert.failure().stderr(predicate::str::contains("--changed-since"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["get", "2", "description_updated_at"]).assert();
+    assert.success().stdout(predicate::str::is_match(r"^\d{4}-\d{2}-\d{2}T").unwrap());
+}
+
+#[test]
+fn test_epoch_timestamp_format() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.env("TASG_TIMESTAMP_FORMAT", "epoch").arg("add").arg("Test task").assert().success();
+
+    let data = std::fs::read_to_string(temp_dir.path().join("tasks.json")).unwrap();
+    let tasks: serde_json::Value = serde_json::from_str(&data).unwrap();
+    assert!(tasks[0]["created_at"].is_i64());
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["get", "1", "created_at"]).assert();
+    assert.success().stdout(predicate::str::is_match(r"^\d{4}-\d{2}-\d{2}T").unwrap());
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.env("TASG_TIMESTAMP_FORMAT", "unix").arg("list").assert();
+    assert
+        .failure()
+        .stderr(predicate::str::contains("TASG_TIMESTAMP_FORMAT must be rfc3339 or epoch"));
+}
+
+#[test]
+fn test_complete_comma_list_reports_not_found() {
+    let (_, temp_dir) = setup();
+    for description in ["Task 1", "Task 2", "Task 3", "Task 4"] {
+        prepare_cmd(&temp_dir).arg("add").arg(description).assert().success();
+    }
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["complete", "1,3-4,3,8-9"]).assert();
+    assert
+        .success()
+        .stdout("Completed 3 task(s)\n")
+        .stderr(predicate::str::contains("Warning: 2 task(s) not found: 8, 9"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("list").assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Task 2"))
+        .stdout(predicate::str::contains("Task 1").not())
+        .stdout(predicate::str::contains("Task 4").not());
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["complete", "7,8-9"]).assert();
+    assert.failure().stderr(predicate::str::contains("Task with ID 7 not found"));
+}
+
+#[test]
+fn test_do_instructions() {
+    let (_, temp_dir) = setup();
+    for (instruction, output) in [
+        ("add buy milk", "added 1\n"),
+        ("add 'walk the dog'", "added 2\n"),
+        ("edit 2 walk the cat", "edited 2\n"),
+        ("complete #1", "completed 1\n"),
+        ("get 1 completed", "true\n"),
+        ("delete 2", "trashed 2\n"),
+        ("restore 2", "restored 2\n"),
+        ("touch 2", "touched 2\n"),
+    ] {
+        let mut cmd = prepare_cmd(&temp_dir);
+        cmd.args(["do", instruction]).assert().success().stdout(output);
+    }
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["show", "2"]).assert();
+    assert.success().stdout(predicate::str::contains("walk the cat"));
+}
+
+#[test]
+fn test_do_refuses_unknown_and_confirming_verbs() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["do", "snooze 1 until friday"]).assert();
+    assert.failure().stderr(predicate::str::contains(
+        "Unknown verb 'snooze'; expected one of: add, complete, delete, restore, edit, touch, get",
+    ));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["do", "nuke"]).assert();
+    assert.failure().stderr(predicate::str::contains("asks for confirmation"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["do", "complete 9"]).assert().failure();
+    prepare_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Test task"));
+}
+
+#[test]
+fn test_config_defaults() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("complete").arg("1").assert().success();
+    std::fs::write(
+        temp_dir.path().join("config.toml"),
+        "list_all = true\ndate_format = \"%d/%m/%Y\"\nunknown_key = 1\n",
+    )
+    .unwrap();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("list").assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Test task"))
+        .stdout(predicate::str::contains("Completed"))
+        .stdout(predicate::str::is_match(r"\d{2}/\d{2}/\d{4}").unwrap())
+        .stderr(predicate::str::contains("Warning: unknown key 'unknown_key'"));
+
+    std::fs::write(temp_dir.path().join("config.toml"), "list_all = \"yes\"\n").unwrap();
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.arg("list").assert().failure().stderr(predicate::str::contains("Invalid config file"));
+}
+
+#[test]
+fn test_config_tasks_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let tasks_file = temp_dir.path().join("elsewhere").join("tasks.json");
+    let config = format!("tasks_file = {:?}\n", tasks_file.to_str().unwrap());
+    std::fs::write(temp_dir.path().join("config.toml"), config).unwrap();
+    let cmd = || {
+        let mut cmd = prepare_cmd(&temp_dir);
+        cmd.env_remove("TASG_FILE");
+        cmd
+    };
+
+    cmd().arg("add").arg("Test task").assert().success();
+    assert!(std::fs::read_to_string(&tasks_file).unwrap().contains("Test task"));
+    cmd().arg("list").assert().success().stdout(predicate::str::contains("Test task"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("list").assert();
+    assert.success().stdout(predicate::str::contains("No tasks found"));
+}
+
+#[test]
+fn test_show_json_and_width() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Renew the passport before the summer holidays start").assert().success();
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let output = cmd.args(["show", "1", "--json"]).output().unwrap();
+    assert!(output.status.success());
+    let task: tasg::task::Task = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(task.description, "Renew the passport before the summer holidays start");
+    let object: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(object.get("deleted_at").is_none());
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["show", "1", "--width", "40"]).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Description: Renew the passport before\n"))
+        .stdout(predicate::str::contains("\n             the summer holidays start\n"));
+}
+
+#[test]
+fn test_help_topics() {
+    let (mut cmd, temp_dir) = setup();
+    let assert = cmd.args(["help", "durations"]).assert();
+    assert.success().stdout(
+        "A duration is a number followed by a unit, e.g. `30m` or `2w`. Several may be\n\
+         combined, e.g. `1d12h`.\n\
+         \n\
+         Units:\n  s  seconds\n  m  minutes\n  h  hours\n  d  days\n  w  weeks\n",
+    );
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.arg("help").assert();
+    assert.success().stdout(predicate::str::contains("Help topics (tasg help <topic>):"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["help", "add"]).assert();
+    assert.success().stdout(predicate::str::contains("Usage: tasg add"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["help", "filters"]).assert();
+    assert.failure().stderr(predicate::str::contains("Unknown help topic or command 'filters'"));
+}
+
+#[test]
+fn test_completions_install() {
+    let (mut cmd, temp_dir) = setup();
+    let path = temp_dir.path().join("completions").join("tasg.bash");
+    let assert = cmd.args(["completions", "install", "bash", "--path"]).arg(&path).assert();
+    assert.success().stdout(predicate::str::contains(format!(
+        "Installed bash completions to {}",
+        path.display()
+    )));
+
+    let installed = std::fs::read_to_string(&path).unwrap();
+    let output = prepare_cmd(&temp_dir).args(["completions", "generate", "bash"]).output().unwrap();
+    assert_eq!(installed, String::from_utf8(output.stdout).unwrap());
+    assert!(installed.contains("complete -F _tasg"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    let assert = cmd.args(["completions", "install", "powershell"]).assert();
+    assert.failure().stderr(predicate::str::contains("pass --path to choose one"));
+
+    let mut cmd = prepare_cmd(&temp_dir);
+    cmd.args(["completions", "install", "tcsh"]).assert().failure();
+}
+
+#[test]
+fn test_list_since_last_run() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Seen task").assert().success();
+
+    prepare_cmd(&temp_dir)
+        .arg("list")
+        .arg("--since-last-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Seen task"));
+
+    prepare_cmd(&temp_dir).arg("add").arg("New task").assert().success();
+
+    prepare_cmd(&temp_dir)
+        .arg("list")
+        .arg("--since-last-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("New task"))
+        .stdout(predicate::str::contains("Seen task").not());
+
+    prepare_cmd(&temp_dir)
+        .arg("list")
+        .arg("--since-last-run")
+        .assert()
+        .success()
+        .stdout("No tasks found; 2 hidden: 2 unchanged since the last run\n");
+
+    prepare_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Seen task"))
+        .stdout(predicate::str::contains("New task"));
+}
+
+#[test]
+fn test_list_since_last_run_read_only() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+
+    prepare_cmd(&temp_dir).arg("--read-only").arg("list").assert().success();
+    assert!(!temp_dir.path().join("state.json").exists());
+}
+
+#[test]
+fn test_list_compact() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Buy milk").assert().success();
+    prepare_cmd(&temp_dir).arg("add").arg("Walk the dog").assert().success();
+    prepare_cmd(&temp_dir).arg("complete").arg("2").assert().success();
+
+    prepare_cmd(&temp_dir)
+        .arg("list")
+        .arg("--all")
+        .arg("--compact")
+        .assert()
+        .success()
+        .stdout("#1 [ ] Buy milk\n#2 [x] Walk the dog\n");
+
+    prepare_cmd(&temp_dir).arg("list").arg("--compact").arg("--json").assert().failure();
+}
+
+#[test]
+fn test_add_stdin_description() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add")
+        .arg("--stdin-description")
+        .write_stdin("Fix \"quoted\" $HOME bug  \n\nSteps: run `tasg`\nthen check; | & *  \n\n")
+        .assert()
+        .success();
+
+    prepare_cmd(&temp_dir)
+        .arg("get")
+        .arg("1")
+        .arg("description")
+        .assert()
+        .success()
+        .stdout("Fix \"quoted\" $HOME bug\n");
+
+    prepare_cmd(&temp_dir)
+        .arg("get")
+        .arg("1")
+        .arg("notes")
+        .assert()
+        .success()
+        .stdout("Steps: run `tasg`\nthen check; | & *\n");
+
+    prepare_cmd(&temp_dir).arg("show").arg("1").assert().success().stdout(
+        predicate::str::contains(
+            "Notes:       Steps: run `tasg`\n             then check; | & *\n",
+        ),
+    );
+}
+
+#[test]
+fn test_add_stdin_description_empty() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add")
+        .arg("--stdin-description")
+        .write_stdin("  \n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No description given on standard input"));
+
+    prepare_cmd(&temp_dir).arg("add").arg("Task").arg("--stdin-description").assert().failure();
+
+    prepare_cmd(&temp_dir)
+        .arg("add")
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("A description is required"));
+}
+
+#[test]
+fn test_undo_steps() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("First task").assert().success();
+    prepare_cmd(&temp_dir).arg("add").arg("Second task").assert().success();
+    prepare_cmd(&temp_dir).arg("complete").arg("1").assert().success();
+    prepare_cmd(&temp_dir).arg("delete").arg("2").assert().success();
+    prepare_cmd(&temp_dir).arg("list").assert().success();
+
+    prepare_cmd(&temp_dir).arg("undo").arg("--steps").arg("2").assert().success().stdout(
+        predicate::str::is_match(concat!(
+            r"^Undid 2 change\(s\)\n",
+            r"~ \[1\] First task \(status: Done -> Todo, completed_at: \S+ -> none\)\n",
+            r"\+ \[2\] Second task\n$",
+        ))
+        .unwrap(),
+    );
+
+    prepare_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("First task"))
+        .stdout(predicate::str::contains("Second task"));
+
+    prepare_cmd(&temp_dir).arg("undo").assert().success();
+    prepare_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("First task"))
+        .stdout(predicate::str::contains("Second task").not());
+
+    prepare_cmd(&temp_dir)
+        .arg("undo")
+        .arg("--steps")
+        .arg("2")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Only 1 change(s) can be undone, not 2"));
+}
+
+#[test]
+fn test_undo_corrupt_history() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    let history = temp_dir.path().join("tasks.json.undo");
+    std::fs::write(&history, "[{").unwrap();
+
+    prepare_cmd(&temp_dir)
+        .arg("undo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is corrupt"));
+
+    prepare_cmd(&temp_dir)
+        .arg("add")
+        .arg("Another task")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("starting a new one"));
+    prepare_cmd(&temp_dir).arg("undo").assert().success();
+    prepare_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Another task").not());
+}
+
+#[test]
+fn test_redo() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("complete").arg("1").assert().success();
+    let completed = std::fs::read_to_string(temp_dir.path().join("tasks.json")).unwrap();
+
+    prepare_cmd(&temp_dir).arg("undo").assert().success();
+    prepare_cmd(&temp_dir).arg("get").arg("1").arg("completed").assert().stdout("false\n");
+
+    let assert = prepare_cmd(&temp_dir).arg("redo").assert();
+    assert.success().stdout(
+        predicate::str::is_match(
+            r"^Redid 1 change\(s\)\n~ \[1\] Test task \(status: Todo -> Done, completed_at: none -> \S+\)\n$",
+        )
+        .unwrap(),
+    );
+    prepare_cmd(&temp_dir).arg("get").arg("1").arg("completed").assert().stdout("true\n");
+    let redone = std::fs::read_to_string(temp_dir.path().join("tasks.json")).unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&redone).unwrap(),
+        serde_json::from_str::<serde_json::Value>(&completed).unwrap()
+    );
+
+    prepare_cmd(&temp_dir)
+        .arg("redo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to redo"));
+}
+
+#[test]
+fn test_redo_cleared_by_edit() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Test task").assert().success();
+    prepare_cmd(&temp_dir).arg("complete").arg("1").assert().success();
+    prepare_cmd(&temp_dir).arg("undo").assert().success();
+
+    prepare_cmd(&temp_dir).args(["edit", "1", "--description", "Renamed task"]).assert().success();
+    prepare_cmd(&temp_dir)
+        .arg("redo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to redo"));
+    prepare_cmd(&temp_dir).arg("get").arg("1").arg("completed").assert().stdout("false\n");
+}
+
+#[test]
+fn test_list_borders() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("add").arg("Buy milk").assert().success();
+
+    prepare_cmd(&temp_dir)
+        .args(["list", "--borders", "--fields", "id,description"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("+-----+-"))
+        .stdout(predicate::str::contains("| 1   | Buy milk "));
+
+    prepare_cmd(&temp_dir).args(["list", "--borders", "--json"]).assert().failure();
+}
+
+#[test]
+fn test_list_empty_reasons() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("list").assert().success().stdout("No tasks found\n");
+
+    prepare_cmd(&temp_dir).arg("add").arg("First task").assert().success();
+    prepare_cmd(&temp_dir).arg("add").arg("Second task").assert().success();
+    prepare_cmd(&temp_dir).args(["complete", "1", "2"]).assert().success();
+
+    prepare_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout("No tasks found; 2 hidden: 2 completed\nUse --all to show completed tasks\n");
+
+    prepare_cmd(&temp_dir)
+        .args(["list", "--completed-between", "2000-01-01", "2000-12-31"])
+        .assert()
+        .success()
+        .stdout("No tasks found; 2 hidden: 2 not completed between 2000-01-01 and 2000-12-31\n");
+
+    prepare_cmd(&temp_dir).args(["list", "--json"]).assert().success().stdout("[]\n");
+}
+
+#[test]
+fn test_count() {
+    let (mut cmd, temp_dir) = setup();
+    cmd.arg("count").assert().success().stdout("0 task(s): 0 completed, 0 pending\n");
+
+    for description in ["First task", "Second task", "Third task"] {
+        prepare_cmd(&temp_dir).arg("add").arg(description).assert().success();
+    }
+    prepare_cmd(&temp_dir).arg("complete").arg("1").assert().success();
+    prepare_cmd(&temp_dir)
+        .arg("count")
+        .assert()
+        .success()
+        .stdout("3 task(s): 1 completed, 2 pending\n");
+
+    let output = prepare_cmd(&temp_dir).arg("count").arg("--json").output().unwrap();
+    assert!(output.status.success());
+    let counts: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(counts["total"], 3);
+    assert_eq!(counts["completed"], 1);
+    assert_eq!(counts["pending"], 2);
+    assert_eq!(
+        counts["total"].as_u64(),
+        Some(counts["completed"].as_u64().unwrap() + counts["pending"].as_u64().unwrap())
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_save_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (mut cmd, temp_dir) = setup();
+    let tasks_file = temp_dir.path().join("tasks.json");
+    std::fs::write(&tasks_file, "[]").unwrap();
+    std::fs::set_permissions(&tasks_file, std::fs::Permissions::from_mode(0o600)).unwrap();
+    cmd.arg("add").arg("First task").assert().success();
+    prepare_cmd(&temp_dir).arg("add").arg("Second task").assert().success();
+
+    let mode = |name: &str| {
+        std::fs::metadata(temp_dir.path().join(name)).unwrap().permissions().mode() & 0o777
+    };
+    assert_eq!(mode("tasks.json"), 0o600);
+    assert_eq!(mode("tasks.json.undo"), 0o600);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_preserve_symlinks_config() {
+    let (mut cmd, temp_dir) = setup();
+    let target = temp_dir.path().join("target.json");
+    let link = temp_dir.path().join("tasks.json");
+    std::fs::write(&target, "[]").unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+    std::fs::write(temp_dir.path().join("config.toml"), "preserve_symlinks = false\n").unwrap();
+
+    cmd.arg("add").arg("First task").assert().success();
+    assert!(!link.is_symlink());
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "[]");
+}